@@ -0,0 +1,55 @@
+//! Starts the server as a child process and talks to it with the typed client.
+//!
+//! ```sh
+//! cargo build
+//! cargo run -p cat-mcp-core --example client --features client -- target/debug/mcp-server-rust
+//! ```
+//!
+//! The server binary defaults to `target/debug/mcp-server-rust`; pass another
+//! path as the first argument.
+
+use cat_mcp_core::client::CatClient;
+use serde_json::json;
+
+const DEFAULT_SERVER: &str = "target/debug/mcp-server-rust";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let server = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_SERVER.to_string());
+    let client = CatClient::spawn(&server).await?;
+
+    let cats = client.list_cats().await?;
+    println!("{} cats:", cats.len());
+    for cat in &cats {
+        println!("  #{} {} ({}, {} years)", cat.id, cat.name, cat.breed, cat.age);
+    }
+
+    if let Some(cat) = client.get_cat(2).await? {
+        println!("Cat 2 is {}, who likes their {}", cat.name, cat.favorite_toy);
+    }
+    let persians = client.search_cats("Persian").await?;
+    println!("{} Persian cats", persians.len());
+
+    let added = client.add_cat("Momo", 1, "Scottish Fold", "Gray").await?;
+    println!("Added {} as #{} (version {})", added.name, added.id, added.version);
+
+    // Tools without a typed method go through `call`.
+    let result = client
+        .call(
+            "log_incident",
+            json!({
+                "cat_id": added.id,
+                "type": "scratching",
+                "severity": "low",
+                "description": "Scratched the sofa on day one",
+            }),
+        )
+        .await?;
+    if let Some(text) = result.content.iter().find_map(|content| content.as_text()) {
+        println!("{}", text.text.lines().next().unwrap_or_default());
+    }
+    let incidents = client.list_incidents(Some(added.id)).await?;
+    println!("{} has {} incident(s)", added.name, incidents.len());
+
+    client.close().await
+}
@@ -0,0 +1,83 @@
+//! Adds a record type through the extension API and drives its generated tools
+//! the way the server does when a client calls them.
+//!
+//! ```sh
+//! cargo run -p cat-mcp-core --example custom_entity
+//! ```
+//!
+//! A fork serving dogs alongside cats registers the same [`EntityTools`] in
+//! `CatServer::new()`; the tool names, schemas and results are what its
+//! clients then see.
+
+use cat_mcp_core::entity::{EntityTools, Toolset};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dog {
+    pub id: u32,
+    pub name: String,
+    pub breed: String,
+    pub age: u32,
+}
+
+cat_mcp_core::entity_tools!(Dog {
+    name: "dog",
+    plural: "dogs",
+    search: [name, breed],
+    fields: { name: "string", breed: "string", age: "number" },
+});
+
+fn arguments(value: Value) -> Option<Map<String, Value>> {
+    match value {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+/// Calls `tool` and prints its text, as a client would see it.
+async fn call(dogs: &dyn Toolset, tool: &str, args: Value) -> anyhow::Result<()> {
+    let args = arguments(args);
+    let result = dogs
+        .call(tool, args.as_ref())
+        .await
+        .ok_or_else(|| anyhow::anyhow!("{} is not a dog tool", tool))?;
+    match result {
+        Ok(contents) => {
+            println!("> {}", tool);
+            for content in contents {
+                if let Some(text) = content.as_text() {
+                    println!("{}", text.text);
+                }
+            }
+        }
+        Err(e) => println!("> {} failed: {}", tool, e.message),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let dogs: Box<dyn Toolset> = Box::new(EntityTools::new(vec![Dog {
+        id: 1,
+        name: "Pochi".to_string(),
+        breed: "Shiba Inu".to_string(),
+        age: 4,
+    }]));
+
+    println!("Tools:");
+    for tool in dogs.tools() {
+        println!("  {} - {}", tool.name, tool.description.as_deref().unwrap_or_default());
+    }
+
+    call(dogs.as_ref(), "add_dog", json!({ "name": "Hachi", "breed": "Akita", "age": 2 })).await?;
+    call(dogs.as_ref(), "update_dog", json!({ "id": 2, "age": 3 })).await?;
+    call(dogs.as_ref(), "search_dogs", json!({ "query": "shiba" })).await?;
+    call(dogs.as_ref(), "update_dog", json!({ "id": 2, "weight": 30 })).await?;
+    call(dogs.as_ref(), "delete_dog", json!({ "id": 1 })).await?;
+    call(dogs.as_ref(), "list_dogs", json!({})).await?;
+
+    // The server asks every toolset in turn; `None` means "not mine".
+    assert!(dogs.call("list_all_cats", None).await.is_none());
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! A small MCP server over HTTP that only answers clients presenting a bearer
+//! token, built on the library's [`CatRepository`].
+//!
+//! ```sh
+//! CAT_EXAMPLE_TOKEN=secret cargo run -p cat-mcp-core --example http_server
+//! ```
+//!
+//! Clients open `GET /sse` and post to `/message` on `127.0.0.1:8000` (or
+//! `CAT_EXAMPLE_ADDR`), sending `Authorization: Bearer secret` with both;
+//! anything else gets `401 Unauthorized`. The full server's `--http` transport
+//! has no authentication of its own, so this is also the pattern for putting
+//! it behind a token.
+
+use axum::extract::Request;
+use axum::http::{StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use cat_mcp_core::Cat;
+use cat_mcp_core::repository::{CatRepository, InMemoryCatRepository, RepositoryError};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, Content, ErrorCode, ListToolsResult, PaginatedRequestParam, ServerCapabilities,
+    ServerInfo, Tool,
+};
+use rmcp::service::RequestContext;
+use rmcp::transport::sse_server::{SseServer, SseServerConfig};
+use rmcp::{ErrorData, RoleServer, ServerHandler};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8000";
+
+#[derive(Clone)]
+struct CatsOverHttp {
+    cats: Arc<InMemoryCatRepository>,
+}
+
+fn invalid(message: String) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn unavailable(e: RepositoryError) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!("Could not read the cats: {}", e).into(),
+        data: None,
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, ErrorData> {
+    serde_json::to_string(value).map_err(|e| ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!("Serialization error: {}", e).into(),
+        data: None,
+    })
+}
+
+impl ServerHandler for CatsOverHttp {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some("Read-only cat lookups over authenticated HTTP.".to_string()),
+            ..Default::default()
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        Ok(ListToolsResult {
+            tools: vec![
+                Tool {
+                    name: "list_all_cats".into(),
+                    description: Some("Get a list of all cats".into()),
+                    input_schema: {
+                        let mut map = serde_json::Map::new();
+                        map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                        map.insert("properties".to_string(), serde_json::json!({}));
+                        Arc::new(map)
+                    },
+                    annotations: None,
+                },
+                Tool {
+                    name: "get_cat_by_id".into(),
+                    description: Some("Get a specific cat by ID".into()),
+                    input_schema: {
+                        let mut map = serde_json::Map::new();
+                        map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                        map.insert("properties".to_string(), serde_json::json!({
+                            "id": {
+                                "type": "number",
+                                "description": "ID of the cat"
+                            }
+                        }));
+                        map.insert("required".to_string(), serde_json::json!(["id"]));
+                        Arc::new(map)
+                    },
+                    annotations: None,
+                },
+            ],
+            next_cursor: None,
+        })
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let content = match request.name.as_ref() {
+            "list_all_cats" => {
+                let cats = self.cats.list().await.map_err(unavailable)?;
+                format!("All cats ({}):\n{}", cats.len(), to_json(&cats)?)
+            }
+            "get_cat_by_id" => {
+                let id = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("id"))
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| invalid("Cat ID is required".to_string()))?;
+                let id = u32::try_from(id).map_err(|_| invalid(format!("Cat ID must be at most {}, got {}", u32::MAX, id)))?;
+                match self.cats.get(id).await.map_err(unavailable)? {
+                    Some(cat) => format!("Cat {}:\n{}", id, to_json(&cat)?),
+                    None => format!("Cat with ID {} not found", id),
+                }
+            }
+            other => return Err(invalid(format!("Unknown tool: {}", other))),
+        };
+        Ok(CallToolResult::success(vec![Content::text(content)]))
+    }
+}
+
+/// Rejects requests whose `Authorization` header is not `Bearer <token>`.
+async fn require_token(token: Arc<String>, request: Request, next: Next) -> Result<Response, StatusCode> {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if presented == token.as_str() => Ok(next.run(request).await),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+fn sample_cats() -> Vec<Cat> {
+    vec![Cat {
+        id: 1,
+        name: "Mike".to_string(),
+        name_variants: vec!["ミケ".to_string()],
+        age: 3,
+        breed: "Calico".to_string(),
+        color: "Calico".to_string(),
+        is_indoor: true,
+        favorite_toy: "Mouse toy".to_string(),
+        allergies: vec![],
+        prescribed_diets: vec![],
+        temperament: vec!["playful".to_string()],
+        photos: vec![],
+        version: 1,
+    }]
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let token = std::env::var("CAT_EXAMPLE_TOKEN")
+        .map_err(|_| anyhow::anyhow!("Set CAT_EXAMPLE_TOKEN to the token clients must send"))?;
+    let addr = std::env::var("CAT_EXAMPLE_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string()).parse()?;
+
+    let shutdown = CancellationToken::new();
+    let (sse, router) = SseServer::new(SseServerConfig {
+        bind: addr,
+        sse_path: "/sse".to_string(),
+        post_path: "/message".to_string(),
+        ct: shutdown.clone(),
+        sse_keep_alive: None,
+    });
+    let token = Arc::new(token);
+    let router = router.layer(middleware::from_fn(move |request: Request, next: Next| {
+        require_token(token.clone(), request, next)
+    }));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let stop = shutdown.clone();
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).with_graceful_shutdown(stop.cancelled_owned()).await {
+            eprintln!("HTTP server stopped: {}", e);
+        }
+    });
+
+    let cats = Arc::new(InMemoryCatRepository::new(sample_cats()));
+    sse.with_service(move || CatsOverHttp { cats: cats.clone() });
+    println!("Listening on http://{}/sse; press Ctrl-C to stop", addr);
+
+    tokio::signal::ctrl_c().await?;
+    shutdown.cancel();
+    Ok(())
+}
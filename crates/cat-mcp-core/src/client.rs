@@ -0,0 +1,104 @@
+//! Typed client for Rust programs that talk to this server.
+//!
+//! Tool results are text with a one-line header followed by JSON, so this relies
+//! on the built-in response templates (a custom `CAT_SERVER_TEMPLATE_DIR` that
+//! changes that layout will break it).
+
+use crate::Cat;
+use crate::incident::Incident;
+use anyhow::{Context, bail};
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::{CallToolRequestParam, CallToolResult},
+    service::RunningService,
+    transport::TokioChildProcess,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use std::ffi::OsStr;
+
+pub struct CatClient {
+    service: RunningService<RoleClient, ()>,
+}
+
+impl CatClient {
+    /// Starts the server binary as a child process and connects over stdio.
+    pub async fn spawn(program: impl AsRef<OsStr>) -> anyhow::Result<Self> {
+        let transport = TokioChildProcess::new(tokio::process::Command::new(program))?;
+        Ok(Self::new(().serve(transport).await?))
+    }
+
+    /// Wraps an rmcp client that is already connected to the server.
+    pub fn new(service: RunningService<RoleClient, ()>) -> Self {
+        Self { service }
+    }
+
+    pub async fn list_cats(&self) -> anyhow::Result<Vec<Cat>> {
+        let result = self.call("list_all_cats", json!({ "full": true })).await?;
+        body(&result)?.context("list_all_cats returned no data")
+    }
+
+    /// `None` when no cat has this ID.
+    pub async fn get_cat(&self, id: u32) -> anyhow::Result<Option<Cat>> {
+        let result = self.call("get_cat_by_id", json!({ "id": id })).await?;
+        body(&result)
+    }
+
+    pub async fn search_cats(&self, breed: &str) -> anyhow::Result<Vec<Cat>> {
+        let result = self.call("search_cats", json!({ "breed": breed, "full": true })).await?;
+        Ok(body(&result)?.unwrap_or_default())
+    }
+
+    pub async fn add_cat(&self, name: &str, age: u32, breed: &str, color: &str) -> anyhow::Result<Cat> {
+        let arguments = json!({ "name": name, "age": age, "breed": breed, "color": color });
+        let result = self.call("add_cat", arguments).await?;
+        body(&result)?.context("add_cat returned no data")
+    }
+
+    pub async fn list_incidents(&self, cat_id: Option<u32>) -> anyhow::Result<Vec<Incident>> {
+        let arguments = match cat_id {
+            Some(cat_id) => json!({ "cat_id": cat_id }),
+            None => json!({}),
+        };
+        let result = self.call("list_incidents", arguments).await?;
+        Ok(body(&result)?.unwrap_or_default())
+    }
+
+    /// Calls any tool by name, for tools without a typed method.
+    pub async fn call(&self, tool: &str, arguments: Value) -> anyhow::Result<CallToolResult> {
+        let arguments: Map<String, Value> = match arguments {
+            Value::Object(arguments) => arguments,
+            _ => bail!("tool arguments must be a JSON object"),
+        };
+        let result = self
+            .service
+            .call_tool(CallToolRequestParam {
+                name: tool.to_string().into(),
+                arguments: Some(arguments),
+            })
+            .await?;
+        if result.is_error == Some(true) {
+            bail!("{} failed", tool);
+        }
+        Ok(result)
+    }
+
+    pub async fn close(self) -> anyhow::Result<()> {
+        self.service.cancel().await?;
+        Ok(())
+    }
+}
+
+/// Parses the JSON after the header line of the first text part. Results with
+/// only a header (e.g. "Cat with ID 9 not found") give `None`.
+fn body<T: DeserializeOwned>(result: &CallToolResult) -> anyhow::Result<Option<T>> {
+    let text = result
+        .content
+        .iter()
+        .find_map(|content| content.as_text())
+        .context("tool returned no text")?;
+    match text.text.split_once('\n') {
+        Some((_, json)) => Ok(Some(serde_json::from_str(json)?)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,277 @@
+//! Generic list/get/search/add/update/delete tools for any record type.
+//!
+//! A fork adding, say, dogs defines the struct, describes it with
+//! [`entity_tools!`](crate::entity_tools), and registers an [`EntityTools`]
+//! with the server:
+//!
+//! ```ignore
+//! #[derive(Debug, Clone, Serialize, Deserialize)]
+//! pub struct Dog { pub id: u32, pub name: String, pub breed: String, pub age: u32 }
+//!
+//! cat_mcp_core::entity_tools!(Dog {
+//!     name: "dog",
+//!     plural: "dogs",
+//!     search: [name, breed],
+//!     fields: { name: "string", breed: "string", age: "number" },
+//! });
+//!
+//! // in CatServer::new(): entities: vec![Box::new(EntityTools::<Dog>::new(vec![]))],
+//! ```
+
+use rmcp::{
+    ErrorData,
+    model::{Content, ErrorCode, Tool},
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value, json};
+use crate::ids::IdAllocator;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A record type that can be served by [`EntityTools`]. Usually implemented with
+/// [`entity_tools!`](crate::entity_tools).
+pub trait Entity: Serialize + DeserializeOwned + Clone + Send + Sync + 'static {
+    /// Singular name used in tool names, e.g. `"dog"` → `get_dog`.
+    const NAME: &'static str;
+    /// Plural name used in tool names, e.g. `"dogs"` → `list_dogs`.
+    const PLURAL: &'static str;
+
+    fn id(&self) -> u32;
+    fn set_id(&mut self, id: u32);
+    /// Text the search tool matches against.
+    fn search_text(&self) -> Vec<String>;
+    /// JSON schema properties for every field except `id`.
+    fn properties() -> Map<String, Value>;
+}
+
+/// Object-safe view of [`EntityTools`], so one server can hold several types.
+pub trait Toolset: Send + Sync {
+    fn tools(&self) -> Vec<Tool>;
+    /// `None` when `tool` is not one of this toolset's tools.
+    fn call<'a>(
+        &'a self,
+        tool: &'a str,
+        arguments: Option<&'a Map<String, Value>>,
+    ) -> Pin<Box<dyn Future<Output = Option<Result<Vec<Content>, ErrorData>>> + Send + 'a>>;
+}
+
+pub struct EntityTools<T: Entity> {
+    records: RwLock<BTreeMap<u32, T>>,
+    ids: IdAllocator,
+}
+
+fn invalid(message: String) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: message.into(),
+        data: None,
+    }
+}
+
+fn schema(properties: Value, required: &[&str]) -> Arc<Map<String, Value>> {
+    let mut map = Map::new();
+    map.insert("type".to_string(), Value::String("object".to_string()));
+    map.insert("properties".to_string(), properties);
+    map.insert("required".to_string(), json!(required));
+    Arc::new(map)
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, ErrorData> {
+    serde_json::to_string_pretty(value).map_err(|e| ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!("Serialization error: {}", e).into(),
+        data: None,
+    })
+}
+
+impl<T: Entity> EntityTools<T> {
+    pub fn new(records: Vec<T>) -> Self {
+        Self {
+            ids: IdAllocator::after(records.iter().map(|record| record.id())),
+            records: RwLock::new(records.into_iter().map(|record| (record.id(), record)).collect()),
+        }
+    }
+
+    fn id_argument(arguments: Option<&Map<String, Value>>) -> Result<u32, ErrorData> {
+        arguments
+            .and_then(|args| args.get("id"))
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| invalid(format!("{} ID is required", T::NAME)))
+            .and_then(|v| u32::try_from(v).map_err(|_| invalid(format!("{} ID must be at most {}, got {}", T::NAME, u32::MAX, v))))
+    }
+
+    async fn handle(&self, tool: &str, arguments: Option<&Map<String, Value>>) -> Option<Result<Vec<Content>, ErrorData>> {
+        let (name, plural) = (T::NAME, T::PLURAL);
+        let result = if tool == format!("list_{}", plural) {
+            self.list().await
+        } else if tool == format!("get_{}", name) {
+            self.get(arguments).await
+        } else if tool == format!("search_{}", plural) {
+            self.search(arguments).await
+        } else if tool == format!("add_{}", name) {
+            self.add(arguments).await
+        } else if tool == format!("update_{}", name) {
+            self.update(arguments).await
+        } else if tool == format!("delete_{}", name) {
+            self.delete(arguments).await
+        } else {
+            return None;
+        };
+        Some(result)
+    }
+
+    async fn list(&self) -> Result<Vec<Content>, ErrorData> {
+        let records = self.records.read().await;
+        let all: Vec<&T> = records.values().collect();
+        Ok(vec![Content::text(format!("All {} ({}):\n{}", T::PLURAL, all.len(), to_json(&all)?))])
+    }
+
+    async fn get(&self, arguments: Option<&Map<String, Value>>) -> Result<Vec<Content>, ErrorData> {
+        let id = Self::id_argument(arguments)?;
+        match self.records.read().await.get(&id) {
+            Some(record) => Ok(vec![Content::text(format!("{} {}:\n{}", T::NAME, id, to_json(record)?))]),
+            None => Ok(vec![Content::text(format!("No {} with ID {}", T::NAME, id))]),
+        }
+    }
+
+    async fn search(&self, arguments: Option<&Map<String, Value>>) -> Result<Vec<Content>, ErrorData> {
+        let query = arguments
+            .and_then(|args| args.get("query"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| invalid("query is required".to_string()))?
+            .to_lowercase();
+        let records = self.records.read().await;
+        let matches: Vec<&T> = records
+            .values()
+            .filter(|record| record.search_text().iter().any(|text| text.to_lowercase().contains(&query)))
+            .collect();
+        Ok(vec![Content::text(format!(
+            "{} matching \"{}\" ({}):\n{}",
+            T::PLURAL,
+            query,
+            matches.len(),
+            to_json(&matches)?
+        ))])
+    }
+
+    async fn add(&self, arguments: Option<&Map<String, Value>>) -> Result<Vec<Content>, ErrorData> {
+        let mut records = self.records.write().await;
+        let id = self.ids.next();
+        let mut fields = arguments.cloned().unwrap_or_default();
+        fields.insert("id".to_string(), json!(id));
+        let record: T = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| invalid(format!("Invalid {} fields: {}", T::NAME, e)))?;
+        let content = to_json(&record)?;
+        records.insert(id, record);
+        Ok(vec![Content::text(format!("Added {} {}:\n{}", T::NAME, id, content))])
+    }
+
+    async fn update(&self, arguments: Option<&Map<String, Value>>) -> Result<Vec<Content>, ErrorData> {
+        let id = Self::id_argument(arguments)?;
+        let mut records = self.records.write().await;
+        let existing = records
+            .get(&id)
+            .ok_or_else(|| invalid(format!("No {} with ID {}", T::NAME, id)))?;
+        let mut value = serde_json::to_value(existing).map_err(|e| invalid(e.to_string()))?;
+        for (key, field) in arguments.into_iter().flatten().filter(|(key, _)| key.as_str() != "id") {
+            match value.get_mut(key) {
+                Some(slot) => *slot = field.clone(),
+                None => return Err(invalid(format!("Unknown {} field \"{}\"", T::NAME, key))),
+            }
+        }
+        let mut record: T = serde_json::from_value(value)
+            .map_err(|e| invalid(format!("Invalid {} fields: {}", T::NAME, e)))?;
+        record.set_id(id);
+        let content = to_json(&record)?;
+        records.insert(id, record);
+        Ok(vec![Content::text(format!("Updated {} {}:\n{}", T::NAME, id, content))])
+    }
+
+    async fn delete(&self, arguments: Option<&Map<String, Value>>) -> Result<Vec<Content>, ErrorData> {
+        let id = Self::id_argument(arguments)?;
+        let record = self
+            .records
+            .write()
+            .await
+            .remove(&id)
+            .ok_or_else(|| invalid(format!("No {} with ID {}", T::NAME, id)))?;
+        Ok(vec![Content::text(format!("Deleted {} {}:\n{}", T::NAME, id, to_json(&record)?))])
+    }
+}
+
+impl<T: Entity> Toolset for EntityTools<T> {
+    fn tools(&self) -> Vec<Tool> {
+        let (name, plural) = (T::NAME, T::PLURAL);
+        let id = json!({ "id": { "type": "number", "description": format!("ID of the {}", name) } });
+        let mut with_id = T::properties();
+        with_id.insert("id".to_string(), id["id"].clone());
+        let tool = |tool_name: String, description: String, input_schema: Arc<Map<String, Value>>| Tool {
+            name: tool_name.into(),
+            description: Some(description.into()),
+            input_schema,
+            annotations: None,
+        };
+        vec![
+            tool(format!("list_{}", plural), format!("Get a list of all {}", plural), schema(json!({}), &[])),
+            tool(format!("get_{}", name), format!("Get a specific {} by ID", name), schema(id.clone(), &["id"])),
+            tool(
+                format!("search_{}", plural),
+                format!("Search {} by text (partial, case-insensitive)", plural),
+                schema(json!({ "query": { "type": "string", "description": "Text to search for" } }), &["query"]),
+            ),
+            tool(format!("add_{}", name), format!("Add a new {}", name), schema(Value::Object(T::properties()), &[])),
+            tool(
+                format!("update_{}", name),
+                format!("Update some fields of an existing {}", name),
+                schema(Value::Object(with_id), &["id"]),
+            ),
+            tool(format!("delete_{}", name), format!("Permanently remove a {}", name), schema(id, &["id"])),
+        ]
+    }
+
+    fn call<'a>(
+        &'a self,
+        tool: &'a str,
+        arguments: Option<&'a Map<String, Value>>,
+    ) -> Pin<Box<dyn Future<Output = Option<Result<Vec<Content>, ErrorData>>> + Send + 'a>> {
+        Box::pin(self.handle(tool, arguments))
+    }
+}
+
+/// Implements [`Entity`] for a struct with an `id: u32` field.
+#[macro_export]
+macro_rules! entity_tools {
+    ($ty:ident {
+        name: $name:literal,
+        plural: $plural:literal,
+        search: [$($search:ident),* $(,)?],
+        fields: { $($field:ident: $json_type:literal),* $(,)? } $(,)?
+    }) => {
+        impl $crate::entity::Entity for $ty {
+            const NAME: &'static str = $name;
+            const PLURAL: &'static str = $plural;
+
+            fn id(&self) -> u32 {
+                self.id
+            }
+
+            fn set_id(&mut self, id: u32) {
+                self.id = id;
+            }
+
+            fn search_text(&self) -> Vec<String> {
+                vec![$(self.$search.to_string()),*]
+            }
+
+            fn properties() -> $crate::__serde_json::Map<String, $crate::__serde_json::Value> {
+                let mut properties = $crate::__serde_json::Map::new();
+                $(properties.insert(stringify!($field).to_string(), $crate::__serde_json::json!({ "type": $json_type }));)*
+                properties
+            }
+        }
+    };
+}
@@ -0,0 +1,28 @@
+//! Record IDs that are never handed out twice.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Hands out increasing IDs. Deleting a record does not free its ID, so an old
+/// ID cannot come back as a different record.
+#[derive(Debug, Default)]
+pub struct IdAllocator {
+    last: AtomicU32,
+}
+
+impl IdAllocator {
+    /// Starts after the highest of `existing`.
+    pub fn after(existing: impl IntoIterator<Item = u32>) -> Self {
+        Self {
+            last: AtomicU32::new(existing.into_iter().max().unwrap_or(0)),
+        }
+    }
+
+    pub fn next(&self) -> u32 {
+        self.last.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Makes sure `id`, taken from outside (e.g. an import), is never handed out.
+    pub fn reserve(&self, id: u32) {
+        self.last.fetch_max(id, Ordering::SeqCst);
+    }
+}
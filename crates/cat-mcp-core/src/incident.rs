@@ -0,0 +1,130 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub id: u32,
+    pub cat_id: u32,
+    pub datetime: NaiveDateTime,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+/// Accepts `YYYY-MM-DDTHH:MM[:SS]` (a space may replace the `T`).
+pub fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+}
+
+#[derive(Debug, Serialize)]
+pub struct BehaviorPattern {
+    pub cat_id: u32,
+    pub total_incidents: usize,
+    pub by_type: BTreeMap<String, usize>,
+    pub by_severity: BTreeMap<Severity, usize>,
+    /// Incident counts per calendar month (`YYYY-MM`), oldest first. Months
+    /// between the first and last incident with none are counted as zero.
+    pub monthly_frequency: BTreeMap<String, usize>,
+    pub trend: &'static str,
+    pub time_of_day: BTreeMap<&'static str, usize>,
+    pub peak_time_of_day: Option<&'static str>,
+}
+
+fn time_of_day_bucket(hour: u32) -> &'static str {
+    match hour {
+        0..=5 => "night (00-06)",
+        6..=11 => "morning (06-12)",
+        12..=17 => "afternoon (12-18)",
+        _ => "evening (18-24)",
+    }
+}
+
+/// Months since year 0, so that consecutive months are consecutive numbers.
+fn month_index(datetime: &NaiveDateTime) -> i32 {
+    datetime.year() * 12 + datetime.month0() as i32
+}
+
+/// Compares the latest month with the average of the months before it.
+fn frequency_trend(monthly: &BTreeMap<String, usize>) -> &'static str {
+    let counts: Vec<usize> = monthly.values().copied().collect();
+    let Some((&latest, earlier)) = counts.split_last() else {
+        return "no data";
+    };
+    if earlier.is_empty() {
+        return "insufficient data";
+    }
+
+    let average = earlier.iter().sum::<usize>() as f64 / earlier.len() as f64;
+    let latest = latest as f64;
+    if latest > average * 1.25 {
+        "increasing"
+    } else if latest < average * 0.75 {
+        "decreasing"
+    } else {
+        "stable"
+    }
+}
+
+pub fn analyze_patterns(cat_id: u32, incidents: &[&Incident]) -> BehaviorPattern {
+    let mut by_type = BTreeMap::new();
+    let mut by_severity = BTreeMap::new();
+    let mut monthly_frequency = BTreeMap::new();
+    let mut time_of_day = BTreeMap::new();
+
+    for incident in incidents {
+        *by_type.entry(incident.kind.clone()).or_insert(0) += 1;
+        *by_severity.entry(incident.severity).or_insert(0) += 1;
+        *monthly_frequency.entry(month_index(&incident.datetime)).or_insert(0) += 1;
+        *time_of_day.entry(time_of_day_bucket(incident.datetime.hour())).or_insert(0) += 1;
+    }
+    // Quiet months count too: one incident in January and one in June is not
+    // the same trend as one in each of two consecutive months.
+    if let (Some(&first), Some(&last)) = (monthly_frequency.keys().next(), monthly_frequency.keys().next_back()) {
+        for month in first..=last {
+            monthly_frequency.entry(month).or_insert(0);
+        }
+    }
+    let monthly_frequency: BTreeMap<String, usize> = monthly_frequency
+        .into_iter()
+        .map(|(month, count)| (format!("{:04}-{:02}", month.div_euclid(12), month.rem_euclid(12) + 1), count))
+        .collect();
+
+    let peak_time_of_day = time_of_day
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map(|(bucket, _)| *bucket);
+
+    BehaviorPattern {
+        cat_id,
+        total_incidents: incidents.len(),
+        trend: frequency_trend(&monthly_frequency),
+        by_type,
+        by_severity,
+        monthly_frequency,
+        time_of_day,
+        peak_time_of_day,
+    }
+}
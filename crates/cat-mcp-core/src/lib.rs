@@ -0,0 +1,46 @@
+//! The cat model, repository, storage backends and entity tools, shared by
+//! the server binary and the optional typed client. Programs embedding the
+//! core pull in no transport; storage backends are behind the `sqlite`
+//! (default) and `postgres` features.
+
+use serde::{Deserialize, Serialize};
+
+pub mod entity;
+pub mod ids;
+pub mod incident;
+pub mod migration;
+pub mod repository;
+pub mod store;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[doc(hidden)]
+pub use serde_json as __serde_json;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cat {
+    pub id: u32,
+    pub name: String,
+    /// The name in other scripts (e.g. katakana); `name` is the display form.
+    #[serde(default)]
+    pub name_variants: Vec<String>,
+    pub age: u32,
+    pub breed: String,
+    pub color: String,
+    pub is_indoor: bool,
+    #[serde(default)]
+    pub favorite_toy: String,
+    #[serde(default)]
+    pub allergies: Vec<String>,
+    #[serde(default)]
+    pub prescribed_diets: Vec<String>,
+    #[serde(default)]
+    pub temperament: Vec<String>,
+    #[serde(default)]
+    pub photos: Vec<String>,
+    /// Goes up by one on every write. Clients send the version they last saw
+    /// as `expected_version`, so a change based on stale data is refused.
+    #[serde(default)]
+    pub version: u64,
+}
@@ -0,0 +1,149 @@
+//! Versioned storage format. Every store records the format version it was
+//! written in; on load, records are upgraded as raw JSON by each migration
+//! newer than that version before they are read as cats and incidents, and
+//! the store is then rewritten in the current format.
+//!
+//! Stores from before versioning count as version 1.
+
+use crate::Cat;
+use crate::incident::Incident;
+use crate::repository::ArchivedCat;
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// The format version stores are written in.
+pub const CURRENT: u32 = 2;
+
+/// Stored records before they are read into their types.
+#[derive(Debug, Default)]
+pub struct RawData {
+    pub cats: Vec<Value>,
+    pub incidents: Vec<Value>,
+    pub archived_cats: Vec<Value>,
+}
+
+struct Migration {
+    /// The version this migration produces.
+    to: u32,
+    description: &'static str,
+    /// Upgrades the records in place, describing each change it made.
+    apply: fn(&mut RawData) -> Vec<String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 2,
+    description: "Give every cat a version number, starting at 1",
+    apply: number_versions,
+}];
+
+fn number_versions(data: &mut RawData) -> Vec<String> {
+    let mut changes = Vec::new();
+    for cat in data.cats.iter_mut().chain(data.archived_cats.iter_mut()) {
+        let Some(record) = cat.as_object_mut() else { continue };
+        if record.get("version").and_then(Value::as_u64).unwrap_or(0) == 0 {
+            record.insert("version".to_string(), Value::from(1));
+            changes.push(format!("cat {}: version set to 1", record.get("id").unwrap_or(&Value::Null)));
+        }
+    }
+    changes
+}
+
+/// One migration as applied to a store.
+#[derive(Debug)]
+pub struct Step {
+    pub to: u32,
+    pub description: &'static str,
+    pub changes: Vec<String>,
+}
+
+/// Brings `data` from `version` up to [`CURRENT`].
+pub fn migrate(data: &mut RawData, version: u32) -> anyhow::Result<Vec<Step>> {
+    if version > CURRENT {
+        anyhow::bail!(
+            "The store is in format version {}, but this server only understands up to {}; upgrade the server",
+            version,
+            CURRENT
+        );
+    }
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|migration| migration.to > version)
+        .map(|migration| Step {
+            to: migration.to,
+            description: migration.description,
+            changes: (migration.apply)(data),
+        })
+        .collect())
+}
+
+fn read_all<T: DeserializeOwned>(records: Vec<Value>, kind: &str) -> anyhow::Result<Vec<T>> {
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let id = record.get("id").cloned().unwrap_or(Value::Null);
+            serde_json::from_value(record).with_context(|| format!("Could not read {} {} (record {})", kind, id, index + 1))
+        })
+        .collect()
+}
+
+impl RawData {
+    pub fn into_records(self) -> anyhow::Result<(Vec<Cat>, Vec<Incident>, Vec<ArchivedCat>)> {
+        Ok((
+            read_all(self.cats, "cat")?,
+            read_all(self.incidents, "incident")?,
+            read_all(self.archived_cats, "archived cat")?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cat(id: u32) -> Value {
+        json!({ "id": id, "name": "Mochi", "age": 3, "breed": "Persian", "color": "White", "is_indoor": true })
+    }
+
+    #[test]
+    fn version_1_cats_are_numbered_from_1() {
+        let mut versioned = cat(2);
+        versioned["version"] = json!(4);
+        let mut archived = cat(3);
+        archived["deleted_at"] = json!("2024-01-01T00:00:00");
+        let mut data = RawData {
+            cats: vec![cat(1), versioned],
+            incidents: vec![],
+            archived_cats: vec![archived],
+        };
+        let steps = migrate(&mut data, 1).expect("version 1 migrates");
+        assert_eq!(steps.iter().map(|step| step.to).collect::<Vec<_>>(), [2]);
+        assert_eq!(steps[0].changes, ["cat 1: version set to 1", "cat 3: version set to 1"]);
+
+        let (cats, _, archived) = data.into_records().expect("the migrated records read");
+        assert_eq!(cats.iter().map(|cat| cat.version).collect::<Vec<_>>(), [1, 4]);
+        assert_eq!(archived[0].cat.version, 1);
+    }
+
+    #[test]
+    fn a_current_store_needs_no_migration() {
+        let mut data = RawData { cats: vec![cat(1)], ..RawData::default() };
+        assert!(migrate(&mut data, CURRENT).expect("the current version migrates").is_empty());
+        assert!(data.cats[0].get("version").is_none(), "records are left as they were");
+    }
+
+    #[test]
+    fn a_store_from_a_newer_server_is_refused() {
+        let error = migrate(&mut RawData::default(), CURRENT + 1).expect_err("a newer version is refused");
+        assert!(error.to_string().contains("upgrade the server"), "{}", error);
+    }
+
+    #[test]
+    fn an_unreadable_record_is_named() {
+        let data = RawData { cats: vec![cat(1), json!({ "id": 7, "name": "Tama" })], ..RawData::default() };
+        let error = data.into_records().expect_err("a cat without its fields does not read");
+        assert!(error.to_string().contains("cat 7 (record 2)"), "{}", error);
+    }
+}
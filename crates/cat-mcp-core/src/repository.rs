@@ -0,0 +1,245 @@
+//! Where the cats and incidents live. Tool handlers only go through
+//! [`CatRepository`] and [`IncidentRepository`], so a different backend can be
+//! swapped in without touching them. The server uses the in-memory ones with
+//! the JSON file store or no store at all, and the store's own with SQLite,
+//! which reads and writes one row at a time.
+//!
+//! Deleting a cat archives it: archived cats are left out of `list`, `get` and
+//! `search` until they are restored under the same ID.
+
+use crate::Cat;
+use crate::ids::IdAllocator;
+use crate::incident::Incident;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::RwLock;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A deleted cat, kept so it can be restored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedCat {
+    #[serde(flatten)]
+    pub cat: Cat,
+    pub deleted_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryError {
+    /// The cat is no longer at the version the write was based on; someone
+    /// else (possibly another server on the same database) changed it first.
+    Conflict { id: u32, current: u64 },
+    /// The backend could not be reached, or gave back something unreadable.
+    Unavailable(String),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::Conflict { id, current } => write!(f, "cat {} was changed by someone else and is now at version {}", id, current),
+            RepositoryError::Unavailable(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+pub type RepositoryResult<T> = Result<T, RepositoryError>;
+
+/// Storage for cats. Methods return owned copies, so no lock is held once a
+/// call returns.
+pub trait CatRepository: Send + Sync {
+    /// Every cat, in ID order.
+    fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Cat>>>;
+    fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>>;
+    /// Cats for which `filter` returns true, in ID order.
+    fn search<'a>(&'a self, filter: &'a (dyn Fn(&Cat) -> bool + Send + Sync)) -> BoxFuture<'a, RepositoryResult<Vec<Cat>>>;
+    /// Stores `cat` under a new ID (its own `id` is ignored) at version 1 and
+    /// returns it as stored. IDs of deleted cats are not reused.
+    fn insert(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Cat>>;
+    /// Replaces the cat with `cat.id` if it is still at `cat.version`, storing
+    /// it one version above. Returns the previous cat, `None` (storing nothing)
+    /// when there is no such cat, or [`RepositoryError::Conflict`] when it has
+    /// moved on.
+    fn update(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>>;
+    /// Stores `cat` under its own ID and version as given, replacing any live cat
+    /// with that ID, which is returned. Only for IDs handed out before, e.g. to
+    /// undo a change.
+    fn replace(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>>;
+    /// Removes a live cat for good, returning it.
+    fn delete(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>>;
+    /// Moves a cat to the archive, returning it as archived.
+    fn archive(&self, id: u32, deleted_at: NaiveDateTime) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>>;
+    /// Moves an archived cat back, returning it as it was archived.
+    fn restore(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>>;
+    /// Every archived cat, in ID order.
+    fn archived(&self) -> BoxFuture<'_, RepositoryResult<Vec<ArchivedCat>>>;
+
+    fn contains(&self, id: u32) -> BoxFuture<'_, RepositoryResult<bool>> {
+        Box::pin(async move { Ok(self.get(id).await?.is_some()) })
+    }
+}
+
+/// Storage for incidents, kept in ID order.
+pub trait IncidentRepository: Send + Sync {
+    fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>>;
+    fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>>;
+    fn for_cat(&self, cat_id: u32) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>>;
+    /// Stores `incident` under a new ID (its own `id` is ignored) and returns
+    /// it as stored.
+    fn insert(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Incident>>;
+    /// Stores `incident` under its own ID, returning the one it replaced.
+    fn put(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Option<Incident>>>;
+    fn remove(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>>;
+}
+
+/// Cats in a map behind an async `RwLock`: reads run concurrently, and a write
+/// waits its turn without blocking a runtime thread.
+#[derive(Default)]
+pub struct InMemoryCatRepository {
+    cats: RwLock<BTreeMap<u32, Cat>>,
+    /// Always locked after `cats` when both are needed.
+    archived: RwLock<BTreeMap<u32, ArchivedCat>>,
+    ids: IdAllocator,
+}
+
+impl InMemoryCatRepository {
+    pub fn new(cats: Vec<Cat>) -> Self {
+        Self::with_archived(cats, Vec::new())
+    }
+
+    pub fn with_archived(cats: Vec<Cat>, archived: Vec<ArchivedCat>) -> Self {
+        Self {
+            ids: IdAllocator::after(cats.iter().map(|cat| cat.id).chain(archived.iter().map(|entry| entry.cat.id))),
+            cats: RwLock::new(cats.into_iter().map(|cat| (cat.id, cat)).collect()),
+            archived: RwLock::new(archived.into_iter().map(|entry| (entry.cat.id, entry)).collect()),
+        }
+    }
+}
+
+impl CatRepository for InMemoryCatRepository {
+    fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Cat>>> {
+        Box::pin(async move { Ok(self.cats.read().await.values().cloned().collect()) })
+    }
+
+    fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+        Box::pin(async move { Ok(self.cats.read().await.get(&id).cloned()) })
+    }
+
+    fn search<'a>(&'a self, filter: &'a (dyn Fn(&Cat) -> bool + Send + Sync)) -> BoxFuture<'a, RepositoryResult<Vec<Cat>>> {
+        Box::pin(async move { Ok(self.cats.read().await.values().filter(|cat| filter(cat)).cloned().collect()) })
+    }
+
+    fn insert(&self, mut cat: Cat) -> BoxFuture<'_, RepositoryResult<Cat>> {
+        Box::pin(async move {
+            cat.id = self.ids.next();
+            cat.version = 1;
+            self.cats.write().await.insert(cat.id, cat.clone());
+            Ok(cat)
+        })
+    }
+
+    fn update(&self, mut cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+        Box::pin(async move {
+            let mut cats = self.cats.write().await;
+            let Some(slot) = cats.get_mut(&cat.id) else {
+                return Ok(None);
+            };
+            if slot.version != cat.version {
+                return Err(RepositoryError::Conflict { id: cat.id, current: slot.version });
+            }
+            cat.version += 1;
+            Ok(Some(std::mem::replace(slot, cat)))
+        })
+    }
+
+    fn replace(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+        Box::pin(async move {
+            self.ids.reserve(cat.id);
+            Ok(self.cats.write().await.insert(cat.id, cat))
+        })
+    }
+
+    fn delete(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+        Box::pin(async move { Ok(self.cats.write().await.remove(&id)) })
+    }
+
+    fn archive(&self, id: u32, deleted_at: NaiveDateTime) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+        Box::pin(async move {
+            let mut cats = self.cats.write().await;
+            let Some(cat) = cats.remove(&id) else {
+                return Ok(None);
+            };
+            let entry = ArchivedCat { cat, deleted_at };
+            self.archived.write().await.insert(id, entry.clone());
+            Ok(Some(entry))
+        })
+    }
+
+    fn restore(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+        Box::pin(async move {
+            let mut cats = self.cats.write().await;
+            let Some(entry) = self.archived.write().await.remove(&id) else {
+                return Ok(None);
+            };
+            cats.insert(id, entry.cat.clone());
+            Ok(Some(entry))
+        })
+    }
+
+    fn archived(&self) -> BoxFuture<'_, RepositoryResult<Vec<ArchivedCat>>> {
+        Box::pin(async move { Ok(self.archived.read().await.values().cloned().collect()) })
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryIncidentRepository {
+    incidents: RwLock<BTreeMap<u32, Incident>>,
+    ids: IdAllocator,
+}
+
+impl InMemoryIncidentRepository {
+    pub fn new(incidents: Vec<Incident>) -> Self {
+        Self {
+            ids: IdAllocator::after(incidents.iter().map(|incident| incident.id)),
+            incidents: RwLock::new(incidents.into_iter().map(|incident| (incident.id, incident)).collect()),
+        }
+    }
+}
+
+impl IncidentRepository for InMemoryIncidentRepository {
+    fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+        Box::pin(async move { Ok(self.incidents.read().await.values().cloned().collect()) })
+    }
+
+    fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+        Box::pin(async move { Ok(self.incidents.read().await.get(&id).cloned()) })
+    }
+
+    fn for_cat(&self, cat_id: u32) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+        Box::pin(async move { Ok(self.incidents.read().await.values().filter(|incident| incident.cat_id == cat_id).cloned().collect()) })
+    }
+
+    fn insert(&self, mut incident: Incident) -> BoxFuture<'_, RepositoryResult<Incident>> {
+        Box::pin(async move {
+            incident.id = self.ids.next();
+            self.incidents.write().await.insert(incident.id, incident.clone());
+            Ok(incident)
+        })
+    }
+
+    fn put(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+        Box::pin(async move {
+            self.ids.reserve(incident.id);
+            Ok(self.incidents.write().await.insert(incident.id, incident))
+        })
+    }
+
+    fn remove(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+        Box::pin(async move { Ok(self.incidents.write().await.remove(&id)) })
+    }
+}
@@ -0,0 +1,1031 @@
+//! Where cats and incidents are persisted: SQLite (the `sqlite` feature, on by
+//! default), a JSON file, or PostgreSQL (the `postgres` feature).
+//!
+//! The databases read and write one row at a time through their own
+//! repositories ([`Store::repositories`]), so several servers can share one.
+//! The JSON file holds everything in one document, which [`Store::save`]
+//! replaces at once. Each store records the format version it was written in;
+//! see [`crate::migration`].
+
+use crate::Cat;
+use crate::incident::Incident;
+use crate::migration::{self, RawData};
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+use crate::repository::RepositoryError;
+use crate::repository::{ArchivedCat, CatRepository, IncidentRepository};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "postgres")]
+pub use postgres::{PostgresCatRepository, PostgresIncidentRepository, PostgresStore};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteCatRepository, SqliteIncidentRepository, SqliteStore};
+
+pub enum Store {
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteStore),
+    JsonFile(JsonFileStore),
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresStore),
+}
+
+impl Store {
+    pub fn describe(&self) -> String {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => format!("SQLite database {}", store.path.display()),
+            Store::JsonFile(store) => format!("JSON file {}", store.path.display()),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(_) => "PostgreSQL (DATABASE_URL)".to_string(),
+        }
+    }
+
+    pub fn is_fresh(&self) -> bool {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.fresh,
+            Store::JsonFile(store) => !store.path.exists(),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(store) => store.fresh,
+        }
+    }
+
+    /// Everything stored so far as raw JSON, with the format version it was
+    /// written in, for [`migration::migrate`].
+    pub async fn load_raw(&self) -> anyhow::Result<(RawData, u32)> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.load_raw(),
+            Store::JsonFile(store) => store.load_raw(),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(store) => store.load_raw().await,
+        }
+    }
+
+    /// Whether changes are written as they are made, through
+    /// [`Store::repositories`], rather than by saving everything.
+    pub fn writes_rows(&self) -> bool {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(_) => true,
+            Store::JsonFile(_) => false,
+            #[cfg(feature = "postgres")]
+            Store::Postgres(_) => true,
+        }
+    }
+
+    /// The store's own repositories, which read and write single rows, or
+    /// `None` for the JSON file, which is written whole with [`Store::save`].
+    pub fn repositories(&self) -> Option<(Arc<dyn CatRepository>, Arc<dyn IncidentRepository>)> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => Some((Arc::new(store.cats()), Arc::new(store.incidents()))),
+            Store::JsonFile(_) => None,
+            #[cfg(feature = "postgres")]
+            Store::Postgres(store) => Some((Arc::new(store.cats()), Arc::new(store.incidents()))),
+        }
+    }
+
+    /// Whether the store can be written to, to tell when an outage is over.
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.ping().await,
+            Store::JsonFile(_) => Ok(()),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(store) => store.ping().await,
+        }
+    }
+
+    /// Rebuilds the SQLite database file without its free pages, or `None` for
+    /// the other stores, which have no such file (PostgreSQL vacuums itself).
+    pub async fn vacuum(&self) -> anyhow::Result<Option<Vacuumed>> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.vacuum().await.map(Some),
+            Store::JsonFile(_) => Ok(None),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(_) => Ok(None),
+        }
+    }
+
+    /// Refreshes SQLite's query planner statistics after a bulk change; the
+    /// other stores need nothing.
+    pub async fn analyze(&self) -> anyhow::Result<()> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.analyze().await,
+            Store::JsonFile(_) => Ok(()),
+            #[cfg(feature = "postgres")]
+            Store::Postgres(_) => Ok(()),
+        }
+    }
+
+    /// Replaces everything stored with these records. With the databases this
+    /// is only for seeding and for rewriting a migrated store.
+    pub async fn save(&self, cats: Vec<Cat>, incidents: Vec<Incident>, archived: Vec<ArchivedCat>) -> anyhow::Result<()> {
+        match self {
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => store.save(cats, incidents, archived).await,
+            Store::JsonFile(store) => store.save(cats, incidents, archived).await,
+            #[cfg(feature = "postgres")]
+            Store::Postgres(store) => store.save(cats, incidents, archived).await,
+        }
+    }
+}
+
+/// The database file's size around a [`Store::vacuum`].
+#[derive(Debug, Serialize)]
+pub struct Vacuumed {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+/// A failed repository operation as a [`RepositoryError`]: one the operation
+/// raised itself comes back as it is, and anything else means the database
+/// could not be used.
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+fn repository_error(e: anyhow::Error) -> RepositoryError {
+    match e.downcast::<RepositoryError>() {
+        Ok(error) => error,
+        Err(e) => RepositoryError::Unavailable(format!("{:#}", e)),
+    }
+}
+
+#[derive(Serialize)]
+struct DataFile {
+    format_version: u32,
+    cats: Vec<Cat>,
+    incidents: Vec<Incident>,
+    archived_cats: Vec<ArchivedCat>,
+}
+
+pub struct JsonFileStore {
+    path: PathBuf,
+    /// Serializes writers so the temp file is never shared.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl JsonFileStore {
+    pub fn open(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Files from before versioning have no `format_version`, and those from
+    /// before cats could be archived no `archived_cats`.
+    fn load_raw(&self) -> anyhow::Result<(RawData, u32)> {
+        let mut file: serde_json::Map<String, Value> = serde_json::from_str(&std::fs::read_to_string(&self.path)?)?;
+        let mut records = |key: &str| -> anyhow::Result<Vec<Value>> {
+            match file.remove(key) {
+                Some(Value::Array(records)) => Ok(records),
+                None if key == "archived_cats" => Ok(Vec::new()),
+                None => anyhow::bail!("{} has no \"{}\" list", self.path.display(), key),
+                Some(_) => anyhow::bail!("\"{}\" in {} is not a list", key, self.path.display()),
+            }
+        };
+        let data = RawData {
+            cats: records("cats")?,
+            incidents: records("incidents")?,
+            archived_cats: records("archived_cats")?,
+        };
+        let version = file.get("format_version").and_then(Value::as_u64).unwrap_or(1) as u32;
+        Ok((data, version))
+    }
+
+    /// Writes a temp file next to the target, syncs it, then renames it over the
+    /// target, so a crash leaves either the old file or the new one.
+    async fn save(&self, cats: Vec<Cat>, incidents: Vec<Incident>, archived: Vec<ArchivedCat>) -> anyhow::Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let json = serde_json::to_vec_pretty(&DataFile {
+            format_version: migration::CURRENT,
+            cats,
+            incidents,
+            archived_cats: archived,
+        })?;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+            use std::io::Write;
+            let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+            temp_name.push(".tmp");
+            let temp = path.with_file_name(temp_name);
+            let mut file = std::fs::File::create(&temp)?;
+            file.write_all(&json)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, &path)?;
+            Ok(())
+        })
+        .await?
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use crate::Cat;
+    use crate::incident::Incident;
+    use crate::migration::{self, RawData};
+    use crate::repository::{ArchivedCat, BoxFuture, CatRepository, IncidentRepository, RepositoryError, RepositoryResult};
+    use chrono::NaiveDateTime;
+    use rusqlite::{Connection, Params, Transaction, TransactionBehavior, params};
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use super::Vacuumed;
+
+    /// How long a write waits for another server sharing the database to
+    /// finish its own before giving up.
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS cats (
+            id INTEGER PRIMARY KEY,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS incidents (
+            id INTEGER PRIMARY KEY,
+            cat_id INTEGER NOT NULL,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS archived_cats (
+            id INTEGER PRIMARY KEY,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS store_meta (
+            key TEXT PRIMARY KEY,
+            value INTEGER NOT NULL
+        );
+    ";
+
+    pub struct SqliteStore {
+        pub(super) path: PathBuf,
+        connection: Arc<Mutex<Connection>>,
+        /// Nothing had been saved when it was opened. A database whose cats were
+        /// all deleted is not fresh, so it is not re-seeded.
+        pub(super) fresh: bool,
+    }
+
+    impl SqliteStore {
+        /// Opens (or creates) the database and makes sure the tables exist.
+        pub fn open(path: &Path) -> rusqlite::Result<Self> {
+            let connection = Connection::open(path)?;
+            connection.busy_timeout(BUSY_TIMEOUT)?;
+            connection.execute_batch(SCHEMA)?;
+            let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+            Ok(Self {
+                path: path.to_path_buf(),
+                connection: Arc::new(Mutex::new(connection)),
+                fresh: version == 0,
+            })
+        }
+
+        pub fn cats(&self) -> SqliteCatRepository {
+            SqliteCatRepository(Database(self.connection.clone()))
+        }
+
+        pub fn incidents(&self) -> SqliteIncidentRepository {
+            SqliteIncidentRepository(Database(self.connection.clone()))
+        }
+
+        /// Writes to the database without changing anything, to tell whether it
+        /// is writable again after an outage.
+        pub(super) async fn ping(&self) -> anyhow::Result<()> {
+            Database(self.connection.clone())
+                .write(|transaction| {
+                    transaction.execute(
+                        "INSERT INTO store_meta (key, value) VALUES ('last_ping', strftime('%s', 'now')) ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                        [],
+                    )?;
+                    Ok(())
+                })
+                .await
+                .map_err(anyhow::Error::from)
+        }
+
+        /// Runs `VACUUM`, which cannot run inside a transaction; like a write it
+        /// waits up to [`BUSY_TIMEOUT`] for other servers sharing the file.
+        pub(super) async fn vacuum(&self) -> anyhow::Result<Vacuumed> {
+            let connection = self.connection.clone();
+            tokio::task::spawn_blocking(move || -> anyhow::Result<Vacuumed> {
+                let connection = connection.lock().expect("store lock poisoned");
+                let size = || {
+                    connection.query_row("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()", [], |row| {
+                        row.get::<_, i64>(0)
+                    })
+                };
+                let before = size()?;
+                connection.execute_batch("VACUUM")?;
+                let after = size()?;
+                Ok(Vacuumed {
+                    before_bytes: before as u64,
+                    after_bytes: after as u64,
+                })
+            })
+            .await?
+        }
+
+        pub(super) async fn analyze(&self) -> anyhow::Result<()> {
+            Database(self.connection.clone())
+                .write(|transaction| {
+                    transaction.execute_batch("ANALYZE")?;
+                    Ok(())
+                })
+                .await
+                .map_err(anyhow::Error::from)
+        }
+
+        /// Everything stored so far. `user_version` is 0 until the first save and
+        /// the format version after it.
+        pub(super) fn load_raw(&self) -> anyhow::Result<(RawData, u32)> {
+            let connection = self.connection.lock().expect("store lock poisoned");
+            let data = RawData {
+                cats: records::<Value>(&connection, "SELECT data FROM cats ORDER BY id", [])?,
+                incidents: records::<Value>(&connection, "SELECT data FROM incidents ORDER BY id", [])?,
+                archived_cats: records::<Value>(&connection, "SELECT data FROM archived_cats ORDER BY id", [])?,
+            };
+            let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+            Ok((data, version.max(1)))
+        }
+
+        /// Replaces the stored records with these, in a single transaction. Only
+        /// for seeding and for rewriting a migrated store; changes afterwards go
+        /// through [`SqliteStore::cats`] and [`SqliteStore::incidents`] a row at a
+        /// time.
+        pub(super) async fn save(&self, cats: Vec<Cat>, incidents: Vec<Incident>, archived: Vec<ArchivedCat>) -> anyhow::Result<()> {
+            let connection = self.connection.clone();
+            tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+                let mut connection = connection.lock().expect("store lock poisoned");
+                let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+                transaction.execute("DELETE FROM cats", [])?;
+                transaction.execute("DELETE FROM incidents", [])?;
+                transaction.execute("DELETE FROM archived_cats", [])?;
+                for cat in &cats {
+                    transaction.execute(
+                        "INSERT INTO cats (id, data) VALUES (?1, ?2)",
+                        params![cat.id, serde_json::to_string(cat)?],
+                    )?;
+                }
+                for incident in &incidents {
+                    transaction.execute(
+                        "INSERT INTO incidents (id, cat_id, data) VALUES (?1, ?2, ?3)",
+                        params![incident.id, incident.cat_id, serde_json::to_string(incident)?],
+                    )?;
+                }
+                for entry in &archived {
+                    transaction.execute(
+                        "INSERT INTO archived_cats (id, data) VALUES (?1, ?2)",
+                        params![entry.cat.id, serde_json::to_string(entry)?],
+                    )?;
+                }
+                mark_written(&transaction)?;
+                transaction.commit()?;
+                Ok(())
+            })
+            .await?
+        }
+    }
+
+    /// The connection the store and its repositories share.
+    #[derive(Clone)]
+    struct Database(Arc<Mutex<Connection>>);
+
+    impl Database {
+        /// Runs `work`, which only reads, in a transaction of its own.
+        async fn read<T: Send + 'static>(&self, work: impl FnOnce(&Transaction) -> anyhow::Result<T> + Send + 'static) -> RepositoryResult<T> {
+            self.run(TransactionBehavior::Deferred, work).await
+        }
+
+        /// Runs `work`, which writes, in a transaction that takes the write lock
+        /// up front. A deferred one would read first and then fail at once with
+        /// SQLITE_BUSY if another server took the lock meanwhile; this one waits
+        /// for it instead (see [`BUSY_TIMEOUT`]).
+        async fn write<T: Send + 'static>(&self, work: impl FnOnce(&Transaction) -> anyhow::Result<T> + Send + 'static) -> RepositoryResult<T> {
+            self.run(TransactionBehavior::Immediate, work).await
+        }
+
+        /// Runs `work` in a transaction on a blocking thread, committing if it
+        /// succeeds.
+        async fn run<T: Send + 'static>(
+            &self,
+            behavior: TransactionBehavior,
+            work: impl FnOnce(&Transaction) -> anyhow::Result<T> + Send + 'static,
+        ) -> RepositoryResult<T> {
+            let connection = self.0.clone();
+            let outcome = tokio::task::spawn_blocking(move || -> anyhow::Result<T> {
+                let mut connection = connection.lock().expect("store lock poisoned");
+                let transaction = connection.transaction_with_behavior(behavior)?;
+                let value = work(&transaction)?;
+                transaction.commit()?;
+                Ok(value)
+            })
+            .await
+            .map_err(|e| RepositoryError::Unavailable(e.to_string()))?;
+            outcome.map_err(super::repository_error)
+        }
+    }
+
+    /// The `data` column of every row `sql` selects.
+    fn records<T: DeserializeOwned>(connection: &Connection, sql: &str, params: impl Params) -> anyhow::Result<Vec<T>> {
+        let mut statement = connection.prepare_cached(sql)?;
+        let rows = statement.query_map(params, |row| row.get::<_, String>(0))?;
+        let mut records = Vec::new();
+        for data in rows {
+            records.push(serde_json::from_str(&data?)?);
+        }
+        Ok(records)
+    }
+
+    fn record<T: DeserializeOwned>(connection: &Connection, sql: &str, params: impl Params) -> anyhow::Result<Option<T>> {
+        Ok(records(connection, sql, params)?.into_iter().next())
+    }
+
+    /// Stamps the format version, so a store written only row by row is not
+    /// taken for a fresh one on the next start.
+    fn mark_written(transaction: &Transaction) -> anyhow::Result<()> {
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", migration::CURRENT))?;
+        Ok(())
+    }
+
+    /// The next ID for `key`: above every ID in `tables` and every ID handed out
+    /// before, so a deleted record's ID does not come back.
+    fn next_id(transaction: &Transaction, key: &str, tables: &[&str]) -> anyhow::Result<u32> {
+        let highest: Vec<String> = tables.iter().map(|table| format!("(SELECT COALESCE(MAX(id), 0) FROM {})", table)).collect();
+        let sql = format!("SELECT MAX(COALESCE((SELECT value FROM store_meta WHERE key = ?1), 0), {})", highest.join(", "));
+        let id = transaction.query_row(&sql, [key], |row| row.get::<_, u32>(0))? + 1;
+        reserve_id(transaction, key, id)?;
+        Ok(id)
+    }
+
+    /// Makes sure `id` is never handed out again under `key`.
+    fn reserve_id(transaction: &Transaction, key: &str, id: u32) -> anyhow::Result<()> {
+        transaction.execute(
+            "INSERT INTO store_meta (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = MAX(value, excluded.value)",
+            params![key, id],
+        )?;
+        Ok(())
+    }
+
+    const CAT_IDS: &str = "last_cat_id";
+    const INCIDENT_IDS: &str = "last_incident_id";
+
+    /// Cats in the `cats` and `archived_cats` tables, one row per cat. Each
+    /// change is a transaction of its own.
+    pub struct SqliteCatRepository(Database);
+
+    impl CatRepository for SqliteCatRepository {
+        fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Cat>>> {
+            Box::pin(self.0.read(|transaction| records(transaction, "SELECT data FROM cats ORDER BY id", [])))
+        }
+
+        fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(self.0.read(move |transaction| record(transaction, "SELECT data FROM cats WHERE id = ?1", [id])))
+        }
+
+        fn search<'a>(&'a self, filter: &'a (dyn Fn(&Cat) -> bool + Send + Sync)) -> BoxFuture<'a, RepositoryResult<Vec<Cat>>> {
+            Box::pin(async move { Ok(self.list().await?.into_iter().filter(|cat| filter(cat)).collect()) })
+        }
+
+        fn insert(&self, mut cat: Cat) -> BoxFuture<'_, RepositoryResult<Cat>> {
+            Box::pin(self.0.write(move |transaction| {
+                cat.id = next_id(transaction, CAT_IDS, &["cats", "archived_cats"])?;
+                cat.version = 1;
+                transaction.execute("INSERT INTO cats (id, data) VALUES (?1, ?2)", params![cat.id, serde_json::to_string(&cat)?])?;
+                mark_written(transaction)?;
+                Ok(cat)
+            }))
+        }
+
+        fn update(&self, mut cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let Some(previous) = record::<Cat>(transaction, "SELECT data FROM cats WHERE id = ?1", [cat.id])? else {
+                    return Ok(None);
+                };
+                let expected = cat.version;
+                cat.version += 1;
+                let updated = transaction.execute(
+                    "UPDATE cats SET data = ?2 WHERE id = ?1 AND COALESCE(json_extract(data, '$.version'), 0) = ?3",
+                    params![cat.id, serde_json::to_string(&cat)?, expected as i64],
+                )?;
+                if updated == 0 {
+                    return Err(RepositoryError::Conflict { id: cat.id, current: previous.version }.into());
+                }
+                mark_written(transaction)?;
+                Ok(Some(previous))
+            }))
+        }
+
+        fn replace(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let previous = record(transaction, "SELECT data FROM cats WHERE id = ?1", [cat.id])?;
+                transaction.execute(
+                    "INSERT INTO cats (id, data) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+                    params![cat.id, serde_json::to_string(&cat)?],
+                )?;
+                reserve_id(transaction, CAT_IDS, cat.id)?;
+                mark_written(transaction)?;
+                Ok(previous)
+            }))
+        }
+
+        fn delete(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let previous = record(transaction, "SELECT data FROM cats WHERE id = ?1", [id])?;
+                if previous.is_some() {
+                    transaction.execute("DELETE FROM cats WHERE id = ?1", [id])?;
+                    reserve_id(transaction, CAT_IDS, id)?;
+                    mark_written(transaction)?;
+                }
+                Ok(previous)
+            }))
+        }
+
+        fn archive(&self, id: u32, deleted_at: NaiveDateTime) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let Some(cat) = record::<Cat>(transaction, "SELECT data FROM cats WHERE id = ?1", [id])? else {
+                    return Ok(None);
+                };
+                let entry = ArchivedCat { cat, deleted_at };
+                transaction.execute("DELETE FROM cats WHERE id = ?1", [id])?;
+                transaction.execute(
+                    "INSERT INTO archived_cats (id, data) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+                    params![id, serde_json::to_string(&entry)?],
+                )?;
+                mark_written(transaction)?;
+                Ok(Some(entry))
+            }))
+        }
+
+        fn restore(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let Some(entry) = record::<ArchivedCat>(transaction, "SELECT data FROM archived_cats WHERE id = ?1", [id])? else {
+                    return Ok(None);
+                };
+                transaction.execute("DELETE FROM archived_cats WHERE id = ?1", [id])?;
+                transaction.execute(
+                    "INSERT INTO cats (id, data) VALUES (?1, ?2) ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+                    params![id, serde_json::to_string(&entry.cat)?],
+                )?;
+                mark_written(transaction)?;
+                Ok(Some(entry))
+            }))
+        }
+
+        fn archived(&self) -> BoxFuture<'_, RepositoryResult<Vec<ArchivedCat>>> {
+            Box::pin(self.0.read(|transaction| records(transaction, "SELECT data FROM archived_cats ORDER BY id", [])))
+        }
+    }
+
+    /// Incidents in the `incidents` table, one row each.
+    pub struct SqliteIncidentRepository(Database);
+
+    impl IncidentRepository for SqliteIncidentRepository {
+        fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+            Box::pin(self.0.read(|transaction| records(transaction, "SELECT data FROM incidents ORDER BY id", [])))
+        }
+
+        fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(self.0.read(move |transaction| record(transaction, "SELECT data FROM incidents WHERE id = ?1", [id])))
+        }
+
+        fn for_cat(&self, cat_id: u32) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+            Box::pin(self.0.read(move |transaction| records(transaction, "SELECT data FROM incidents WHERE cat_id = ?1 ORDER BY id", [cat_id])))
+        }
+
+        fn insert(&self, mut incident: Incident) -> BoxFuture<'_, RepositoryResult<Incident>> {
+            Box::pin(self.0.write(move |transaction| {
+                incident.id = next_id(transaction, INCIDENT_IDS, &["incidents"])?;
+                transaction.execute(
+                    "INSERT INTO incidents (id, cat_id, data) VALUES (?1, ?2, ?3)",
+                    params![incident.id, incident.cat_id, serde_json::to_string(&incident)?],
+                )?;
+                mark_written(transaction)?;
+                Ok(incident)
+            }))
+        }
+
+        fn put(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let previous = record(transaction, "SELECT data FROM incidents WHERE id = ?1", [incident.id])?;
+                transaction.execute(
+                    "INSERT INTO incidents (id, cat_id, data) VALUES (?1, ?2, ?3) ON CONFLICT (id) DO UPDATE SET cat_id = excluded.cat_id, data = excluded.data",
+                    params![incident.id, incident.cat_id, serde_json::to_string(&incident)?],
+                )?;
+                reserve_id(transaction, INCIDENT_IDS, incident.id)?;
+                mark_written(transaction)?;
+                Ok(previous)
+            }))
+        }
+
+        fn remove(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(self.0.write(move |transaction| {
+                let previous = record(transaction, "SELECT data FROM incidents WHERE id = ?1", [id])?;
+                if previous.is_some() {
+                    transaction.execute("DELETE FROM incidents WHERE id = ?1", [id])?;
+                    reserve_id(transaction, INCIDENT_IDS, id)?;
+                    mark_written(transaction)?;
+                }
+                Ok(previous)
+            }))
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use crate::Cat;
+    use crate::incident::Incident;
+    use crate::migration::{self, RawData};
+    use crate::repository::{ArchivedCat, BoxFuture, CatRepository, IncidentRepository, RepositoryError, RepositoryResult};
+    use chrono::NaiveDateTime;
+    use serde::de::DeserializeOwned;
+    use sqlx::postgres::{PgPool, PgPoolOptions};
+    use sqlx::types::Json;
+    use sqlx::{PgConnection, Postgres, Transaction};
+
+    const SCHEMA: &[&str] = &[
+        "CREATE TABLE IF NOT EXISTS cats (id BIGINT PRIMARY KEY, data JSONB NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS incidents (id BIGINT PRIMARY KEY, cat_id BIGINT NOT NULL, data JSONB NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS archived_cats (id BIGINT PRIMARY KEY, data JSONB NOT NULL)",
+        "CREATE TABLE IF NOT EXISTS store_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+    ];
+
+    pub struct PostgresStore {
+        pool: PgPool,
+        pub(super) fresh: bool,
+    }
+
+    impl PostgresStore {
+        pub async fn connect(url: &str) -> anyhow::Result<Self> {
+            let pool = PgPoolOptions::new().max_connections(5).connect(url).await?;
+            for statement in SCHEMA {
+                sqlx::query(statement).execute(&pool).await?;
+            }
+            let seeded: Option<(String,)> = sqlx::query_as("SELECT value FROM store_meta WHERE key = 'seeded'")
+                .fetch_optional(&pool)
+                .await?;
+            Ok(Self {
+                pool,
+                fresh: seeded.is_none(),
+            })
+        }
+
+        pub fn cats(&self) -> PostgresCatRepository {
+            PostgresCatRepository(self.pool.clone())
+        }
+
+        pub fn incidents(&self) -> PostgresIncidentRepository {
+            PostgresIncidentRepository(self.pool.clone())
+        }
+
+        pub(super) async fn ping(&self) -> anyhow::Result<()> {
+            sqlx::query("SELECT 1").execute(&self.pool).await?;
+            Ok(())
+        }
+
+        /// The format version is in `store_meta`; stores from before it was
+        /// recorded are version 1.
+        pub(super) async fn load_raw(&self) -> anyhow::Result<(RawData, u32)> {
+            let data = RawData {
+                cats: records(&self.pool, "SELECT data FROM cats ORDER BY id", None).await?,
+                incidents: records(&self.pool, "SELECT data FROM incidents ORDER BY id", None).await?,
+                archived_cats: records(&self.pool, "SELECT data FROM archived_cats ORDER BY id", None).await?,
+            };
+            let version: Option<(String,)> = sqlx::query_as("SELECT value FROM store_meta WHERE key = 'format_version'")
+                .fetch_optional(&self.pool)
+                .await?;
+            Ok((data, version.and_then(|(value,)| value.parse().ok()).unwrap_or(1)))
+        }
+
+        /// Replaces the stored records with these, in a single transaction. Only
+        /// for seeding and for rewriting a migrated store; changes afterwards go
+        /// through [`PostgresStore::cats`] and [`PostgresStore::incidents`] a row
+        /// at a time.
+        pub(super) async fn save(&self, cats: Vec<Cat>, incidents: Vec<Incident>, archived: Vec<ArchivedCat>) -> anyhow::Result<()> {
+            let mut transaction = self.pool.begin().await?;
+            sqlx::query("DELETE FROM cats").execute(&mut *transaction).await?;
+            sqlx::query("DELETE FROM incidents").execute(&mut *transaction).await?;
+            sqlx::query("DELETE FROM archived_cats").execute(&mut *transaction).await?;
+            for cat in &cats {
+                sqlx::query("INSERT INTO cats (id, data) VALUES ($1, $2)")
+                    .bind(cat.id as i64)
+                    .bind(Json(cat))
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            for incident in &incidents {
+                sqlx::query("INSERT INTO incidents (id, cat_id, data) VALUES ($1, $2, $3)")
+                    .bind(incident.id as i64)
+                    .bind(incident.cat_id as i64)
+                    .bind(Json(incident))
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            for entry in &archived {
+                sqlx::query("INSERT INTO archived_cats (id, data) VALUES ($1, $2)")
+                    .bind(entry.cat.id as i64)
+                    .bind(Json(entry))
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            sqlx::query("INSERT INTO store_meta (key, value) VALUES ('seeded', 'true') ON CONFLICT (key) DO NOTHING")
+                .execute(&mut *transaction)
+                .await?;
+            sqlx::query("INSERT INTO store_meta (key, value) VALUES ('format_version', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+                .bind(migration::CURRENT.to_string())
+                .execute(&mut *transaction)
+                .await?;
+            transaction.commit().await?;
+            Ok(())
+        }
+    }
+
+    /// The `data` column of every row `sql` selects, binding `id` as `$1` if
+    /// given.
+    async fn records<'c, T, E>(executor: E, sql: &str, id: Option<u32>) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned + Send + Unpin,
+        E: sqlx::Executor<'c, Database = Postgres>,
+    {
+        let mut query = sqlx::query_as::<_, (Json<T>,)>(sql);
+        if let Some(id) = id {
+            query = query.bind(id as i64);
+        }
+        let rows = query.fetch_all(executor).await?;
+        Ok(rows.into_iter().map(|(Json(record),)| record).collect())
+    }
+
+    async fn record<T: DeserializeOwned + Send + Unpin>(connection: &mut PgConnection, sql: &str, id: u32) -> anyhow::Result<Option<T>> {
+        Ok(records(connection, sql, Some(id)).await?.into_iter().next())
+    }
+
+    /// Marks the store as seeded and stamps the format version, so a store
+    /// written only row by row is not taken for a fresh one on the next start.
+    async fn mark_written(connection: &mut PgConnection) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO store_meta (key, value) VALUES ('seeded', 'true'), ('format_version', $1) ON CONFLICT (key) DO NOTHING")
+            .bind(migration::CURRENT.to_string())
+            .execute(connection)
+            .await?;
+        Ok(())
+    }
+
+    /// The next ID for `key`: above every ID in `tables` and every ID handed out
+    /// before, so a deleted record's ID does not come back. The `store_meta` row
+    /// stays locked until the transaction ends, so two servers never get the
+    /// same ID.
+    async fn next_id(connection: &mut PgConnection, key: &str, tables: &[&str]) -> anyhow::Result<u32> {
+        let highest: Vec<String> = tables.iter().map(|table| format!("(SELECT COALESCE(MAX(id), 0) FROM {})", table)).collect();
+        let highest = highest.join(", ");
+        let sql = format!(
+            "INSERT INTO store_meta (key, value) VALUES ($1, (GREATEST({highest}) + 1)::text) \
+             ON CONFLICT (key) DO UPDATE SET value = (GREATEST(store_meta.value::bigint, {highest}) + 1)::text \
+             RETURNING value"
+        );
+        let (id,): (String,) = sqlx::query_as(&sql).bind(key).fetch_one(connection).await?;
+        Ok(id.parse()?)
+    }
+
+    /// Makes sure `id` is never handed out again under `key`.
+    async fn reserve_id(connection: &mut PgConnection, key: &str, id: u32) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO store_meta (key, value) VALUES ($1, $2) \
+             ON CONFLICT (key) DO UPDATE SET value = GREATEST(store_meta.value::bigint, excluded.value::bigint)::text",
+        )
+        .bind(key)
+        .bind(id.to_string())
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+
+    const CAT_IDS: &str = "last_cat_id";
+    const INCIDENT_IDS: &str = "last_incident_id";
+
+    /// Cats in the `cats` and `archived_cats` tables, one row per cat. Each
+    /// change is a transaction of its own, with the rows it reads locked.
+    pub struct PostgresCatRepository(PgPool);
+
+    impl PostgresCatRepository {
+        async fn begin(&self) -> anyhow::Result<Transaction<'static, Postgres>> {
+            Ok(self.0.begin().await?)
+        }
+
+        async fn insert_row(&self, mut cat: Cat) -> anyhow::Result<Cat> {
+            let mut transaction = self.begin().await?;
+            cat.id = next_id(&mut transaction, CAT_IDS, &["cats", "archived_cats"]).await?;
+            cat.version = 1;
+            sqlx::query("INSERT INTO cats (id, data) VALUES ($1, $2)")
+                .bind(cat.id as i64)
+                .bind(Json(&cat))
+                .execute(&mut *transaction)
+                .await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(cat)
+        }
+
+        async fn update_row(&self, mut cat: Cat) -> anyhow::Result<Option<Cat>> {
+            let mut transaction = self.begin().await?;
+            let Some(previous) = record::<Cat>(&mut transaction, "SELECT data FROM cats WHERE id = $1 FOR UPDATE", cat.id).await? else {
+                return Ok(None);
+            };
+            let expected = cat.version;
+            cat.version += 1;
+            let updated = sqlx::query("UPDATE cats SET data = $2 WHERE id = $1 AND COALESCE((data->>'version')::bigint, 0) = $3")
+                .bind(cat.id as i64)
+                .bind(Json(&cat))
+                .bind(expected as i64)
+                .execute(&mut *transaction)
+                .await?
+                .rows_affected();
+            if updated == 0 {
+                return Err(RepositoryError::Conflict { id: cat.id, current: previous.version }.into());
+            }
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(Some(previous))
+        }
+
+        async fn replace_row(&self, cat: Cat) -> anyhow::Result<Option<Cat>> {
+            let mut transaction = self.begin().await?;
+            let previous = record(&mut transaction, "SELECT data FROM cats WHERE id = $1 FOR UPDATE", cat.id).await?;
+            sqlx::query("INSERT INTO cats (id, data) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET data = excluded.data")
+                .bind(cat.id as i64)
+                .bind(Json(&cat))
+                .execute(&mut *transaction)
+                .await?;
+            reserve_id(&mut transaction, CAT_IDS, cat.id).await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(previous)
+        }
+
+        async fn delete_row(&self, id: u32) -> anyhow::Result<Option<Cat>> {
+            let mut transaction = self.begin().await?;
+            let previous: Option<Cat> = record(&mut transaction, "DELETE FROM cats WHERE id = $1 RETURNING data", id).await?;
+            if previous.is_some() {
+                reserve_id(&mut transaction, CAT_IDS, id).await?;
+                mark_written(&mut transaction).await?;
+            }
+            transaction.commit().await?;
+            Ok(previous)
+        }
+
+        async fn archive_row(&self, id: u32, deleted_at: NaiveDateTime) -> anyhow::Result<Option<ArchivedCat>> {
+            let mut transaction = self.begin().await?;
+            let Some(cat) = record::<Cat>(&mut transaction, "DELETE FROM cats WHERE id = $1 RETURNING data", id).await? else {
+                return Ok(None);
+            };
+            let entry = ArchivedCat { cat, deleted_at };
+            sqlx::query("INSERT INTO archived_cats (id, data) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET data = excluded.data")
+                .bind(id as i64)
+                .bind(Json(&entry))
+                .execute(&mut *transaction)
+                .await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(Some(entry))
+        }
+
+        async fn restore_row(&self, id: u32) -> anyhow::Result<Option<ArchivedCat>> {
+            let mut transaction = self.begin().await?;
+            let Some(entry) = record::<ArchivedCat>(&mut transaction, "DELETE FROM archived_cats WHERE id = $1 RETURNING data", id).await? else {
+                return Ok(None);
+            };
+            sqlx::query("INSERT INTO cats (id, data) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET data = excluded.data")
+                .bind(id as i64)
+                .bind(Json(&entry.cat))
+                .execute(&mut *transaction)
+                .await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(Some(entry))
+        }
+    }
+
+    impl CatRepository for PostgresCatRepository {
+        fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Cat>>> {
+            Box::pin(async move { records(&self.0, "SELECT data FROM cats ORDER BY id", None).await.map_err(super::repository_error) })
+        }
+
+        fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(async move {
+                let cats = records(&self.0, "SELECT data FROM cats WHERE id = $1", Some(id)).await.map_err(super::repository_error)?;
+                Ok(cats.into_iter().next())
+            })
+        }
+
+        fn search<'a>(&'a self, filter: &'a (dyn Fn(&Cat) -> bool + Send + Sync)) -> BoxFuture<'a, RepositoryResult<Vec<Cat>>> {
+            Box::pin(async move { Ok(self.list().await?.into_iter().filter(|cat| filter(cat)).collect()) })
+        }
+
+        fn insert(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Cat>> {
+            Box::pin(async move { self.insert_row(cat).await.map_err(super::repository_error) })
+        }
+
+        fn update(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(async move { self.update_row(cat).await.map_err(super::repository_error) })
+        }
+
+        fn replace(&self, cat: Cat) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(async move { self.replace_row(cat).await.map_err(super::repository_error) })
+        }
+
+        fn delete(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Cat>>> {
+            Box::pin(async move { self.delete_row(id).await.map_err(super::repository_error) })
+        }
+
+        fn archive(&self, id: u32, deleted_at: NaiveDateTime) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+            Box::pin(async move { self.archive_row(id, deleted_at).await.map_err(super::repository_error) })
+        }
+
+        fn restore(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<ArchivedCat>>> {
+            Box::pin(async move { self.restore_row(id).await.map_err(super::repository_error) })
+        }
+
+        fn archived(&self) -> BoxFuture<'_, RepositoryResult<Vec<ArchivedCat>>> {
+            Box::pin(async move { records(&self.0, "SELECT data FROM archived_cats ORDER BY id", None).await.map_err(super::repository_error) })
+        }
+    }
+
+    /// Incidents in the `incidents` table, one row each.
+    pub struct PostgresIncidentRepository(PgPool);
+
+    impl PostgresIncidentRepository {
+        async fn insert_row(&self, mut incident: Incident) -> anyhow::Result<Incident> {
+            let mut transaction = self.0.begin().await?;
+            incident.id = next_id(&mut transaction, INCIDENT_IDS, &["incidents"]).await?;
+            sqlx::query("INSERT INTO incidents (id, cat_id, data) VALUES ($1, $2, $3)")
+                .bind(incident.id as i64)
+                .bind(incident.cat_id as i64)
+                .bind(Json(&incident))
+                .execute(&mut *transaction)
+                .await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(incident)
+        }
+
+        async fn put_row(&self, incident: Incident) -> anyhow::Result<Option<Incident>> {
+            let mut transaction = self.0.begin().await?;
+            let previous = record(&mut transaction, "SELECT data FROM incidents WHERE id = $1 FOR UPDATE", incident.id).await?;
+            sqlx::query(
+                "INSERT INTO incidents (id, cat_id, data) VALUES ($1, $2, $3) \
+                 ON CONFLICT (id) DO UPDATE SET cat_id = excluded.cat_id, data = excluded.data",
+            )
+            .bind(incident.id as i64)
+            .bind(incident.cat_id as i64)
+            .bind(Json(&incident))
+            .execute(&mut *transaction)
+            .await?;
+            reserve_id(&mut transaction, INCIDENT_IDS, incident.id).await?;
+            mark_written(&mut transaction).await?;
+            transaction.commit().await?;
+            Ok(previous)
+        }
+
+        async fn remove_row(&self, id: u32) -> anyhow::Result<Option<Incident>> {
+            let mut transaction = self.0.begin().await?;
+            let previous: Option<Incident> = record(&mut transaction, "DELETE FROM incidents WHERE id = $1 RETURNING data", id).await?;
+            if previous.is_some() {
+                reserve_id(&mut transaction, INCIDENT_IDS, id).await?;
+                mark_written(&mut transaction).await?;
+            }
+            transaction.commit().await?;
+            Ok(previous)
+        }
+    }
+
+    impl IncidentRepository for PostgresIncidentRepository {
+        fn list(&self) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+            Box::pin(async move { records(&self.0, "SELECT data FROM incidents ORDER BY id", None).await.map_err(super::repository_error) })
+        }
+
+        fn get(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(async move {
+                let incidents = records(&self.0, "SELECT data FROM incidents WHERE id = $1", Some(id)).await.map_err(super::repository_error)?;
+                Ok(incidents.into_iter().next())
+            })
+        }
+
+        fn for_cat(&self, cat_id: u32) -> BoxFuture<'_, RepositoryResult<Vec<Incident>>> {
+            Box::pin(async move { records(&self.0, "SELECT data FROM incidents WHERE cat_id = $1 ORDER BY id", Some(cat_id)).await.map_err(super::repository_error) })
+        }
+
+        fn insert(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Incident>> {
+            Box::pin(async move { self.insert_row(incident).await.map_err(super::repository_error) })
+        }
+
+        fn put(&self, incident: Incident) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(async move { self.put_row(incident).await.map_err(super::repository_error) })
+        }
+
+        fn remove(&self, id: u32) -> BoxFuture<'_, RepositoryResult<Option<Incident>>> {
+            Box::pin(async move { self.remove_row(id).await.map_err(super::repository_error) })
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Who may call the tools that manage the server rather than the cats:
+//! maintenance mode, other clients' sessions, the audit log, the repairs,
+//! database compaction, the review queue, promoting a standby and what the
+//! server reports about itself. Sessions without admin access are also the
+//! ones whose changes wait for review when that is on (see [`crate::review`]).
+//!
+//! They are open on the transports in `CAT_SERVER_ADMIN_TRANSPORTS`
+//! (comma-separated), else `[admin] transports`, else stdio alone, since
+//! whoever talks to the server over stdio started the process. Over any other
+//! transport a call must carry `admin_token`, matching `CAT_SERVER_ADMIN_TOKEN`
+//! (else `[admin] token`); with no token configured they are refused there.
+
+use crate::config;
+use rmcp::{
+    ErrorData,
+    model::{ErrorCode, Tool},
+};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Tools that need admin access.
+pub const TOOLS: &[&str] = &[
+    "set_maintenance_mode",
+    "validation_report",
+    "server_stats",
+    "list_sessions",
+    "disconnect_session",
+    "get_audit_log",
+    "fix_orphaned_records",
+    "recompute_versions",
+    "normalize_text_fields",
+    "vacuum_database",
+    "list_pending_changes",
+    "approve_change",
+    "reject_change",
+    "promote",
+];
+
+pub const ARGUMENT: &str = "admin_token";
+
+/// Every transport name, as `list_sessions` shows them.
+const TRANSPORTS: &[&str] = &["stdio", "http", "unix"];
+
+pub struct Access {
+    token: Option<String>,
+    /// Transports where the tools need no token.
+    open_on: HashSet<String>,
+}
+
+impl Default for Access {
+    fn default() -> Self {
+        Self {
+            token: None,
+            open_on: HashSet::from(["stdio".to_string()]),
+        }
+    }
+}
+
+/// Compares in time independent of where the two differ.
+fn same(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len() && given.iter().zip(expected).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+impl Access {
+    pub fn from_config() -> anyhow::Result<Self> {
+        let section = &config::file().admin;
+        let token = std::env::var("CAT_SERVER_ADMIN_TOKEN").ok().or_else(|| section.token.clone()).filter(|token| !token.is_empty());
+        let open_on: HashSet<String> = match std::env::var("CAT_SERVER_ADMIN_TRANSPORTS") {
+            Ok(value) => value.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect(),
+            Err(_) => match &section.transports {
+                Some(names) => names.iter().cloned().collect(),
+                None => Self::default().open_on,
+            },
+        };
+        let mut unknown: Vec<&str> = open_on.iter().map(String::as_str).filter(|name| !TRANSPORTS.contains(name)).collect();
+        if !unknown.is_empty() {
+            unknown.sort();
+            anyhow::bail!("The admin configuration names transports that do not exist: {} (known: {})", unknown.join(", "), TRANSPORTS.join(", "));
+        }
+        Ok(Self { token, open_on })
+    }
+
+    /// The configured admin token, which a standby also sends its primary.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Whether a call to the admin tool `tool` over `transport`, carrying
+    /// `token`, may go ahead.
+    pub fn check(&self, tool: &str, transport: &str, token: Option<&Value>) -> Result<(), ErrorData> {
+        if self.open_on.contains(transport) {
+            return Ok(());
+        }
+        let refused = |message: String| ErrorData {
+            code: ErrorCode::INVALID_REQUEST,
+            message: message.into(),
+            data: None,
+        };
+        let Some(expected) = &self.token else {
+            return Err(refused(format!("{} is an admin tool and is not available over {}", tool, transport)));
+        };
+        match token.and_then(Value::as_str) {
+            Some(given) if same(given.as_bytes(), expected.as_bytes()) => Ok(()),
+            Some(_) => Err(refused(format!("{} does not match; {} is an admin tool", ARGUMENT, tool))),
+            None => Err(refused(format!("{} is an admin tool; over {} it needs {}", tool, transport, ARGUMENT))),
+        }
+    }
+}
+
+/// Adds the `admin_token` argument to every admin tool.
+pub fn with_token_property(tools: Vec<Tool>) -> Vec<Tool> {
+    tools
+        .into_iter()
+        .map(|mut tool| {
+            if !TOOLS.contains(&tool.name.as_ref()) {
+                return tool;
+            }
+            let mut schema = (*tool.input_schema).clone();
+            if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+                properties.insert(ARGUMENT.to_string(), serde_json::json!({
+                    "type": "string",
+                    "description": "The server's admin token. Not needed over stdio, or over any transport the server opens admin tools on"
+                }));
+            }
+            tool.input_schema = Arc::new(schema);
+            tool
+        })
+        .collect()
+}
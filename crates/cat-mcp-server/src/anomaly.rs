@@ -0,0 +1,97 @@
+//! Spots suspicious bursts of tool usage so they can be throttled and reported.
+//!
+//! Each session is counted on its own, so one misbehaving client is slowed
+//! down without refusing everyone else's calls. Admin tools are not counted.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// The `tool` of the audit log entry recording an alert.
+pub const AUDIT_TOOL: &str = "anomaly_alert";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Walking the records one ID at a time.
+    Enumeration,
+    /// Removing records.
+    Deletion,
+    /// Calls rejected with an error, e.g. probing for valid IDs.
+    Failure,
+}
+
+impl Signal {
+    /// The signal a call to `tool` counts towards, if any.
+    pub fn for_tool(tool: &str) -> Option<Self> {
+        match tool {
+            "get_cat_by_id" => Some(Signal::Enumeration),
+            "delete_cat" | "discard_draft" => Some(Signal::Deletion),
+            _ => None,
+        }
+    }
+
+    /// Events allowed per minute before calls are refused.
+    fn limit(self) -> usize {
+        match self {
+            Signal::Enumeration => 30,
+            Signal::Deletion => 10,
+            Signal::Failure => 10,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Signal::Enumeration => "record lookups",
+            Signal::Deletion => "deletions",
+            Signal::Failure => "failed calls",
+        }
+    }
+}
+
+pub struct Alert {
+    pub signal: Signal,
+    pub count: usize,
+    /// Only the call that crosses the limit should raise a notification.
+    pub newly_raised: bool,
+}
+
+#[derive(Default)]
+pub struct AnomalyDetector {
+    /// Recent events by session ID and signal.
+    events: RwLock<HashMap<(u64, Signal), VecDeque<Instant>>>,
+}
+
+impl AnomalyDetector {
+    /// Counts one event for `session`, returning an alert while the signal is
+    /// over its limit for that session.
+    pub async fn record(&self, session: u64, signal: Signal) -> Option<Alert> {
+        let now = Instant::now();
+        let mut events = self.events.write().await;
+        // Sessions that have gone quiet, or closed, are forgotten.
+        events.retain(|_, recent| recent.back().is_some_and(|at| now.duration_since(*at) <= WINDOW));
+        let recent = events.entry((session, signal)).or_default();
+        recent.retain(|at| now.duration_since(*at) <= WINDOW);
+        recent.push_back(now);
+        (recent.len() > signal.limit()).then(|| Alert {
+            signal,
+            count: recent.len(),
+            newly_raised: recent.len() == signal.limit() + 1,
+        })
+    }
+
+    /// Like [`record`](Self::record) without counting a new event.
+    pub async fn check(&self, session: u64, signal: Signal) -> Option<Alert> {
+        let now = Instant::now();
+        let mut events = self.events.write().await;
+        let recent = events.get_mut(&(session, signal))?;
+        recent.retain(|at| now.duration_since(*at) <= WINDOW);
+        (recent.len() > signal.limit()).then(|| Alert {
+            signal,
+            count: recent.len(),
+            newly_raised: false,
+        })
+    }
+}
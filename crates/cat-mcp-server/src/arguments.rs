@@ -0,0 +1,43 @@
+//! Reading record IDs out of tool arguments. IDs are `u32`, but JSON numbers
+//! arrive as `u64`: a number too large for an ID is refused rather than cut
+//! down, which would otherwise make 4294967297 address cat 1.
+
+use rmcp::{
+    ErrorData,
+    model::{ErrorCode, JsonObject},
+};
+use serde_json::Value;
+
+/// `value` as an ID; `field` names it in the error.
+pub fn id_value(field: &str, value: &Value) -> Result<u32, ErrorData> {
+    value.as_u64().and_then(|id| u32::try_from(id).ok()).ok_or_else(|| ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: format!("{} must be an ID between 0 and {}, got {}", field, u32::MAX, value).into(),
+        data: None,
+    })
+}
+
+/// The ID in `field`, or `None` when it is missing or `null`.
+pub fn id(arguments: Option<&JsonObject>, field: &str) -> Result<Option<u32>, ErrorData> {
+    match arguments.and_then(|args| args.get(field)) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => id_value(field, value).map(Some),
+    }
+}
+
+/// The ID in `field`, which must be given.
+pub fn required_id(arguments: Option<&JsonObject>, field: &str) -> Result<u32, ErrorData> {
+    id(arguments, field)?.ok_or_else(|| ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: format!("{} is required", field).into(),
+        data: None,
+    })
+}
+
+/// The IDs in the array `field`, or `None` when it is missing.
+pub fn ids(arguments: Option<&JsonObject>, field: &str) -> Result<Option<Vec<u32>>, ErrorData> {
+    match arguments.and_then(|args| args.get(field)).and_then(Value::as_array) {
+        None => Ok(None),
+        Some(values) => values.iter().map(|value| id_value(field, value)).collect::<Result<_, _>>().map(Some),
+    }
+}
@@ -0,0 +1,120 @@
+//! Append-only record of every successful mutating call: the tool, its
+//! arguments, the client that made it, and each cat (archived or not) and
+//! incident it changed, before and after. Anomaly alerts are recorded here
+//! too, as `anomaly_alert` entries that change nothing.
+//!
+//! Entries are written in the call path while the mutation still holds
+//! [`AuditLog::begin`]'s lock, so the log has every mutation, in the order
+//! they were applied, by the time the call returns.
+//!
+//! Entries are kept in memory for `get_audit_log`. With `CAT_SERVER_AUDIT_LOG`
+//! set they are also appended to that file (inside the data directory, if
+//! relative) as JSON lines, and the file's existing entries are loaded on
+//! startup.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard, RwLock};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    /// `cat` or `incident`.
+    pub record: String,
+    pub id: u32,
+    /// `null` when the record did not exist yet.
+    pub before: Value,
+    /// `null` when the record is gone.
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: NaiveDateTime,
+    pub tool: String,
+    /// The client's self-reported name, if it gave one.
+    pub client: Option<String>,
+    pub arguments: Value,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Default)]
+pub struct AuditLog {
+    entries: RwLock<Vec<Entry>>,
+    file: Option<PathBuf>,
+    /// Mutations run one at a time, so the journal of a call only shows that
+    /// call's changes; see [`crate::records::Journal`].
+    writing: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("CAT_SERVER_AUDIT_LOG") else {
+            return Self::default();
+        };
+        let path = crate::config::resolve(path);
+        let mut entries = Vec::new();
+        match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+                    match serde_json::from_str(line) {
+                        Ok(entry) => entries.push(entry),
+                        Err(e) => tracing::warn!("Ignoring line {} of audit log {}: {}", index + 1, path.display(), e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Could not read audit log {}: {}", path.display(), e),
+        }
+        Self {
+            entries: RwLock::new(entries),
+            file: Some(path),
+            writing: Mutex::new(()),
+        }
+    }
+
+    pub async fn begin(&self) -> MutexGuard<'_, ()> {
+        self.writing.lock().await
+    }
+
+    pub async fn record(&self, entry: Entry) {
+        if let Some(path) = &self.file {
+            if let Err(e) = append(path, &entry).await {
+                tracing::warn!("Could not append to audit log {}: {}", path.display(), e);
+            }
+        }
+        self.entries.write().await.push(entry);
+    }
+
+    /// Entries from `since` up to `until`, oldest first.
+    pub async fn query(&self, since: Option<NaiveDateTime>, until: Option<NaiveDateTime>, tool: Option<&str>, cat_id: Option<u32>) -> Vec<Entry> {
+        self.entries
+            .read()
+            .await
+            .iter()
+            .filter(|entry| since.is_none_or(|since| entry.at >= since))
+            .filter(|entry| until.is_none_or(|until| entry.at <= until))
+            .filter(|entry| tool.is_none_or(|tool| entry.tool == tool))
+            .filter(|entry| cat_id.is_none_or(|id| entry.changes.iter().any(|change| change.record == "cat" && change.id == id)))
+            .cloned()
+            .collect()
+    }
+
+    /// Up to `limit` entries after the first `after`, and how many there are
+    /// in all; what a standby tails (see [`crate::standby`]).
+    pub async fn since(&self, after: usize, limit: usize) -> (usize, Vec<Entry>) {
+        let entries = self.entries.read().await;
+        (entries.len(), entries.iter().skip(after).take(limit).cloned().collect())
+    }
+}
+
+async fn append(path: &PathBuf, entry: &Entry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+    file.write_all(&line).await?;
+    file.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Periodic saving for the JSON data file. Rewriting the whole file after
+//! every change is slow once there are many cats, so with `--data-file` a
+//! change only marks the state dirty and a background task writes it every
+//! few seconds, and once more on graceful shutdown. A crash loses at most one
+//! interval of edits.
+//!
+//! The interval comes from `--autosave-secs <n>`, `CAT_SERVER_AUTOSAVE_SECS` or
+//! `[storage] autosave_secs` (default 5); `0` writes after every change, as the
+//! other stores always do.
+
+use crate::config;
+use crate::store::flag_value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+pub struct Autosave {
+    pub interval: Duration,
+    dirty: AtomicBool,
+}
+
+impl Autosave {
+    /// `None` when the interval is set to 0.
+    pub fn from_args() -> Option<Self> {
+        let secs = flag_value("--autosave-secs")
+            .or_else(|| std::env::var("CAT_SERVER_AUTOSAVE_SECS").ok())
+            .and_then(|value| value.parse().ok())
+            .or(config::file().storage.autosave_secs)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        (secs > 0).then(|| Self {
+            interval: Duration::from_secs(secs),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether anything changed since the last call; clears the flag, so a
+    /// failed save must mark the state dirty again.
+    pub fn take_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,191 @@
+//! Synthetic datasets for benchmarks and load tests:
+//!
+//! ```text
+//! mcp-server-rust generate-benchmark-dataset --size 100k --out bench.json [--seed 7]
+//! ```
+//!
+//! Sizes are `1k`, `100k`, `1m` or any number of cats. The file has the
+//! `--data-file` layout, so `--data-file bench.json` serves it directly. Every
+//! record is made up; the same seed gives the same records, with incident dates
+//! counted back from the day it runs. Without `--seed`, `--seed-rng` is used,
+//! and without either the seed is 42.
+
+use crate::store::flag_value;
+use anyhow::Context;
+use cat_mcp_core::Cat;
+use cat_mcp_core::incident::{Incident, Severity};
+use chrono::{Duration, NaiveDateTime};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+
+pub const SUBCOMMAND: &str = "generate-benchmark-dataset";
+
+const NAMES: &[(&str, &str)] = &[
+    ("Tama", "タマ"),
+    ("Shiro", "シロ"),
+    ("Kuro", "クロ"),
+    ("Mike", "ミケ"),
+    ("Chatora", "チャトラ"),
+    ("Sora", "ソラ"),
+    ("Momo", "モモ"),
+    ("Kotetsu", "コテツ"),
+    ("Hana", "ハナ"),
+    ("Leo", "レオ"),
+    ("Luna", "ルナ"),
+    ("Milo", "ミロ"),
+    ("Nala", "ナラ"),
+    ("Oliver", "オリバー"),
+    ("Coco", "ココ"),
+    ("Mugi", "ムギ"),
+];
+/// Breed and how common it is in a shelter.
+const BREEDS: &[(&str, u32)] = &[
+    ("Japanese Bobtail", 8),
+    ("Mixed", 40),
+    ("Domestic shorthair", 20),
+    ("Orange tabby", 8),
+    ("Scottish Fold", 5),
+    ("American Shorthair", 6),
+    ("Russian Blue", 3),
+    ("Maine Coon", 3),
+    ("Siamese", 4),
+    ("Ragdoll", 3),
+];
+const COLORS: &[&str] = &["White", "Black", "Calico", "Orange tabby", "Brown tabby", "Gray", "Tortoiseshell", "Black and white"];
+const TOYS: &[&str] = &["Feather wand", "Catnip mouse", "Ball", "Laser pointer", "Cardboard box", "Catnip", ""];
+const TEMPERAMENTS: &[&str] = &["calm", "playful", "shy", "friendly", "energetic", "dominant", "curious", "affectionate"];
+const ALLERGIES: &[&str] = &["chicken", "fish", "beef", "dairy", "grain"];
+const DIETS: &[&str] = &["weight management", "renal", "hypoallergenic", "urinary", "senior"];
+const INCIDENT_KINDS: &[(&str, &str, u32)] = &[
+    ("scratching", "Scratched the sofa", 30),
+    ("biting", "Bit a volunteer during handling", 10),
+    ("hissing", "Hissed at a new cat", 20),
+    ("litter", "Went outside the litter box", 15),
+    ("vomiting", "Vomited after eating", 10),
+    ("escape", "Slipped out of the enclosure", 5),
+    ("fighting", "Fought with a roommate cat", 10),
+];
+
+#[derive(Serialize)]
+struct Dataset<'a> {
+    format_version: u32,
+    cats: &'a [Cat],
+    incidents: &'a [Incident],
+}
+
+fn parse_size(value: &str) -> Option<usize> {
+    let value = value.trim().to_lowercase();
+    let (digits, factor) = match value.strip_suffix('k') {
+        Some(digits) => (digits, 1_000),
+        None => match value.strip_suffix('m') {
+            Some(digits) => (digits, 1_000_000),
+            None => (value.as_str(), 1),
+        },
+    };
+    digits.parse::<usize>().ok().map(|n| n * factor)
+}
+
+/// Up to `max` distinct picks, usually none or one.
+fn some_of(rng: &mut StdRng, items: &[&str], chance: f64, max: usize) -> Vec<String> {
+    let mut count = 0;
+    while count < max && rng.gen_bool(chance) {
+        count += 1;
+    }
+    items.choose_multiple(rng, count).map(|item| item.to_string()).collect()
+}
+
+fn cat(rng: &mut StdRng, id: u32, breeds: &WeightedIndex<u32>) -> Cat {
+    let (name, variant) = NAMES.choose(rng).copied().unwrap_or(("Tama", "タマ"));
+    // Shelters see mostly young cats: roughly half under 4, few over 15.
+    let age = match rng.gen_range(0..100) {
+        0..=49 => rng.gen_range(0..4),
+        50..=89 => rng.gen_range(4..12),
+        _ => rng.gen_range(12..21),
+    };
+    let breed = BREEDS[breeds.sample(rng)].0;
+    Cat {
+        id,
+        name: name.to_string(),
+        name_variants: vec![variant.to_string()],
+        age,
+        breed: breed.to_string(),
+        color: if breed == "Orange tabby" { breed.to_string() } else { COLORS.choose(rng).copied().unwrap_or("Gray").to_string() },
+        is_indoor: rng.gen_bool(0.7),
+        favorite_toy: TOYS.choose(rng).copied().unwrap_or_default().to_string(),
+        allergies: some_of(rng, ALLERGIES, 0.15, 2),
+        prescribed_diets: some_of(rng, DIETS, if age >= 12 { 0.4 } else { 0.1 }, 2),
+        temperament: some_of(rng, TEMPERAMENTS, 0.8, 3),
+        photos: vec![],
+        version: 1,
+    }
+}
+
+/// Most cats have no incidents; a few have many. Times fall in the past year,
+/// more often in the evening.
+fn incidents_for(rng: &mut StdRng, cat_id: u32, next_id: &mut u32, kinds: &WeightedIndex<u32>, now: NaiveDateTime) -> Vec<Incident> {
+    let count = match rng.gen_range(0..100) {
+        0..=54 => 0,
+        55..=79 => 1,
+        80..=91 => 2,
+        _ => rng.gen_range(3..8),
+    };
+    (0..count)
+        .map(|_| {
+            let (kind, description, _) = INCIDENT_KINDS[kinds.sample(rng)];
+            let hour = if rng.gen_bool(0.6) { rng.gen_range(17..24) } else { rng.gen_range(6..17) };
+            let day = now.date() - Duration::days(rng.gen_range(0..365));
+            let datetime = day.and_hms_opt(hour, rng.gen_range(0..60), 0).unwrap_or(now);
+            let severity = match rng.gen_range(0..10) {
+                0..=5 => Severity::Low,
+                6..=8 => Severity::Medium,
+                _ => Severity::High,
+            };
+            *next_id += 1;
+            Incident {
+                id: *next_id,
+                cat_id,
+                datetime,
+                kind: kind.to_string(),
+                severity,
+                description: description.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Runs the subcommand with the flags on the command line.
+pub fn run() -> anyhow::Result<()> {
+    let size = flag_value("--size").unwrap_or_else(|| "1k".to_string());
+    let cats = parse_size(&size).with_context(|| format!("--size {} is not 1k, 100k, 1m or a number", size))?;
+    let out = crate::config::resolve(flag_value("--out").context("--out <path> is required")?);
+    let seed = match flag_value("--seed") {
+        Some(seed) => seed.parse().with_context(|| format!("--seed {} is not a number", seed))?,
+        None => crate::rng::from_args()?.unwrap_or(42),
+    };
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let breeds = WeightedIndex::new(BREEDS.iter().map(|(_, weight)| *weight))?;
+    let kinds = WeightedIndex::new(INCIDENT_KINDS.iter().map(|(_, _, weight)| *weight))?;
+    let now = chrono::Local::now().naive_local();
+    let mut all_cats = Vec::with_capacity(cats);
+    let mut incidents = Vec::new();
+    let mut incident_id = 0;
+    for id in 1..=cats as u32 {
+        all_cats.push(cat(&mut rng, id, &breeds));
+        incidents.extend(incidents_for(&mut rng, id, &mut incident_id, &kinds, now));
+    }
+
+    let mut file = BufWriter::new(std::fs::File::create(&out).with_context(|| format!("Could not create {}", out.display()))?);
+    serde_json::to_writer(&mut file, &Dataset {
+        format_version: crate::migration::CURRENT,
+        cats: &all_cats,
+        incidents: &incidents,
+    })?;
+    file.flush()?;
+    println!("Wrote {} cats and {} incidents to {} (seed {})", all_cats.len(), incidents.len(), out.display(), seed);
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Short-lived cache of read-only tool results, so an agent retrying a call or
+//! asking the same question twice in one turn gets the answer without the
+//! lookup running again.
+//!
+//! Entries are keyed by the tool and its arguments after coercion, with keys
+//! sorted and `null` fields dropped, so `{"a": 1, "b": null}` and `{"a": 1}`
+//! share an entry. They live for `CAT_SERVER_CACHE_TTL_SECS` seconds, else
+//! `[cache] ttl_secs`, else [`DEFAULT_TTL`]; 0 turns the cache off.
+//!
+//! Every call that changes anything, waitlists and drafts included, empties the
+//! cache before it returns. This happens in the call path rather than from the
+//! event bus, which may deliver late or drop changes: a client must never read
+//! a result from before its own change.
+
+use crate::config;
+use anyhow::Context;
+use rmcp::model::{Content, JsonObject};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Tools whose result depends only on their arguments and the stored records.
+/// `list_all_cats` and `search_cats` are left out: they answer with what
+/// changed since the same connection's last call.
+pub const TOOLS: &[&str] = &[
+    "get_cat_by_id",
+    "get_indoor_cats",
+    "search_by_name",
+    "global_search",
+    "list_incidents",
+    "list_archived_cats",
+    "list_waitlist",
+    "behavior_patterns",
+    "check_food_compatibility",
+    "suggest_companion",
+    "match_adopters",
+    "find_similar_cats",
+];
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5);
+/// The most results kept; past it, expired ones are dropped, then all of them.
+const MAX_ENTRIES: usize = 512;
+
+struct Entry {
+    /// The normalized call, to tell apart calls whose keys collide.
+    call: String,
+    stored_at: Instant,
+    content: Vec<Content>,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<u64, Entry>,
+    /// Bumped by every invalidation, so a lookup that started before a change
+    /// cannot store its result after it.
+    generation: u64,
+}
+
+pub struct QueryCache {
+    ttl: Duration,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// A lookup that missed, to hand back to [`QueryCache::store`].
+pub struct Miss {
+    key: u64,
+    call: String,
+    generation: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub ttl_secs: u64,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// `value` with object keys sorted and `null` fields dropped, at every level.
+fn normalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<&String, Value> = map.iter().filter(|(_, value)| !value.is_null()).map(|(key, value)| (key, normalize(value))).collect();
+            Value::Object(sorted.into_iter().map(|(key, value)| (key.clone(), value)).collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(normalize).collect()),
+        other => other.clone(),
+    }
+}
+
+impl QueryCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn from_config() -> anyhow::Result<Self> {
+        let secs = match std::env::var("CAT_SERVER_CACHE_TTL_SECS") {
+            Ok(value) => value.trim().parse().with_context(|| format!("CAT_SERVER_CACHE_TTL_SECS must be a whole number of seconds, got {:?}", value))?,
+            Err(_) => config::file().cache.ttl_secs.unwrap_or(DEFAULT_TTL.as_secs()),
+        };
+        Ok(Self::new(Duration::from_secs(secs)))
+    }
+
+    /// The cached result of `tool` with `arguments`, or what to store it under.
+    /// `None` for tools that are not cached, or with the cache off.
+    pub fn lookup(&self, tool: &str, arguments: Option<&JsonObject>) -> Option<Result<Vec<Content>, Miss>> {
+        if self.ttl.is_zero() || !TOOLS.contains(&tool) {
+            return None;
+        }
+        let arguments = arguments.map(|arguments| normalize(&Value::Object(arguments.clone()))).unwrap_or(Value::Null);
+        let call = format!("{} {}", tool, arguments);
+        let mut hasher = DefaultHasher::new();
+        call.hash(&mut hasher);
+        let key = hasher.finish();
+
+        let entries = self.entries.lock().expect("query cache poisoned");
+        match entries.by_key.get(&key) {
+            Some(entry) if entry.call == call && entry.stored_at.elapsed() < self.ttl => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(Ok(entry.content.clone()))
+            }
+            _ => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Some(Err(Miss { key, call, generation: entries.generation }))
+            }
+        }
+    }
+
+    /// Keeps `content` for the call that missed, unless the records changed
+    /// since the lookup.
+    pub fn store(&self, miss: Miss, content: &[Content]) {
+        let mut entries = self.entries.lock().expect("query cache poisoned");
+        if entries.generation != miss.generation {
+            return;
+        }
+        if entries.by_key.len() >= MAX_ENTRIES {
+            let ttl = self.ttl;
+            entries.by_key.retain(|_, entry| entry.stored_at.elapsed() < ttl);
+            if entries.by_key.len() >= MAX_ENTRIES {
+                entries.by_key.clear();
+            }
+        }
+        entries.by_key.insert(miss.key, Entry {
+            call: miss.call,
+            stored_at: Instant::now(),
+            content: content.to_vec(),
+        });
+    }
+
+    /// Forgets every result, after a change to what they were computed from.
+    pub fn invalidate(&self) {
+        let mut entries = self.entries.lock().expect("query cache poisoned");
+        entries.generation += 1;
+        entries.by_key.clear();
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            ttl_secs: self.ttl.as_secs(),
+            entries: self.entries.lock().expect("query cache poisoned").by_key.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
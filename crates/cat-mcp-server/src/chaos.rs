@@ -0,0 +1,131 @@
+//! `--chaos`: a development mode for testing MCP clients against a flaky
+//! server. Each tool call may be delayed, fail with a transient error before it
+//! runs (so retrying is safe), or succeed but come back with malformed content.
+//!
+//! Rates come from `CAT_SERVER_CHAOS_FILE`, a JSON file such as
+//! `{"error_rate": 0.2, "tools": {"add_cat": {"max_latency_ms": 5000}}}`. A
+//! tool's entry replaces the top-level settings for that tool; anything left out
+//! takes the defaults below. `--chaos-seed <n>`, or else `--seed-rng <n>`, makes
+//! the faults repeatable.
+
+use crate::store::flag_value;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rmcp::{
+    ErrorData,
+    model::{Content, ErrorCode},
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Faults {
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    /// Share of calls, 0-1, refused with a transient error.
+    pub error_rate: f64,
+    /// Share of successful calls, 0-1, whose content is mangled.
+    pub malformed_rate: f64,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Self {
+            min_latency_ms: 50,
+            max_latency_ms: 1500,
+            error_rate: 0.1,
+            malformed_rate: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChaosConfig {
+    #[serde(flatten)]
+    faults: Faults,
+    #[serde(default)]
+    tools: HashMap<String, Faults>,
+}
+
+pub struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl Chaos {
+    /// `Some` when the server was started with `--chaos`; `seed` is the
+    /// `--seed-rng` seed.
+    pub fn from_args(seed: Option<u64>) -> Option<Self> {
+        if !std::env::args().skip(1).any(|arg| arg == "--chaos") {
+            return None;
+        }
+        let config = match std::env::var("CAT_SERVER_CHAOS_FILE") {
+            Ok(path) => std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<ChaosConfig>(&text).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Ignoring chaos file {}: {}", path, e);
+                    ChaosConfig::default()
+                }),
+            Err(_) => ChaosConfig::default(),
+        };
+        let rng = crate::rng::seeded(flag_value("--chaos-seed").and_then(|seed| seed.parse().ok()).or(seed));
+        Some(Self {
+            config,
+            rng: Mutex::new(rng),
+        })
+    }
+
+    fn faults(&self, tool: &str) -> &Faults {
+        self.config.tools.get(tool).unwrap_or(&self.config.faults)
+    }
+
+    fn chance(&self, rate: f64) -> bool {
+        self.rng.lock().expect("chaos rng poisoned").gen_bool(rate.clamp(0.0, 1.0))
+    }
+
+    /// Waits a random time, then maybe fails the call.
+    pub async fn before_call(&self, tool: &str) -> Result<(), ErrorData> {
+        let faults = self.faults(tool);
+        let latency = {
+            let mut rng = self.rng.lock().expect("chaos rng poisoned");
+            rng.gen_range(faults.min_latency_ms..=faults.max_latency_ms.max(faults.min_latency_ms))
+        };
+        tokio::time::sleep(Duration::from_millis(latency)).await;
+        if self.chance(faults.error_rate) {
+            tracing::info!("Chaos: failing {} after {}ms", tool, latency);
+            return Err(ErrorData {
+                code: ErrorCode::INTERNAL_ERROR,
+                message: "Temporarily unavailable (simulated by --chaos); retry the call".into(),
+                data: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Maybe replaces a successful result with one of: the first text cut off
+    /// halfway, no content at all, or text that is not what the tool returns.
+    pub fn after_call(&self, tool: &str, content: Vec<Content>) -> Vec<Content> {
+        if !self.chance(self.faults(tool).malformed_rate) {
+            return content;
+        }
+        let kind = self.rng.lock().expect("chaos rng poisoned").gen_range(0..3);
+        tracing::info!("Chaos: mangling the result of {}", tool);
+        match kind {
+            0 => content
+                .iter()
+                .filter_map(|content| content.as_text())
+                .map(|text| {
+                    let cut = text.text.char_indices().nth(text.text.chars().count() / 2).map_or(0, |(i, _)| i);
+                    Content::text(text.text[..cut].to_string())
+                })
+                .take(1)
+                .collect(),
+            1 => Vec::new(),
+            _ => vec![Content::text("{\"cats\": [{\"id\": \"\u{FFFD}\", \"name\": null,")],
+        }
+    }
+}
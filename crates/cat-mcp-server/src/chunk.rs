@@ -0,0 +1,156 @@
+//! Ranged reads, for clients that cannot take a large resource in one message.
+//!
+//! Any resource URI accepts `?offset=<byte>&length=<bytes>`. Each of the
+//! resource's contents is then cut to that range of its UTF-8 text, and the
+//! URI sent back names the range actually served and the full size, e.g.
+//! `export://cats.json?offset=0&length=65536&total=181234`. Clients read from
+//! `offset + length` until that reaches `total`.
+
+use rmcp::{
+    ErrorData,
+    model::{ErrorCode, ResourceContents},
+};
+
+/// Bytes served when `length` is left out.
+pub const DEFAULT_LENGTH: usize = 64 * 1024;
+/// The largest `length` accepted.
+pub const MAX_LENGTH: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Range {
+    pub offset: usize,
+    pub length: usize,
+}
+
+fn invalid(message: String) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: message.into(),
+        data: None,
+    }
+}
+
+/// Splits a URI into the resource it names and the range asked for, if any.
+pub fn parse(uri: &str) -> Result<(&str, Option<Range>), ErrorData> {
+    let Some((base, query)) = uri.split_once('?') else {
+        return Ok((uri, None));
+    };
+    let (mut offset, mut length) = (None, None);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let number = || value.parse::<usize>().map_err(|_| invalid(format!("{} must be a whole number of bytes, got \"{}\"", key, value)));
+        match key {
+            "offset" => offset = Some(number()?),
+            "length" => length = Some(number()?),
+            // Echoed back in chunk URIs, so clients can pass them straight on.
+            "total" => {}
+            other => return Err(invalid(format!("Unknown parameter \"{}\" in {}; expected offset and length", other, uri))),
+        }
+    }
+    if offset.is_none() && length.is_none() {
+        return Ok((base, None));
+    }
+    let length = length.unwrap_or(DEFAULT_LENGTH);
+    if length == 0 || length > MAX_LENGTH {
+        return Err(invalid(format!("length must be between 1 and {} bytes, got {}", MAX_LENGTH, length)));
+    }
+    Ok((base, Some(Range { offset: offset.unwrap_or(0), length })))
+}
+
+/// The part of `text` in `range`. The end is pulled back to a character
+/// boundary, so a chunk may come out a few bytes short of `length`.
+fn slice(text: &str, range: Range) -> Result<&str, ErrorData> {
+    if range.offset > text.len() {
+        return Err(invalid(format!("offset {} is past the end ({} bytes)", range.offset, text.len())));
+    }
+    if !text.is_char_boundary(range.offset) {
+        return Err(invalid(format!("offset {} falls inside a character; continue from the offset and length of the previous chunk", range.offset)));
+    }
+    let mut end = range.offset.saturating_add(range.length).min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == range.offset && end < text.len() {
+        return Err(invalid(format!("length {} is too short for the character at offset {}", range.length, range.offset)));
+    }
+    Ok(&text[range.offset..end])
+}
+
+/// Cuts every text content of a resource to `range`.
+pub fn apply(base: &str, contents: Vec<ResourceContents>, range: Range) -> Result<Vec<ResourceContents>, ErrorData> {
+    contents
+        .into_iter()
+        .map(|content| match content {
+            ResourceContents::TextResourceContents { mime_type, text, .. } => {
+                let chunk = slice(&text, range)?;
+                Ok(ResourceContents::TextResourceContents {
+                    uri: format!("{}?offset={}&length={}&total={}", base, range.offset, chunk.len(), text.len()),
+                    mime_type,
+                    text: chunk.to_string(),
+                })
+            }
+            other => Ok(other),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(offset: usize, length: usize) -> Range {
+        Range { offset, length }
+    }
+
+    fn chunk(text: &str, range: Range) -> Result<(String, String), ErrorData> {
+        let contents = vec![ResourceContents::TextResourceContents {
+            uri: "export://cats.json".to_string(),
+            mime_type: None,
+            text: text.to_string(),
+        }];
+        match apply("export://cats.json", contents, range)?.remove(0) {
+            ResourceContents::TextResourceContents { uri, text, .. } => Ok((uri, text)),
+            other => panic!("expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_uri_without_a_range_is_read_whole() {
+        assert!(matches!(parse("export://cats.json"), Ok(("export://cats.json", None))));
+        assert!(matches!(parse("export://cats.json?total=10"), Ok(("export://cats.json", None))));
+    }
+
+    #[test]
+    fn offset_and_length_default_independently() {
+        let (_, only_offset) = parse("cats://all?offset=10").expect("the range parses");
+        let only_offset = only_offset.expect("a range was asked for");
+        assert_eq!((only_offset.offset, only_offset.length), (10, DEFAULT_LENGTH));
+        let (base, only_length) = parse("cats://all?length=5&total=99").expect("the range parses");
+        let only_length = only_length.expect("a range was asked for");
+        assert_eq!((base, only_length.offset, only_length.length), ("cats://all", 0, 5));
+    }
+
+    #[test]
+    fn bad_ranges_are_refused() {
+        assert!(parse("cats://all?length=0").is_err());
+        assert!(parse(&format!("cats://all?length={}", MAX_LENGTH + 1)).is_err());
+        assert!(parse("cats://all?offset=-1").is_err());
+        assert!(parse("cats://all?page=2").is_err());
+    }
+
+    #[test]
+    fn chunks_name_the_range_served_and_the_total() {
+        assert_eq!(chunk("abcdef", range(2, 3)).expect("in range"), ("export://cats.json?offset=2&length=3&total=6".to_string(), "cde".to_string()));
+        assert_eq!(chunk("abcdef", range(4, 10)).expect("in range").1, "ef");
+        assert_eq!(chunk("abcdef", range(6, 10)).expect("at the end").1, "");
+        assert!(chunk("abcdef", range(7, 1)).is_err());
+    }
+
+    #[test]
+    fn chunks_end_on_a_character_boundary() {
+        // "ね" and "こ" are three bytes each.
+        assert_eq!(chunk("ねこcat", range(0, 4)).expect("in range"), ("export://cats.json?offset=0&length=3&total=9".to_string(), "ね".to_string()));
+        assert!(chunk("ねこcat", range(1, 4)).is_err(), "an offset inside a character is refused");
+        assert!(chunk("ねこcat", range(0, 2)).is_err(), "a length too short for one character is refused");
+    }
+}
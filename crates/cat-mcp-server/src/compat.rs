@@ -0,0 +1,92 @@
+//! Workarounds for specific clients, picked by the client info sent in the handshake.
+//!
+//! Nothing applies unless `CAT_SERVER_COMPAT_FILE` names a JSON object mapping a
+//! client name (optionally `name@version-prefix`) to a list of shims, e.g.
+//! `{"old-desktop-client@0.": ["merge_content", "compact_schemas"]}`.
+
+use crate::deprecation;
+use rmcp::model::{Content, Implementation, Tool};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shim {
+    /// Join all text parts into one block, for clients that only show the first.
+    /// Parts that are not text (images, resources) follow it unchanged.
+    MergeContent,
+    /// Drop property descriptions from input schemas, for clients that choke on large schemas.
+    CompactSchemas,
+    /// Leave deprecated aliases out of the tool list.
+    HideDeprecated,
+}
+
+#[derive(Default)]
+pub struct Compatibility {
+    rules: HashMap<String, Vec<Shim>>,
+}
+
+impl Compatibility {
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("CAT_SERVER_COMPAT_FILE") else {
+            return Self::default();
+        };
+        let rules = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str::<HashMap<String, Vec<Shim>>>(&text).map_err(|e| e.to_string()));
+        match rules {
+            Ok(rules) => Self {
+                rules: rules.into_iter().map(|(client, shims)| (client.to_lowercase(), shims)).collect(),
+            },
+            Err(e) => {
+                tracing::warn!("Ignoring compatibility file {}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Shims configured for this client; empty when it is unknown or no table is loaded.
+    pub fn shims_for(&self, client: Option<&Implementation>) -> Vec<Shim> {
+        let Some(client) = client else {
+            return Vec::new();
+        };
+        let name = client.name.to_lowercase();
+        self.rules
+            .iter()
+            .filter(|(key, _)| match key.split_once('@') {
+                Some((key_name, version)) => key_name == name && client.version.starts_with(version),
+                None => **key == name,
+            })
+            .flat_map(|(_, shims)| shims.iter().copied())
+            .collect()
+    }
+}
+
+pub fn adjust_tools(shims: &[Shim], tools: Vec<Tool>) -> Vec<Tool> {
+    tools
+        .into_iter()
+        .filter(|tool| !shims.contains(&Shim::HideDeprecated) || deprecation::resolve(&tool.name).is_none())
+        .map(|mut tool| {
+            if shims.contains(&Shim::CompactSchemas) {
+                let mut schema = (*tool.input_schema).clone();
+                if let Some(serde_json::Value::Object(properties)) = schema.get_mut("properties") {
+                    for property in properties.values_mut().filter_map(|p| p.as_object_mut()) {
+                        property.remove("description");
+                    }
+                }
+                tool.input_schema = Arc::new(schema);
+            }
+            tool
+        })
+        .collect()
+}
+
+pub fn adjust_content(shims: &[Shim], content: Vec<Content>) -> Vec<Content> {
+    if !shims.contains(&Shim::MergeContent) || content.iter().filter(|item| item.as_text().is_some()).count() < 2 {
+        return content;
+    }
+    let (text, other): (Vec<Content>, Vec<Content>) = content.into_iter().partition(|item| item.as_text().is_some());
+    let merged: Vec<&str> = text.iter().filter_map(|item| item.as_text()).map(|text| text.text.as_str()).collect();
+    std::iter::once(Content::text(merged.join("\n\n"))).chain(other).collect()
+}
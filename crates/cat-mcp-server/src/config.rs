@@ -0,0 +1,356 @@
+//! Server configuration: where the server keeps its files, and the optional
+//! configuration file.
+//!
+//! The data directory is `--data-dir`, else `MCP_CAT_DATA_DIR`, else
+//! `[storage] data_dir` in the configuration file, else the
+//! platform's data directory (`$XDG_DATA_HOME/mcp-cat-server`, usually
+//! `~/.local/share/mcp-cat-server`, on Linux; `~/Library/Application
+//! Support/mcp-cat-server` on macOS; `%APPDATA%\mcp-cat-server` on Windows).
+//! Relative paths given for the store, the seed file, the audit log and
+//! benchmark output are taken inside it; absolute paths are used as they are.
+//!
+//! Deployments can also be set up with a TOML file given with `--config`; see
+//! [`FileConfig`]. `--profile` picks one of the built-in [`PROFILES`] to start
+//! from, and the file given with `--config` is laid over it.
+
+use crate::store::flag_value;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const APP_DIR: &str = "mcp-cat-server";
+
+pub fn data_dir() -> PathBuf {
+    flag_value("--data-dir")
+        .or_else(|| std::env::var("MCP_CAT_DATA_DIR").ok())
+        .or_else(|| file().storage.data_dir.clone())
+        .map(PathBuf::from)
+        .or_else(|| dirs::data_dir().map(|dir| dir.join(APP_DIR)))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `path` inside the data directory, unless it is absolute. The directories
+/// leading to it are created if need be.
+pub fn resolve(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let resolved = data_dir().join(path);
+    if let Some(parent) = resolved.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            tracing::warn!("Could not create {}: {}", parent.display(), e);
+        }
+    }
+    resolved
+}
+
+/// `path`, as a client gave it, inside the data directory. Unlike [`resolve`],
+/// nothing outside the data directory is reachable: an absolute path, `..`
+/// or a symlink that leads out of it is refused, so a tool taking a path
+/// cannot be used to read the server's other files.
+pub fn resolve_inside(path: &str) -> Result<PathBuf, String> {
+    let root = data_dir();
+    let root = root.canonicalize().map_err(|e| format!("The data directory {} is not readable: {}", root.display(), e))?;
+    let resolved = root.join(path).canonicalize().map_err(|e| format!("Could not open {} in the data directory: {}", path, e))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!("{} is outside the data directory; only files inside it can be read", path));
+    }
+    Ok(resolved)
+}
+
+/// The contents of the file named by `--config` (or `CAT_SERVER_CONFIG`),
+/// over the profile's. Every section and setting is optional; flags and
+/// environment variables win over it, and it wins over the built-in defaults.
+///
+/// ```toml
+/// [server]
+/// name = "shelter-cats"
+/// version = "2.1.0"
+/// instructions = "Cats available at the Shibuya shelter."
+///
+/// [tools]
+/// disabled = ["delete_cat", "import_cats_csv"]   # or: enabled = [...]
+///
+/// [transport]
+/// stdio = false
+/// http = "0.0.0.0:8000"
+///
+/// [storage]
+/// backend = "sqlite"    # memory, sqlite, json or postgres
+/// path = "cats.sqlite"
+///
+/// [logging]
+/// level = "info,mcp_server_rust=debug"
+///
+/// [features]
+/// disabled = ["unix-socket"]   # see crate::features
+///
+/// [admin]
+/// token = "..."               # see crate::admin
+/// transports = ["stdio"]
+///
+/// [matchmaking]
+/// home_type = 50    # weights of the match_adopters score
+/// other_pets = 20
+/// activity_level = 30
+///
+/// [cache]
+/// ttl_secs = 5      # see crate::cache; 0 turns it off
+///
+/// [review]
+/// enabled = true    # see crate::review
+///
+/// [seed]
+/// samples = true    # or: file = "shelter.json"; see crate::seed
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub server: ServerSection,
+    pub tools: ToolsSection,
+    pub transport: TransportSection,
+    pub storage: StorageSection,
+    pub logging: LoggingSection,
+    pub features: FeaturesSection,
+    pub matchmaking: MatchmakingSection,
+    pub admin: AdminSection,
+    pub cache: CacheSection,
+    pub review: ReviewSection,
+    pub seed: SeedSection,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub instructions: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsSection {
+    /// Only these tools are offered; all of them when unset.
+    pub enabled: Option<Vec<String>>,
+    pub disabled: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportSection {
+    pub stdio: Option<bool>,
+    pub http: Option<String>,
+    pub unix_socket: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Memory,
+    Sqlite,
+    Json,
+    Postgres,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSection {
+    pub backend: Option<Backend>,
+    /// The SQLite database or JSON file.
+    pub path: Option<String>,
+    /// The PostgreSQL connection string.
+    pub url: Option<String>,
+    pub data_dir: Option<String>,
+    pub autosave_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSection {
+    /// A `RUST_LOG`-style filter, used when `RUST_LOG` is not set.
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesSection {
+    /// Compiled-in subsystems to leave off, by Cargo feature name.
+    pub disabled: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatchmakingSection {
+    pub home_type: Option<i32>,
+    pub other_pets: Option<i32>,
+    pub activity_level: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSection {
+    /// The `admin_token` admin tools need over transports not in `transports`.
+    pub token: Option<String>,
+    /// Transports where admin tools need no token; stdio alone when unset.
+    pub transports: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSection {
+    /// How long read-only tool results are reused; 0 turns caching off.
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReviewSection {
+    /// Whether changes from sessions without admin access wait for review.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeedSection {
+    /// Start with the sample cats, like `--init-sample-data`.
+    pub samples: Option<bool>,
+    /// Start with the cats in this file, like `--seed`.
+    pub file: Option<String>,
+}
+
+/// Configurations for different audiences, picked with `--profile` (or
+/// `CAT_SERVER_PROFILE`). Each is a configuration file the `--config` file is
+/// laid over.
+pub const PROFILES: &[(&str, &str)] = &[
+    ("demo", include_str!("../profiles/demo.toml")),
+    ("shelter", include_str!("../profiles/shelter.toml")),
+    ("personal", include_str!("../profiles/personal.toml")),
+];
+
+static FILE: OnceLock<FileConfig> = OnceLock::new();
+
+/// `over` laid over `under`: a setting in both comes from `over`, and tables
+/// in both are merged the same way.
+fn merge(under: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (under.get_mut(&key), value) {
+            (Some(toml::Value::Table(under)), toml::Value::Table(over)) => merge(under, over),
+            (_, value) => {
+                under.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Reads the profile and the configuration file, if either is given. Call
+/// before anything reads [`file`].
+pub fn load() -> anyhow::Result<()> {
+    let profile = flag_value("--profile").or_else(|| std::env::var("CAT_SERVER_PROFILE").ok());
+    let path = flag_value("--config").or_else(|| std::env::var("CAT_SERVER_CONFIG").ok());
+    let mut sources = Vec::new();
+    let mut table = toml::Table::new();
+    if let Some(name) = profile {
+        let Some((_, text)) = PROFILES.iter().find(|(profile, _)| *profile == name) else {
+            let known: Vec<&str> = PROFILES.iter().map(|(profile, _)| *profile).collect();
+            anyhow::bail!("There is no profile {} (known: {})", name, known.join(", "));
+        };
+        table = toml::from_str(text).with_context(|| format!("Profile {} is not valid", name))?;
+        sources.push(format!("profile {}", name));
+    }
+    if let Some(path) = path {
+        let text = std::fs::read_to_string(&path).with_context(|| format!("Could not read config file {}", path))?;
+        merge(&mut table, toml::from_str(&text).with_context(|| format!("Config file {} is not valid", path))?);
+        sources.push(format!("config file {}", path));
+    }
+    if sources.is_empty() {
+        return Ok(());
+    }
+    let config: FileConfig = toml::Value::Table(table).try_into().with_context(|| format!("The configuration from {} is not valid", sources.join(" and ")))?;
+    let _ = FILE.set(config);
+    Ok(())
+}
+
+/// The loaded configuration file, or an empty one.
+pub fn file() -> &'static FileConfig {
+    FILE.get_or_init(FileConfig::default)
+}
+
+/// The log filter: `RUST_LOG`, else `[logging] level`, else `info`.
+pub fn log_filter() -> tracing_subscriber::EnvFilter {
+    tracing_subscriber::EnvFilter::try_from_default_env()
+        .ok()
+        .or_else(|| file().logging.level.as_deref().and_then(|level| level.parse().ok()))
+        .unwrap_or_else(|| tracing_subscriber::EnvFilter::new("info"))
+}
+
+/// What the server calls itself when a client connects.
+pub struct Identity {
+    pub name: String,
+    pub version: String,
+    pub instructions: String,
+}
+
+impl Identity {
+    /// `CAT_SERVER_NAME`, `CAT_SERVER_VERSION` and `CAT_SERVER_INSTRUCTIONS`,
+    /// else `[server]`, else the given defaults.
+    pub fn from_config(name: &str, version: &str, instructions: &str) -> Self {
+        let server = &file().server;
+        let setting = |variable: &str, configured: &Option<String>, default: &str| {
+            std::env::var(variable)
+                .ok()
+                .or_else(|| configured.clone())
+                .unwrap_or_else(|| default.to_string())
+        };
+        Self {
+            name: setting("CAT_SERVER_NAME", &server.name, name),
+            version: setting("CAT_SERVER_VERSION", &server.version, version),
+            instructions: setting("CAT_SERVER_INSTRUCTIONS", &server.instructions, instructions),
+        }
+    }
+}
+
+/// Which tools are offered: `CAT_SERVER_ENABLED_TOOLS` and
+/// `CAT_SERVER_DISABLED_TOOLS` (comma-separated), else `[tools]`.
+#[derive(Debug, Default)]
+pub struct ToolFilter {
+    enabled: Option<HashSet<String>>,
+    disabled: HashSet<String>,
+}
+
+fn tool_list(variable: &str) -> Option<Vec<String>> {
+    std::env::var(variable).ok().map(|value| {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+impl ToolFilter {
+    pub fn from_config() -> Self {
+        let tools = &file().tools;
+        Self {
+            enabled: tool_list("CAT_SERVER_ENABLED_TOOLS")
+                .or_else(|| tools.enabled.clone())
+                .map(|names| names.into_iter().collect()),
+            disabled: tool_list("CAT_SERVER_DISABLED_TOOLS")
+                .unwrap_or_else(|| tools.disabled.clone())
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    pub fn allows(&self, tool: &str) -> bool {
+        self.enabled.as_ref().is_none_or(|enabled| enabled.contains(tool)) && !self.disabled.contains(tool)
+    }
+
+    /// Every tool named in the filter, to check against the real ones.
+    pub fn named(&self) -> impl Iterator<Item = &str> {
+        self.enabled.iter().flatten().chain(&self.disabled).map(String::as_str)
+    }
+}
@@ -0,0 +1,45 @@
+//! One client connection. The cat data lives in a [`CatServer`] shared by every
+//! connection on every transport; what is specific to one client (its session
+//! log, the listings it was last sent, and the changes it can undo) lives here.
+
+use crate::CatServer;
+use crate::session::SessionLog;
+use crate::undo::UndoStack;
+use cat_mcp_core::Cat;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub struct Connection {
+    server: Arc<CatServer>,
+    /// Its session ID in `CatServer::connections`.
+    pub id: u64,
+    /// `stdio`, `http` or `unix`.
+    pub transport: &'static str,
+    pub session: RwLock<SessionLog>,
+    /// The last listing sent for each listing call, for `listing_delta`.
+    pub listing_snapshots: RwLock<HashMap<String, BTreeMap<u32, Cat>>>,
+    pub undo: RwLock<UndoStack>,
+}
+
+impl Connection {
+    pub fn new(server: Arc<CatServer>, transport: &'static str) -> Self {
+        Self {
+            id: server.connections.next_id(),
+            transport,
+            server,
+            session: RwLock::new(SessionLog::new()),
+            listing_snapshots: RwLock::new(HashMap::new()),
+            undo: RwLock::new(UndoStack::default()),
+        }
+    }
+}
+
+impl Deref for Connection {
+    type Target = CatServer;
+
+    fn deref(&self) -> &CatServer {
+        &self.server
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Cat;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What changed in a listing since the previous identical call.
+#[derive(Debug, Serialize)]
+pub struct ListingDelta<'a> {
+    pub added: Vec<&'a Cat>,
+    pub changed: Vec<&'a Cat>,
+    pub removed: Vec<u32>,
+}
+
+impl ListingDelta<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+pub fn snapshot(cats: &[&Cat]) -> BTreeMap<u32, Cat> {
+    cats.iter().map(|cat| (cat.id, (*cat).clone())).collect()
+}
+
+pub fn diff<'a>(previous: &BTreeMap<u32, Cat>, current: &[&'a Cat]) -> ListingDelta<'a> {
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for cat in current {
+        match previous.get(&cat.id) {
+            None => added.push(*cat),
+            Some(before) if before != *cat => changed.push(*cat),
+            Some(_) => {}
+        }
+    }
+
+    let removed = previous
+        .keys()
+        .filter(|id| !current.iter().any(|cat| cat.id == **id))
+        .copied()
+        .collect();
+
+    ListingDelta { added, changed, removed }
+}
+
+/// Identifies "the same call": tool name plus arguments, ignoring `full`.
+pub fn listing_key(tool: &str, arguments: Option<&serde_json::Map<String, serde_json::Value>>) -> String {
+    let mut arguments = arguments.cloned().unwrap_or_default();
+    arguments.remove("full");
+    format!("{}:{}", tool, serde_json::Value::Object(arguments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cat(id: u32, name: &str) -> Cat {
+        Cat {
+            id,
+            name: name.to_string(),
+            name_variants: vec![],
+            age: 3,
+            breed: "Persian".to_string(),
+            color: "White".to_string(),
+            is_indoor: true,
+            favorite_toy: String::new(),
+            allergies: vec![],
+            prescribed_diets: vec![],
+            temperament: vec![],
+            photos: vec![],
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn reports_added_changed_and_removed_cats() {
+        let (mochi, tama, kuro) = (cat(1, "Mochi"), cat(2, "Tama"), cat(3, "Kuro"));
+        let previous = snapshot(&[&mochi, &tama]);
+        let renamed = cat(1, "Mochi-chan");
+        let delta = diff(&previous, &[&renamed, &kuro]);
+        assert_eq!(delta.added, [&kuro]);
+        assert_eq!(delta.changed, [&renamed]);
+        assert_eq!(delta.removed, [2]);
+    }
+
+    #[test]
+    fn an_unchanged_listing_is_empty() {
+        let (mochi, tama) = (cat(1, "Mochi"), cat(2, "Tama"));
+        assert!(diff(&snapshot(&[&mochi, &tama]), &[&tama, &mochi]).is_empty());
+    }
+
+    #[test]
+    fn the_same_call_ignores_full() {
+        let arguments = |value: serde_json::Value| value.as_object().cloned();
+        let plain = listing_key("search_cats", arguments(serde_json::json!({ "breed": "Persian" })).as_ref());
+        let full = listing_key("search_cats", arguments(serde_json::json!({ "breed": "Persian", "full": true })).as_ref());
+        assert_eq!(plain, full);
+        assert_ne!(plain, listing_key("search_cats", arguments(serde_json::json!({ "breed": "Siamese" })).as_ref()));
+        assert_ne!(plain, listing_key("list_all_cats", arguments(serde_json::json!({ "breed": "Persian" })).as_ref()));
+    }
+}
@@ -0,0 +1,32 @@
+//! Old tool names that keep working after a rename.
+
+use rmcp::model::Tool;
+
+/// Deprecated name → the tool it now forwards to.
+pub const ALIASES: &[(&str, &str)] = &[("search_by_breed", "search_cats")];
+
+/// The replacement for a deprecated tool name, if it is one.
+pub fn resolve(name: &str) -> Option<&'static str> {
+    ALIASES.iter().find(|(alias, _)| *alias == name).map(|(_, target)| *target)
+}
+
+/// Adds a deprecated copy of each aliased tool so existing clients still see it.
+pub fn with_aliases(mut tools: Vec<Tool>) -> Vec<Tool> {
+    for (alias, target) in ALIASES {
+        let Some(tool) = tools.iter().find(|tool| tool.name == *target) else {
+            continue;
+        };
+        let mut deprecated = tool.clone();
+        deprecated.name = (*alias).into();
+        deprecated.description = Some(
+            format!(
+                "Deprecated: use {} instead. {}",
+                target,
+                tool.description.as_deref().unwrap_or_default()
+            )
+            .into(),
+        );
+        tools.push(deprecated);
+    }
+    tools
+}
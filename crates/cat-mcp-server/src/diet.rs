@@ -0,0 +1,81 @@
+use serde::Serialize;
+
+/// Ingredients that conflict with each known prescribed diet.
+const DIET_RESTRICTIONS: &[(&str, &[&str])] = &[
+    ("grain-free", &["wheat", "corn", "rice", "barley", "oat"]),
+    ("weight management", &["animal fat", "lard", "tallow", "sugar"]),
+    ("renal", &["bone meal", "fish meal", "salt"]),
+    ("low-sodium", &["salt", "sodium"]),
+    ("dairy-free", &["milk", "cheese", "whey", "lactose"]),
+];
+
+#[derive(Debug, Serialize)]
+pub struct Conflict {
+    pub ingredient: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompatibilityReport {
+    pub cat_id: u32,
+    pub cat_name: String,
+    pub compatible: bool,
+    pub conflicts: Vec<Conflict>,
+    /// Prescribed diets with no known ingredient rules; these need a manual check.
+    pub unchecked_diets: Vec<String>,
+}
+
+fn mentions(ingredient: &str, term: &str) -> bool {
+    ingredient.to_lowercase().contains(&term.to_lowercase())
+}
+
+pub fn check_compatibility(
+    cat_id: u32,
+    cat_name: &str,
+    allergies: &[String],
+    prescribed_diets: &[String],
+    ingredients: &[String],
+) -> CompatibilityReport {
+    let mut conflicts = Vec::new();
+    let mut unchecked_diets = Vec::new();
+
+    for ingredient in ingredients {
+        for allergy in allergies {
+            if mentions(ingredient, allergy) {
+                conflicts.push(Conflict {
+                    ingredient: ingredient.clone(),
+                    reason: format!("Allergy: {}", allergy),
+                });
+            }
+        }
+    }
+
+    for diet in prescribed_diets {
+        let rules = DIET_RESTRICTIONS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(diet))
+            .map(|(_, forbidden)| *forbidden);
+
+        let Some(forbidden) = rules else {
+            unchecked_diets.push(diet.clone());
+            continue;
+        };
+
+        for ingredient in ingredients {
+            if let Some(term) = forbidden.iter().find(|term| mentions(ingredient, term)) {
+                conflicts.push(Conflict {
+                    ingredient: ingredient.clone(),
+                    reason: format!("Prescribed {} diet excludes {}", diet, term),
+                });
+            }
+        }
+    }
+
+    CompatibilityReport {
+        cat_id,
+        cat_name: cat_name.to_string(),
+        compatible: conflicts.is_empty(),
+        conflicts,
+        unchecked_diets,
+    }
+}
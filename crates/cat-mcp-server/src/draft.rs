@@ -0,0 +1,63 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordType {
+    Cat,
+    Incident,
+}
+
+impl RecordType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "cat" => Some(RecordType::Cat),
+            "incident" => Some(RecordType::Incident),
+            _ => None,
+        }
+    }
+}
+
+/// A record being built up over several calls. Drafts live apart from the
+/// published collections, so list and search tools never see them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Draft {
+    pub id: u32,
+    pub record_type: RecordType,
+    /// The published record this draft edits, if any.
+    pub record_id: Option<u32>,
+    pub fields: Map<String, Value>,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Draft {
+    pub fn new(id: u32, record_type: RecordType, record_id: Option<u32>) -> Self {
+        Self {
+            id,
+            record_type,
+            record_id,
+            fields: Map::new(),
+            updated_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    pub fn merge(&mut self, fields: &Map<String, Value>) {
+        for (key, value) in fields {
+            self.fields.insert(key.clone(), value.clone());
+        }
+        self.updated_at = chrono::Local::now().naive_local();
+    }
+
+    /// Overlays the draft fields on `base` (the published record, or an empty object).
+    pub fn apply_to(&self, base: Value) -> Value {
+        let mut object = match base {
+            Value::Object(object) => object,
+            _ => Map::new(),
+        };
+        for (key, value) in &self.fields {
+            object.insert(key.clone(), value.clone());
+        }
+        Value::Object(object)
+    }
+}
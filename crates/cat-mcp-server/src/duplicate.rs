@@ -0,0 +1,76 @@
+//! Catching a cat that is added twice: same breed and a name that is the same
+//! or close to it in any of the cat's name forms, so "Mike", "mike", "Mik" and
+//! "ミケ" all count as a Calico named Mike. Numbers in a name are compared
+//! exactly, so "Kitten 1" and "Kitten 2" are different cats.
+
+use crate::matching;
+use cat_mcp_core::Cat;
+
+/// How alike two names must sound and be spelled, 0-1, to count as the same.
+const NAME_CONFIDENCE: f64 = 0.85;
+
+fn same_breed(a: &str, b: &str) -> bool {
+    let (a, b) = (matching::normalize(a), matching::normalize(b));
+    !a.is_empty() && !b.is_empty() && (a.contains(&b) || b.contains(&a))
+}
+
+/// The digits in `name`, in order; full-width ones count as their ASCII form.
+fn digits(name: &str) -> String {
+    matching::normalize(name).chars().filter(char::is_ascii_digit).collect()
+}
+
+fn same_name(candidate: &Cat, existing: &Cat) -> bool {
+    let names = |cat: &Cat| std::iter::once(cat.name.clone()).chain(cat.name_variants.clone()).collect::<Vec<_>>();
+    let theirs = names(existing);
+    names(candidate).iter().any(|name| {
+        theirs
+            .iter()
+            .any(|other| digits(other) == digits(name) && matching::phonetic_confidence(other, name) >= NAME_CONFIDENCE)
+    })
+}
+
+/// Existing cats that `candidate` probably duplicates.
+pub fn find<'a>(candidate: &Cat, cats: &'a [Cat]) -> Vec<&'a Cat> {
+    cats.iter()
+        .filter(|cat| cat.id != candidate.id && same_breed(&cat.breed, &candidate.breed) && same_name(candidate, cat))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cat(id: u32, name: &str, breed: &str) -> Cat {
+        Cat {
+            id,
+            name: name.to_string(),
+            name_variants: vec![],
+            age: 1,
+            breed: breed.to_string(),
+            color: "White".to_string(),
+            is_indoor: true,
+            favorite_toy: String::new(),
+            allergies: vec![],
+            prescribed_diets: vec![],
+            temperament: vec![],
+            photos: vec![],
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn close_names_of_one_breed_are_the_same_cat() {
+        let existing = [cat(1, "Mike", "Calico")];
+        assert_eq!(find(&cat(0, "mike", "Calico"), &existing).len(), 1);
+        assert_eq!(find(&cat(0, "Mik", "calico"), &existing).len(), 1);
+        assert!(find(&cat(0, "Mike", "Persian"), &existing).is_empty());
+    }
+
+    #[test]
+    fn names_with_different_numbers_are_different_cats() {
+        let existing = [cat(1, "Kitten 1", "Persian"), cat(2, "Kitten", "Persian")];
+        assert!(find(&cat(0, "Kitten 2", "Persian"), &existing).is_empty());
+        assert!(find(&cat(0, "Kitten 12", "Persian"), &existing).is_empty());
+        assert_eq!(find(&cat(0, "kitten １", "Persian"), &existing).iter().map(|cat| cat.id).collect::<Vec<_>>(), [1]);
+    }
+}
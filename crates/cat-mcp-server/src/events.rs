@@ -0,0 +1,90 @@
+//! Internal event bus. Every successful mutating call publishes one
+//! [`Mutation`], and each subsystem that reacts to changes (resource
+//! notifications) reads them from its own [`Subscriber`] in its own task, so
+//! the call path does not need to know who is listening. A new reactive
+//! feature subscribes in `CatServer::spawn_subscribers` without touching
+//! `call_tool`.
+//!
+//! Delivery is best effort: subscribers see mutations in the order they were
+//! published, but one that falls more than [`CAPACITY`] behind skips the oldest
+//! and logs how many it missed. Anything that must not miss a change, like the
+//! audit log, is written in the call path instead.
+
+use crate::audit::Change;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio_util::sync::CancellationToken;
+
+/// How many mutations a subscriber may fall behind before it misses some.
+pub const CAPACITY: usize = 1024;
+
+/// One successful mutating call.
+#[derive(Debug)]
+pub struct Mutation {
+    pub changes: Vec<Change>,
+}
+
+pub struct Bus {
+    sender: broadcast::Sender<Arc<Mutation>>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl Bus {
+    /// Sends `mutation` to every subscriber.
+    pub fn publish(&self, mutation: Mutation) {
+        // With no subscribers there is nobody to tell.
+        let _ = self.sender.send(Arc::new(mutation));
+    }
+
+    /// A new subscription to every mutation published from now on. `name`
+    /// identifies it in logs; it stops when `shutdown` fires, after handling
+    /// what was already published.
+    pub fn subscribe(&self, name: &'static str, shutdown: CancellationToken) -> Subscriber {
+        Subscriber {
+            name,
+            receiver: self.sender.subscribe(),
+            shutdown,
+        }
+    }
+}
+
+pub struct Subscriber {
+    name: &'static str,
+    receiver: broadcast::Receiver<Arc<Mutation>>,
+    shutdown: CancellationToken,
+}
+
+impl Subscriber {
+    /// The next mutation, or `None` once shutting down and caught up.
+    pub async fn next(&mut self) -> Option<Arc<Mutation>> {
+        loop {
+            let received = if self.shutdown.is_cancelled() {
+                match self.receiver.try_recv() {
+                    Ok(mutation) => Ok(mutation),
+                    Err(TryRecvError::Lagged(missed)) => Err(RecvError::Lagged(missed)),
+                    Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+                }
+            } else {
+                tokio::select! {
+                    received = self.receiver.recv() => received,
+                    _ = self.shutdown.cancelled() => continue,
+                }
+            };
+            match received {
+                Ok(mutation) => return Some(mutation),
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("The {} fell behind and missed {} change(s)", self.name, missed);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
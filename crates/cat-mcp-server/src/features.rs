@@ -0,0 +1,119 @@
+//! Optional subsystems. Each is a Cargo feature of this crate, so a build
+//! without it (e.g. `--no-default-features --features sqlite` for a small
+//! stdio-only server) leaves its code and dependencies out entirely. A
+//! compiled-in subsystem can also be switched off for one deployment with
+//! `CAT_SERVER_DISABLED_FEATURES` (comma-separated), else `[features] disabled`
+//! in the configuration file, so one build can run with HTTP in staging and
+//! without it in production. Asking for a subsystem that is missing or switched
+//! off stops startup with an error.
+//!
+//! Clients see which are compiled in, enabled and in use in the experimental
+//! `features` capability of `get_info`, and in `server_stats`.
+
+use crate::config;
+use crate::store::Store;
+use crate::transport::Transports;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Every optional subsystem, by its Cargo feature name.
+pub const ALL: &[&str] = &["sqlite", "postgres", "http", "unix-socket"];
+
+/// Whether this build includes `feature`.
+pub fn compiled(feature: &str) -> bool {
+    match feature {
+        "sqlite" => cfg!(feature = "sqlite"),
+        "postgres" => cfg!(feature = "postgres"),
+        "http" => cfg!(feature = "http"),
+        "unix-socket" => cfg!(all(unix, feature = "unix-socket")),
+        _ => false,
+    }
+}
+
+/// The subsystems switched off by configuration.
+pub struct Switches {
+    disabled: HashSet<String>,
+}
+
+impl Switches {
+    pub fn from_config() -> Self {
+        let disabled = match std::env::var("CAT_SERVER_DISABLED_FEATURES") {
+            Ok(value) => value.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect(),
+            Err(_) => config::file().features.disabled.iter().cloned().collect(),
+        };
+        Self { disabled }
+    }
+
+    /// Compiled in and not switched off.
+    pub fn enabled(&self, feature: &str) -> bool {
+        compiled(feature) && !self.disabled.contains(feature)
+    }
+
+    /// Fails unless `feature` is enabled; `what` names what needed it.
+    pub fn require(&self, feature: &str, what: &str) -> anyhow::Result<()> {
+        if !compiled(feature) {
+            anyhow::bail!("{} needs a build with the {} feature", what, feature);
+        }
+        if self.disabled.contains(feature) {
+            anyhow::bail!("{} is switched off in this deployment (the {} feature is disabled by configuration)", what, feature);
+        }
+        Ok(())
+    }
+
+    /// Switched-off names that are not features, to catch typos at startup.
+    pub fn unknown(&self) -> Vec<&str> {
+        let mut unknown: Vec<&str> = self.disabled.iter().map(String::as_str).filter(|name| !ALL.contains(name)).collect();
+        unknown.sort();
+        unknown
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Status {
+    pub name: &'static str,
+    pub compiled: bool,
+    /// Compiled in and not switched off.
+    pub enabled: bool,
+    /// In use by this process: the chosen store, or a transport it listens on.
+    pub active: bool,
+}
+
+/// The feature `store` comes from; the JSON file is always built in.
+fn store_feature(store: &Store) -> Option<&'static str> {
+    match store {
+        #[cfg(feature = "sqlite")]
+        Store::Sqlite(_) => Some("sqlite"),
+        Store::JsonFile(_) => None,
+        #[cfg(feature = "postgres")]
+        Store::Postgres(_) => Some("postgres"),
+    }
+}
+
+/// Every subsystem's status for a server using `transports` and `store`.
+pub fn report(transports: &Transports, store: Option<&Store>) -> Vec<Status> {
+    let switches = Switches::from_config();
+    ALL.iter()
+        .map(|&name| Status {
+            name,
+            compiled: compiled(name),
+            enabled: switches.enabled(name),
+            active: match name {
+                "http" => transports.http.is_some(),
+                "unix-socket" => transports.unix_socket.is_some(),
+                backend => store.and_then(store_feature) == Some(backend),
+            },
+        })
+        .collect()
+}
+
+/// The statuses as the experimental `features` capability: one object per
+/// subsystem with `compiled`, `enabled` and `active`.
+pub fn capability(statuses: &[Status]) -> serde_json::Map<String, serde_json::Value> {
+    statuses
+        .iter()
+        .map(|status| {
+            let flags = serde_json::json!({ "compiled": status.compiled, "enabled": status.enabled, "active": status.active });
+            (status.name.to_string(), flags)
+        })
+        .collect()
+}
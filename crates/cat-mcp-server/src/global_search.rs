@@ -0,0 +1,56 @@
+//! Ranking for `global_search`, which looks through every collection at once.
+//!
+//! Each record lists its searchable fields with a weight. A field scores its
+//! weight ×3 for an exact match, ×2 when it starts with the query and ×1 when
+//! it contains it (after normalization and romaji folding); a record's score is
+//! the sum over its fields.
+
+use crate::matching;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const KINDS: &[&str] = &["cats", "incidents", "adopters", "medical"];
+
+#[derive(Debug, Serialize)]
+pub struct Hit {
+    pub id: u32,
+    pub title: String,
+    pub score: u32,
+    /// Fields that matched.
+    pub matched: Vec<&'static str>,
+    pub record: Value,
+}
+
+/// `None` when no field matches.
+pub fn score(query: &str, fields: &[(&'static str, &str, u32)]) -> Option<(u32, Vec<&'static str>)> {
+    let query_key = matching::normalize_romaji(query);
+    if query_key.is_empty() {
+        return None;
+    }
+    let mut total = 0;
+    let mut matched = Vec::new();
+    for (field, text, weight) in fields {
+        let key = matching::normalize_romaji(text);
+        let factor = if key == query_key {
+            3
+        } else if key.starts_with(&query_key) {
+            2
+        } else if matching::contains(text, query, true) {
+            1
+        } else {
+            continue;
+        };
+        total += weight * factor;
+        if !matched.contains(field) {
+            matched.push(*field);
+        }
+    }
+    (total > 0).then_some((total, matched))
+}
+
+/// Best hits first, at most `limit`.
+pub fn rank(mut hits: Vec<Hit>, limit: usize) -> Vec<Hit> {
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    hits.truncate(limit);
+    hits
+}
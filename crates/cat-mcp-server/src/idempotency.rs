@@ -0,0 +1,94 @@
+//! Replays the original result when a mutating call is retried with the same
+//! `idempotency_key`, so a retry after a timeout does not apply the change twice.
+
+use crate::receipt;
+use rmcp::{
+    ErrorData,
+    model::{Content, ErrorCode, Tool},
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard, RwLock};
+use tokio::time::{Duration, Instant};
+
+pub const ARGUMENT: &str = "idempotency_key";
+/// How long a key is remembered.
+const TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_ENTRIES: usize = 1000;
+
+struct Entry {
+    at: Instant,
+    arguments: Option<Map<String, Value>>,
+    result: Vec<Content>,
+}
+
+#[derive(Default)]
+pub struct IdempotencyCache {
+    entries: RwLock<HashMap<(String, String), Entry>>,
+    /// Keyed calls run one at a time, so a duplicate that arrives while the
+    /// first is still running waits and then gets the replay.
+    running: Mutex<()>,
+}
+
+impl IdempotencyCache {
+    pub async fn begin(&self) -> MutexGuard<'_, ()> {
+        self.running.lock().await
+    }
+
+    /// The stored result for this key, or an error if the key was used with
+    /// different arguments.
+    pub async fn replay(&self, tool: &str, key: &str, arguments: &Option<Map<String, Value>>) -> Result<Option<Vec<Content>>, ErrorData> {
+        let entries = self.entries.read().await;
+        let Some(entry) = entries.get(&(tool.to_string(), key.to_string())) else {
+            return Ok(None);
+        };
+        if entry.at.elapsed() > TTL {
+            return Ok(None);
+        }
+        if &entry.arguments != arguments {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("{} \"{}\" was already used for a different {} call", ARGUMENT, key, tool).into(),
+                data: None,
+            });
+        }
+        Ok(Some(entry.result.clone()))
+    }
+
+    pub async fn remember(&self, tool: &str, key: &str, arguments: Option<Map<String, Value>>, result: &[Content]) {
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, entry| entry.at.elapsed() <= TTL);
+        if entries.len() >= MAX_ENTRIES {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, entry)| entry.at).map(|(k, _)| k.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert((tool.to_string(), key.to_string()), Entry {
+            at: Instant::now(),
+            arguments,
+            result: result.to_vec(),
+        });
+    }
+}
+
+/// Adds the optional `idempotency_key` argument to every mutating tool.
+pub fn with_key_property(tools: Vec<Tool>) -> Vec<Tool> {
+    tools
+        .into_iter()
+        .map(|mut tool| {
+            if !receipt::MUTATING_TOOLS.contains(&tool.name.as_ref()) {
+                return tool;
+            }
+            let mut schema = (*tool.input_schema).clone();
+            if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+                properties.insert(ARGUMENT.to_string(), serde_json::json!({
+                    "type": "string",
+                    "description": "Optional. Retrying with the same key within an hour returns the first result instead of repeating the change"
+                }));
+            }
+            tool.input_schema = Arc::new(schema);
+            tool
+        })
+        .collect()
+}
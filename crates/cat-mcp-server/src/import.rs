@@ -0,0 +1,217 @@
+//! Reads cats from CSV with a header row, e.g. a shelter's existing spreadsheet,
+//! and writes them back out in the same layout.
+//!
+//! Columns are matched by name: `name`, `age`, `breed` and `color` are required;
+//! `is_indoor` (default true), `favorite_toy`, and the list columns
+//! `name_variants`, `allergies`, `prescribed_diets`, `temperament` and `photos`
+//! (items separated by `;`) are optional. An `id` column is ignored, since
+//! imported cats always get new IDs.
+
+use crate::sanitize::Sanitizer;
+use crate::validation;
+use cat_mcp_core::Cat;
+use serde::Serialize;
+
+const REQUIRED_COLUMNS: &[&str] = &["name", "age", "breed", "color"];
+const OPTIONAL_COLUMNS: &[&str] = &[
+    "id",
+    "is_indoor",
+    "favorite_toy",
+    "name_variants",
+    "allergies",
+    "prescribed_diets",
+    "temperament",
+    "photos",
+];
+
+/// Column order used for export.
+const EXPORT_COLUMNS: &[&str] = &[
+    "id",
+    "name",
+    "name_variants",
+    "age",
+    "breed",
+    "color",
+    "is_indoor",
+    "favorite_toy",
+    "allergies",
+    "prescribed_diets",
+    "temperament",
+    "photos",
+];
+
+/// Every problem found in one data row.
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    /// Line number in the CSV, counting the header as line 1.
+    pub row: usize,
+    pub errors: Vec<String>,
+}
+
+pub struct Parsed {
+    /// Valid rows as cats; their IDs are assigned on insert.
+    pub cats: Vec<Cat>,
+    pub errors: Vec<RowError>,
+}
+
+/// Parses and validates every row. Errors in the header (missing or unknown
+/// columns, malformed CSV) fail the whole import.
+pub fn parse_cats(text: &str, sanitizer: &Sanitizer) -> Result<Parsed, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("Could not read the header row: {}", e))?
+        .iter()
+        .map(|header| header.to_lowercase())
+        .collect();
+
+    let missing: Vec<&str> = REQUIRED_COLUMNS
+        .iter()
+        .copied()
+        .filter(|column| !headers.iter().any(|header| header == column))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required columns: {}", missing.join(", ")));
+    }
+    let unknown: Vec<&str> = headers
+        .iter()
+        .map(String::as_str)
+        .filter(|header| !REQUIRED_COLUMNS.contains(header) && !OPTIONAL_COLUMNS.contains(header))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!("Unknown columns: {}", unknown.join(", ")));
+    }
+
+    let mut parsed = Parsed { cats: Vec::new(), errors: Vec::new() };
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                parsed.errors.push(RowError { row, errors: vec![e.to_string()] });
+                continue;
+            }
+        };
+        let cell = |column: &str| {
+            headers
+                .iter()
+                .position(|header| header == column)
+                .and_then(|i| record.get(i))
+                .map(|value| sanitizer.clean(value))
+                .unwrap_or_default()
+        };
+        let list = |column: &str| -> Vec<String> {
+            cell(column)
+                .split(';')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        let mut errors = Vec::new();
+        let age = cell("age").parse::<u32>().map_err(|_| format!("age \"{}\" is not a whole number", cell("age")));
+        let is_indoor = match cell("is_indoor").to_lowercase().as_str() {
+            "" | "true" | "yes" | "1" => Ok(true),
+            "false" | "no" | "0" => Ok(false),
+            other => Err(format!("is_indoor \"{}\" is not true or false", other)),
+        };
+        errors.extend(age.as_ref().err().cloned());
+        errors.extend(is_indoor.as_ref().err().cloned());
+
+        let cat = Cat {
+            id: 0,
+            name: cell("name"),
+            name_variants: list("name_variants"),
+            age: age.unwrap_or_default(),
+            breed: cell("breed"),
+            color: cell("color"),
+            is_indoor: is_indoor.unwrap_or(true),
+            favorite_toy: cell("favorite_toy"),
+            allergies: list("allergies"),
+            prescribed_diets: list("prescribed_diets"),
+            temperament: list("temperament"),
+            photos: list("photos"),
+            version: 0,
+        };
+        errors.extend(validation::check_cat(&cat).into_iter().map(|v| format!("{} {}", v.field, v.problem)));
+        if errors.is_empty() {
+            parsed.cats.push(cat);
+        } else {
+            parsed.errors.push(RowError { row, errors });
+        }
+    }
+    Ok(parsed)
+}
+
+/// One row per cat, with list fields joined by `;`, so the result can be fed
+/// back to [`parse_cats`].
+pub fn cats_to_csv(cats: &[Cat]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(EXPORT_COLUMNS).map_err(|e| e.to_string())?;
+    for cat in cats {
+        writer
+            .write_record([
+                cat.id.to_string(),
+                cat.name.clone(),
+                cat.name_variants.join(";"),
+                cat.age.to_string(),
+                cat.breed.clone(),
+                cat.color.clone(),
+                cat.is_indoor.to_string(),
+                cat.favorite_toy.clone(),
+                cat.allergies.join(";"),
+                cat.prescribed_diets.join(";"),
+                cat.temperament.join(";"),
+                cat.photos.join(";"),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Parsed, String> {
+        parse_cats(text, &Sanitizer::from_env())
+    }
+
+    #[test]
+    fn reads_rows_by_column_name() {
+        let parsed = parse("Color,Name,Age,Breed,is_indoor,allergies,id\nWhite,Mochi,3,Persian,no,chicken; fish ;,42\n").expect("the header is valid");
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        let cat = &parsed.cats[0];
+        assert_eq!((cat.id, cat.name.as_str(), cat.age, cat.breed.as_str(), cat.color.as_str()), (0, "Mochi", 3, "Persian", "White"));
+        assert!(!cat.is_indoor);
+        assert_eq!(cat.allergies, ["chicken", "fish"]);
+    }
+
+    #[test]
+    fn header_problems_fail_the_whole_import() {
+        assert_eq!(parse("name,age\nMochi,3\n").err().as_deref(), Some("Missing required columns: breed, color"));
+        assert_eq!(parse("name,age,breed,color,owner\nMochi,3,Persian,White,Ann\n").err().as_deref(), Some("Unknown columns: owner"));
+    }
+
+    #[test]
+    fn bad_rows_are_reported_by_line_and_the_rest_imported() {
+        let parsed = parse("name,age,breed,color,is_indoor\nTama,old,Siamese,Cream,maybe\nMochi,3,Persian,White,\n").expect("the header is valid");
+        assert_eq!(parsed.cats.len(), 1);
+        assert_eq!(parsed.cats[0].name, "Mochi");
+        assert!(parsed.cats[0].is_indoor);
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].row, 2);
+        assert_eq!(parsed.errors[0].errors, ["age \"old\" is not a whole number", "is_indoor \"maybe\" is not true or false"]);
+    }
+
+    #[test]
+    fn exported_cats_import_unchanged() {
+        let parsed = parse("name,age,breed,color,favorite_toy,temperament,photos\nMochi,3,Persian,White,Mouse,calm;shy,a.jpg\n").expect("the header is valid");
+        let csv = cats_to_csv(&parsed.cats).expect("the cats export");
+        assert_eq!(parse(&csv).expect("the export reads back").cats, parsed.cats);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::Cat;
+use serde::{Deserialize, Serialize};
+
+pub const STEPS: [&str; 3] = ["basic_info", "medical_check", "photos"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasicInfo {
+    pub name: String,
+    #[serde(default)]
+    pub name_variants: Vec<String>,
+    pub age: u32,
+    pub breed: String,
+    pub color: String,
+    #[serde(default = "default_indoor")]
+    pub is_indoor: bool,
+    #[serde(default)]
+    pub favorite_toy: String,
+    #[serde(default)]
+    pub temperament: Vec<String>,
+}
+
+fn default_indoor() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MedicalCheck {
+    #[serde(default)]
+    pub allergies: Vec<String>,
+    #[serde(default)]
+    pub prescribed_diets: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Photos {
+    pub urls: Vec<String>,
+}
+
+/// A cat being taken in. It only becomes a regular cat record once every step is done.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntakeDraft {
+    pub id: u32,
+    pub basic_info: Option<BasicInfo>,
+    pub medical_check: Option<MedicalCheck>,
+    pub photos: Option<Photos>,
+    /// Set once the intake is complete and the cat has been published.
+    pub cat_id: Option<u32>,
+}
+
+impl IntakeDraft {
+    pub fn new(id: u32) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Applies the data for one step, replacing anything recorded for it before.
+    pub fn apply(&mut self, step: &str, data: serde_json::Value) -> Result<(), String> {
+        match step {
+            "basic_info" => self.basic_info = Some(serde_json::from_value(data).map_err(|e| e.to_string())?),
+            "medical_check" => self.medical_check = Some(serde_json::from_value(data).map_err(|e| e.to_string())?),
+            "photos" => self.photos = Some(serde_json::from_value(data).map_err(|e| e.to_string())?),
+            _ => return Err(format!("Unknown step \"{}\", expected one of: {}", step, STEPS.join(", "))),
+        }
+        Ok(())
+    }
+
+    pub fn remaining_steps(&self) -> Vec<&'static str> {
+        let done = [self.basic_info.is_some(), self.medical_check.is_some(), self.photos.is_some()];
+        STEPS
+            .iter()
+            .zip(done)
+            .filter(|(_, done)| !done)
+            .map(|(step, _)| *step)
+            .collect()
+    }
+
+    pub fn stage(&self) -> &'static str {
+        if self.cat_id.is_some() {
+            "ready"
+        } else {
+            self.remaining_steps().first().copied().unwrap_or("ready")
+        }
+    }
+
+    /// Builds the cat record, or `None` while steps remain.
+    pub fn to_cat(&self, id: u32) -> Option<Cat> {
+        let basic = self.basic_info.as_ref()?;
+        let medical = self.medical_check.as_ref()?;
+        let photos = self.photos.as_ref()?;
+
+        Some(Cat {
+            id,
+            name: basic.name.clone(),
+            name_variants: basic.name_variants.clone(),
+            age: basic.age,
+            breed: basic.breed.clone(),
+            color: basic.color.clone(),
+            is_indoor: basic.is_indoor,
+            favorite_toy: basic.favorite_toy.clone(),
+            allergies: medical.allergies.clone(),
+            prescribed_diets: medical.prescribed_diets.clone(),
+            temperament: basic.temperament.clone(),
+            photos: photos.urls.clone(),
+            version: 0,
+        })
+    }
+}
@@ -0,0 +1,63 @@
+//! Priority lanes. Tool calls are either interactive (lookups, searches and
+//! single-record edits) or batch (imports, exports, bulk edits and reports),
+//! and each kind has its own bounded number of calls that may run at once. A
+//! burst of exports queues behind the few batch slots instead of taking the
+//! capacity a chat session needs for a quick `get_cat_by_id`. Batch work runs
+//! on the shared runtime, so keeping its lane small also leaves runtime threads
+//! free for interactive calls.
+//!
+//! Slot counts come from `CAT_SERVER_INTERACTIVE_WORKERS` and
+//! `CAT_SERVER_BATCH_WORKERS`.
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Tools that run in the batch lane; everything else is interactive.
+pub const BATCH_TOOLS: &[&str] = &[
+    "import_cats_csv",
+    "export_cats",
+    "batch_update",
+    "behavior_patterns",
+    "validation_report",
+    "get_audit_log",
+    "fix_orphaned_records",
+    "recompute_versions",
+    "normalize_text_fields",
+    "vacuum_database",
+];
+const DEFAULT_INTERACTIVE_WORKERS: usize = 32;
+const DEFAULT_BATCH_WORKERS: usize = 2;
+
+pub struct Lanes {
+    interactive: Semaphore,
+    batch: Semaphore,
+}
+
+fn workers(variable: &str, default: usize) -> usize {
+    std::env::var(variable)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|workers| *workers > 0)
+        .unwrap_or(default)
+}
+
+impl Lanes {
+    pub fn from_env() -> Self {
+        Self {
+            interactive: Semaphore::new(workers("CAT_SERVER_INTERACTIVE_WORKERS", DEFAULT_INTERACTIVE_WORKERS)),
+            batch: Semaphore::new(workers("CAT_SERVER_BATCH_WORKERS", DEFAULT_BATCH_WORKERS)),
+        }
+    }
+
+    /// Waits for a free slot in `tool`'s lane; the call holds it until the
+    /// permit is dropped.
+    pub async fn enter(&self, tool: &str) -> SemaphorePermit<'_> {
+        let (lane, name) = match BATCH_TOOLS.contains(&tool) {
+            true => (&self.batch, "batch"),
+            false => (&self.interactive, "interactive"),
+        };
+        if lane.available_permits() == 0 {
+            tracing::debug!("{} is waiting for a free {} slot", tool, name);
+        }
+        lane.acquire().await.expect("lane semaphores are never closed")
+    }
+}
@@ -0,0 +1,4112 @@
+use anyhow::Result;
+use minijinja::context;
+use rmcp::{
+    ErrorData, ServerHandler, ServiceExt,
+    model::{
+        AnnotateAble, CallToolRequestParam, CallToolResult, Content, ErrorCode, GetPromptRequestParam, GetPromptResult,
+        JsonObject, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, LoggingLevel,
+        LoggingMessageNotificationParam, PaginatedRequestParam, PromptMessage, PromptMessageRole, RawResource, RawResourceTemplate, ReadResourceRequestParam, ReadResourceResult, ResourceContents, ResourceTemplate,
+        SubscribeRequestParam, Tool, ToolAnnotations, ServerCapabilities, UnsubscribeRequestParam,
+    },
+    service::{Peer, RequestContext},
+    transport::stdio,
+    RoleServer,
+};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::info;
+
+mod admin;
+mod anomaly;
+mod arguments;
+mod audit;
+mod autosave;
+mod cache;
+mod benchmark;
+mod chaos;
+mod chunk;
+mod compat;
+mod config;
+mod connection;
+mod delta;
+mod deprecation;
+mod diet;
+mod draft;
+mod duplicate;
+mod events;
+mod features;
+mod global_search;
+mod idempotency;
+mod import;
+mod intake;
+mod lanes;
+mod maintenance;
+mod merge_patch;
+mod migration;
+mod matching;
+mod matchmaking;
+mod notify;
+mod outage;
+mod prompts;
+mod receipt;
+mod records;
+mod redact;
+mod repair;
+mod response;
+#[cfg(feature = "http")]
+mod rest;
+mod review;
+mod rng;
+mod sanitize;
+mod schema;
+mod seed;
+mod selfcheck;
+mod session;
+mod shutdown;
+mod similarity;
+mod standby;
+mod store;
+mod templates;
+mod timeline;
+mod transliterate;
+mod transport;
+mod undo;
+mod validation;
+mod waitlist;
+#[cfg(feature = "http")]
+mod web;
+
+use cat_mcp_core::Cat;
+use cat_mcp_core::entity;
+use cat_mcp_core::ids::IdAllocator;
+use cat_mcp_core::incident::{self, Incident, Severity};
+use cat_mcp_core::repository::{self, BoxFuture, InMemoryCatRepository, InMemoryIncidentRepository};
+use connection::Connection;
+
+struct CatServer {
+    /// Shared, so several sessions can be served from the same cats.
+    cats: records::Cats,
+    incidents: records::Incidents,
+    /// What the current mutating call has written.
+    journal: Arc<records::Journal>,
+    history: timeline::History,
+    adopter_weights: matchmaking::AdopterWeights,
+    query_cache: cache::QueryCache,
+    waitlists: RwLock<HashMap<u32, VecDeque<waitlist::WaitlistEntry>>>,
+    intakes: RwLock<HashMap<u32, intake::IntakeDraft>>,
+    drafts: RwLock<HashMap<u32, draft::Draft>>,
+    intake_ids: IdAllocator,
+    draft_ids: IdAllocator,
+    receipts: receipt::ReceiptSigner,
+    idempotency: idempotency::IdempotencyCache,
+    audit: audit::AuditLog,
+    /// Where successful mutations are published for the audit log and
+    /// resource notifications.
+    events: events::Bus,
+    drain: Arc<shutdown::DrainState>,
+    /// Separate concurrency limits for interactive and batch tools.
+    lanes: lanes::Lanes,
+    /// Whether the store is currently reachable.
+    backend: Arc<outage::Backend>,
+    text_search: matching::TextSearch,
+    templates: templates::Templates,
+    json: response::JsonWriter,
+    redactor: redact::Redactor,
+    sanitizer: sanitize::Sanitizer,
+    anomalies: anomaly::AnomalyDetector,
+    /// The message refused calls get while maintenance mode is on.
+    maintenance: RwLock<Option<String>>,
+    /// Changes from sessions without admin access, waiting for an admin.
+    review: review::Queue,
+    /// Where admin tools may be called, and the token they need elsewhere.
+    admin: admin::Access,
+    compat: compat::Compatibility,
+    validation_failures: schema::FailureStats,
+    field_aliases: schema::FieldAliases,
+    argument_mode: schema::Mode,
+    /// Whether `delete_cat` needs `confirm: true`; off only if `CAT_SERVER_CONFIRM_DELETES=false`.
+    confirm_deletes: bool,
+    store: Option<store::Store>,
+    /// Held while a snapshot is taken and saved, so saves land in order.
+    persist_lock: tokio::sync::Mutex<()>,
+    /// Batches saves to the JSON data file; `None` writes after every change.
+    autosave: Option<autosave::Autosave>,
+    /// Every open client connection, on any transport.
+    connections: transport::Connections,
+    /// Fault injection for client testing; only with `--chaos`.
+    chaos: Option<chaos::Chaos>,
+    /// The `--seed-rng` seed, reported by `server_stats`.
+    seed_rng: Option<u64>,
+    /// The primary this server follows with `--standby-of`, until promoted.
+    standby: Option<standby::Standby>,
+    /// Extra record types served through `entity::EntityTools` (none built in).
+    entities: Vec<Box<dyn entity::Toolset>>,
+    /// [`CatServer::advertised_tools`], built on first use.
+    advertised: std::sync::OnceLock<Vec<Tool>>,
+    /// Name, version and instructions reported to clients.
+    identity: config::Identity,
+    /// Tools switched off by configuration; they are neither listed nor run.
+    tool_filter: config::ToolFilter,
+    /// Which optional subsystems are built in, enabled and in use; filled in
+    /// once the store and transports are chosen.
+    features: Vec<features::Status>,
+    started_at: chrono::NaiveDateTime,
+}
+
+/// The instructions clients get unless the configuration replaces them.
+const INSTRUCTIONS: &str = "A Cat Database MCP Server that provides tools to manage and query cat data. Use the available tools to list all cats, get specific cat information by ID, search by name (romanized or Japanese) or breed, filter for indoor cats only, add, update or delete cats (deleted cats are archived and can be restored with restore_cat), log behavioral incidents and analyze behavior patterns, check food compatibility against allergies and prescribed diets, suggest compatible companions for co-adoption, rank cats for an adopter profile, manage per-cat adoption waitlists, take in new cats through the staged intake flow, or build cat and incident records incrementally as drafts before publishing them. Every change returns a signed receipt that can be checked with verify_receipt. Call summarize_session to close out a conversation with an accurate account of what was read and changed. Read the cats://{id} resource for one cat's record, cats://all for every cat, cats://breed/{breed} for every cat of a breed, and cat://{id}/timeline for a cat's whole story in date order. Subscribe to a resource to be told when it changes. The summarize_cat_profile, draft_adoption_listing and compare_cats prompts come filled in with the cats' current records.";
+
+fn match_candidates<'a>(cats: &[&'a Cat], incidents: &'a [Incident]) -> Vec<matchmaking::Candidate<'a>> {
+    cats.iter()
+        .map(|cat| matchmaking::Candidate {
+            id: cat.id,
+            name: &cat.name,
+            age: cat.age,
+            is_indoor: cat.is_indoor,
+            temperament: &cat.temperament,
+            incidents: incidents.iter().filter(|incident| incident.cat_id == cat.id).collect(),
+        })
+        .collect()
+}
+
+/// Refuses a write unless the caller's `expected_version` is `cat`'s current
+/// version, so nobody overwrites a change they have not seen.
+fn check_version(cat: &Cat, args: Option<&serde_json::Map<String, serde_json::Value>>) -> Result<(), ErrorData> {
+    match args.and_then(|args| args.get("expected_version")).and_then(|v| v.as_u64()) {
+        Some(expected) if expected == cat.version => Ok(()),
+        Some(expected) => Err(ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!(
+                "Cat {} has changed since you read it (now version {}, you sent {}); read it again and retry",
+                cat.id, cat.version, expected
+            )
+            .into(),
+            data: serde_json::to_value(cat).ok(),
+        }),
+        None => Err(ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("expected_version is required (cat {} is at version {})", cat.id, cat.version).into(),
+            data: None,
+        }),
+    }
+}
+
+/// Formats the database can be read in as an `export://cats.{format}`
+/// resource, with their MIME types.
+const EXPORT_FORMATS: &[(&str, &str)] = &[("csv", "text/csv"), ("json", "application/json"), ("ndjson", "application/x-ndjson")];
+
+/// The most operations one `batch_update` call may hold.
+const MAX_BATCH_OPERATIONS: usize = 100;
+
+/// Mutating tools that save their changes themselves before returning, so
+/// `call_tool` does not save again.
+const SAVES_ITSELF: &[&str] = &["batch_update"];
+
+fn export_uri(format: &str) -> String {
+    format!("export://cats.{}", format)
+}
+
+/// Every listed cat as one resource; each is also `cats://{id}` on its own.
+const ALL_CATS_URI: &str = "cats://all";
+const CAT_URI_TEMPLATE: &str = "cats://{id}";
+/// Every cat of one breed, matched like search (case and width do not matter).
+const BREED_URI_TEMPLATE: &str = "cats://breed/{breed}";
+
+fn cat_uri(cat_id: u32) -> String {
+    format!("cats://{}", cat_id)
+}
+
+/// The cat ID in a `cats://{id}` URI.
+fn parse_cat_uri(uri: &str) -> Option<u32> {
+    uri.strip_prefix("cats://")?.parse().ok()
+}
+
+/// The breed percent-encoded, as a client expanding [`BREED_URI_TEMPLATE`] would.
+fn breed_uri(breed: &str) -> String {
+    format!("cats://breed/{}", percent_encoding::utf8_percent_encode(breed, percent_encoding::NON_ALPHANUMERIC))
+}
+
+/// The breed in a `cats://breed/{breed}` URI, decoded.
+fn parse_breed_uri(uri: &str) -> Option<String> {
+    let breed = percent_encoding::percent_decode_str(uri.strip_prefix("cats://breed/")?).decode_utf8().ok()?;
+    (!breed.trim().is_empty()).then(|| breed.into_owned())
+}
+
+/// What a resource URI names.
+enum Resource {
+    Export { format: &'static str, mime_type: &'static str },
+    AllCats,
+    Breed(String),
+    Cat(u32),
+    Timeline(u32),
+}
+
+/// The resource `uri` names, if `read_resource` serves it. The startup
+/// self-check holds every template in [`resource_templates`] to this.
+fn parse_resource(uri: &str) -> Option<Resource> {
+    if let Some(&(format, mime_type)) = EXPORT_FORMATS.iter().find(|(format, _)| export_uri(format) == uri) {
+        return Some(Resource::Export { format, mime_type });
+    }
+    if uri == ALL_CATS_URI {
+        return Some(Resource::AllCats);
+    }
+    parse_breed_uri(uri)
+        .map(Resource::Breed)
+        .or_else(|| parse_cat_uri(uri).map(Resource::Cat))
+        .or_else(|| timeline::parse_uri(uri).map(Resource::Timeline))
+}
+
+/// The URI templates `list_resource_templates` offers.
+fn resource_templates() -> Vec<ResourceTemplate> {
+    vec![
+        RawResourceTemplate {
+            uri_template: CAT_URI_TEMPLATE.to_string(),
+            name: "Cat".to_string(),
+            description: Some("One cat's record by ID, the same fields get_cat_by_id returns".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: BREED_URI_TEMPLATE.to_string(),
+            name: "Cats by breed".to_string(),
+            description: Some("The records of every cat of a breed, e.g. cats://breed/Persian. Case and full-width characters do not matter; the breed must match exactly otherwise".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }
+        .no_annotation(),
+        RawResourceTemplate {
+            uri_template: timeline::URI_TEMPLATE.to_string(),
+            name: "Cat timeline".to_string(),
+            description: Some("Creation, field changes, medical checks, incidents and waitlist activity for one cat in date order, as JSON and Markdown".to_string()),
+            mime_type: Some("application/json".to_string()),
+        }
+        .no_annotation(),
+    ]
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<serde_json::Value, ErrorData> {
+    serde_json::to_value(value).map_err(|e| ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!("Serialization error: {}", e).into(),
+        data: None,
+    })
+}
+
+impl CatServer {
+    /// A server over `cats`, from a seed file or the samples, or none.
+    fn new(cats: Vec<Cat>) -> Self {
+        let (journal, backend) = (Arc::new(records::Journal::default()), Arc::new(outage::Backend::default()));
+        Self {
+            cats: records::Cats::new(Arc::new(InMemoryCatRepository::new(cats)), journal.clone(), backend.clone()),
+            incidents: records::Incidents::new(Arc::new(InMemoryIncidentRepository::default()), journal.clone(), backend.clone()),
+            journal,
+            history: timeline::History::default(),
+            adopter_weights: matchmaking::AdopterWeights::default(),
+            query_cache: cache::QueryCache::new(cache::DEFAULT_TTL),
+            waitlists: RwLock::new(HashMap::new()),
+            intakes: RwLock::new(HashMap::new()),
+            drafts: RwLock::new(HashMap::new()),
+            intake_ids: IdAllocator::default(),
+            draft_ids: IdAllocator::default(),
+            receipts: receipt::ReceiptSigner::from_env(),
+            idempotency: idempotency::IdempotencyCache::default(),
+            audit: audit::AuditLog::from_env(),
+            events: events::Bus::default(),
+            drain: Arc::new(shutdown::DrainState::default()),
+            lanes: lanes::Lanes::from_env(),
+            backend,
+            text_search: matching::TextSearch::from_env(),
+            templates: templates::Templates::from_env(),
+            json: response::JsonWriter::from_env(),
+            redactor: redact::Redactor::from_env(),
+            sanitizer: sanitize::Sanitizer::from_env(),
+            anomalies: anomaly::AnomalyDetector::default(),
+            maintenance: RwLock::new(None),
+            review: review::Queue::default(),
+            admin: admin::Access::default(),
+            compat: compat::Compatibility::from_env(),
+            validation_failures: schema::FailureStats::default(),
+            field_aliases: schema::FieldAliases::from_env(),
+            argument_mode: schema::Mode::from_env(),
+            confirm_deletes: std::env::var("CAT_SERVER_CONFIRM_DELETES")
+                .map(|value| value != "false")
+                .unwrap_or(true),
+            store: None,
+            persist_lock: tokio::sync::Mutex::new(()),
+            autosave: None,
+            connections: transport::Connections::default(),
+            chaos: None,
+            seed_rng: None,
+            standby: None,
+            entities: Vec::new(),
+            advertised: std::sync::OnceLock::new(),
+            identity: config::Identity::from_config("cat-database-server", "1.0.0", INSTRUCTIONS),
+            tool_filter: config::ToolFilter::from_config(),
+            features: Vec::new(),
+            started_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    /// What every connection reports when a client initializes.
+    fn server_info(&self) -> rmcp::model::ServerInfo {
+        let mut capabilities = ServerCapabilities::builder()
+            .enable_prompts()
+            .enable_tools()
+            .enable_resources()
+            .enable_resources_list_changed()
+            .enable_resources_subscribe()
+            .enable_logging()
+            .build();
+        capabilities.experimental = Some([("features".to_string(), features::capability(&self.features))].into());
+        rmcp::model::ServerInfo {
+            protocol_version: rmcp::model::ProtocolVersion::V_2024_11_05,
+            capabilities,
+            server_info: rmcp::model::Implementation {
+                name: self.identity.name.clone(),
+                version: self.identity.version.clone(),
+            },
+            instructions: Some(self.identity.instructions.clone()),
+        }
+    }
+
+    /// The built-in tools plus those of any registered entity types, with field
+    /// aliases noted in their schemas and `idempotency_key` on mutating tools.
+    /// Built once: the entities and aliases are fixed when the server is made.
+    fn advertised_tools(&self) -> &[Tool] {
+        self.advertised.get_or_init(|| {
+            let mut advertised = tools();
+            advertised.extend(self.entities.iter().flat_map(|entity| entity.tools()));
+            admin::with_token_property(idempotency::with_key_property(self.field_aliases.describe(advertised)))
+        })
+    }
+
+    /// The advertised tools the configuration leaves enabled, as `list_tools`
+    /// returns them.
+    fn enabled_tools(&self) -> Vec<Tool> {
+        self.advertised_tools().iter().filter(|tool| self.tool_filter.allows(&tool.name)).cloned().collect()
+    }
+
+    /// Loads the records saved in `store`, or on first run fills it with the
+    /// server's seed cats. A store in an older format is migrated and rewritten
+    /// straight away. With a database, the records are read and written there
+    /// from then on.
+    async fn with_store(mut self, store: store::Store) -> anyhow::Result<Self> {
+        let seeded = self.cats.repository().list().await?.len();
+        if store.is_fresh() {
+            // Nothing is written without seed cats, so the store stays fresh
+            // and a later start with them still fills it.
+            if seeded == 0 {
+                info!("🌱 {} is new and empty; start once with {} to fill it with the sample cats", store.describe(), seed::SAMPLES_FLAG);
+            } else {
+                store
+                    .save(self.cats.repository().list().await?, self.incidents.repository().list().await?, self.cats.repository().archived().await?)
+                    .await?;
+            }
+        } else {
+            if seeded > 0 {
+                tracing::warn!("{} already holds data, so the {} seed cats were not added", store.describe(), seeded);
+            }
+            let (mut data, version) = store.load_raw().await?;
+            let steps = migration::migrate(&mut data, version)?;
+            let (cats, incidents, archived) = data.into_records()?;
+            if !steps.is_empty() {
+                for step in &steps {
+                    info!("🔧 Migrated to format version {}: {} ({} changes)", step.to, step.description, step.changes.len());
+                }
+                store.save(cats.clone(), incidents.clone(), archived.clone()).await?;
+            }
+            self.cats.set_repository(Arc::new(InMemoryCatRepository::with_archived(cats, archived)));
+            self.incidents.set_repository(Arc::new(InMemoryIncidentRepository::new(incidents)));
+        }
+        if let Some((cats, incidents)) = store.repositories() {
+            self.cats.set_repository(cats);
+            self.incidents.set_repository(incidents);
+        }
+        if matches!(store, store::Store::JsonFile(_)) {
+            self.autosave = autosave::Autosave::from_args();
+        }
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    /// Saves after a change: straight away, or at the next autosave.
+    async fn persist(&self) -> Result<(), ErrorData> {
+        match &self.autosave {
+            Some(autosave) => {
+                autosave.mark_dirty();
+                Ok(())
+            }
+            None => self.save_now().await,
+        }
+    }
+
+    /// Writes cats and incidents to the JSON file, if that is the store; the
+    /// databases are written as each change is made. Snapshots are taken under
+    /// `persist_lock` so a newer change cannot be overwritten by an older
+    /// snapshot.
+    async fn save_now(&self) -> Result<(), ErrorData> {
+        let Some(store) = self.store.as_ref().filter(|store| !store.writes_rows()) else {
+            return Ok(());
+        };
+        let _guard = self.persist_lock.lock().await;
+        let cats = self.cats.list().await?;
+        let incidents = self.incidents.list().await?;
+        let archived = self.cats.archived().await?;
+        if let Err(e) = store.save(cats, incidents, archived).await {
+            self.backend.failed(e.to_string()).await;
+            return Err(ErrorData {
+                code: ErrorCode::INTERNAL_ERROR,
+                message: format!(
+                    "The change could not be saved: {}. Nothing was changed; further changes are refused until the database is reachable again",
+                    e
+                )
+                .into(),
+                data: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets every record in `changes` back to its state before them, and the
+    /// cats' history back to `history`. Carries on past a record it cannot
+    /// restore, so one bad record does not leave the rest applied.
+    async fn roll_back(&self, changes: &[audit::Change], history: &timeline::Checkpoint) -> Result<(), ErrorData> {
+        let mut failed = None;
+        for (record, id, _, state) in undo::steps(changes, true) {
+            if let Err(e) = self.set_record(record, id, state).await {
+                tracing::error!("Could not roll back {} {}: {}", record, id, e.message);
+                failed.get_or_insert(e);
+            }
+        }
+        self.history.rollback(history).await;
+        failed.map_or(Ok(()), Err)
+    }
+
+    /// On a standby, applies a change the primary made, as its journal entry
+    /// records it: saved, audited and announced like a change made here.
+    async fn apply_primary_change(&self, entry: audit::Entry) -> Result<(), ErrorData> {
+        let _writing = self.audit.begin().await;
+        self.journal.start();
+        let mut applied = Ok(());
+        for change in &entry.changes {
+            applied = self.set_record(&change.record, change.id, &change.after).await;
+            if applied.is_err() {
+                break;
+            }
+        }
+        self.journal.take();
+        self.query_cache.invalidate();
+        applied?;
+        self.persist().await?;
+        self.events.publish(events::Mutation { changes: entry.changes.clone() });
+        self.audit.record(entry).await;
+        Ok(())
+    }
+
+    /// While the store is unreachable, keeps trying to reach it, backing off
+    /// between attempts: the JSON file by writing the current state to it, a
+    /// database with a write that changes nothing.
+    async fn reconnect(self: Arc<Self>, shutdown: tokio_util::sync::CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = self.backend.outage_started() => {}
+                _ = shutdown.cancelled() => return,
+            }
+            while let Some(outage) = self.backend.outage().await {
+                tokio::select! {
+                    _ = tokio::time::sleep(outage::backoff(outage.attempts)) => {}
+                    _ = shutdown.cancelled() => return,
+                }
+                let reached = match self.store.as_ref().filter(|store| store.writes_rows()) {
+                    Some(store) => match store.ping().await {
+                        Ok(()) => true,
+                        Err(e) => {
+                            self.backend.failed(e.to_string()).await;
+                            false
+                        }
+                    },
+                    None => self.save_now().await.is_ok(),
+                };
+                if reached {
+                    self.backend.recovered().await;
+                }
+            }
+        }
+    }
+
+    /// Writes the state whenever it changed, once per autosave interval, until
+    /// `shutdown`; see [`autosave`].
+    async fn autosave(self: Arc<Self>, shutdown: tokio_util::sync::CancellationToken) {
+        let Some(autosave) = &self.autosave else {
+            return;
+        };
+        let mut ticks = tokio::time::interval(autosave.interval);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = shutdown.cancelled() => return,
+            }
+            // During an outage the reconnect task does the writing.
+            if self.backend.outage().await.is_none() && autosave.take_dirty() && self.save_now().await.is_err() {
+                autosave.mark_dirty();
+            }
+        }
+    }
+
+    /// Starts a task per subsystem that reacts to mutations, each with its own
+    /// subscription to [`events`]. They stop once `shutdown` fires and they
+    /// have handled everything already published.
+    fn spawn_subscribers(self: &Arc<Self>, shutdown: &tokio_util::sync::CancellationToken) -> Vec<tokio::task::JoinHandle<()>> {
+        let mut notifications = self.events.subscribe("resource notifications", shutdown.clone());
+        let mut availability = self.events.subscribe("waitlist notifications", shutdown.clone());
+        let (for_notifications, for_availability) = (self.clone(), self.clone());
+        vec![
+            tokio::spawn(async move {
+                while let Some(mutation) = availability.next().await {
+                    for cat_id in waitlist::became_available(&mutation.changes) {
+                        for_availability.notify_waitlist(cat_id).await;
+                    }
+                }
+            }),
+            tokio::spawn(async move {
+                while let Some(mutation) = notifications.next().await {
+                    let batch = notify::Batch::from_changes(&mutation.changes);
+                    for_notifications.connections.broadcast(&batch).await;
+                }
+            }),
+        ]
+    }
+
+    /// Tells every session waiting for `cat_id` that it is available, with its
+    /// adopters' places in the queue.
+    async fn notify_waitlist(&self, cat_id: u32) {
+        let queue: Vec<waitlist::WaitlistEntry> = match self.waitlists.read().await.get(&cat_id) {
+            Some(queue) if !queue.is_empty() => queue.iter().cloned().collect(),
+            _ => return,
+        };
+        let cat = match self.cats.get(cat_id).await {
+            Ok(Some(cat)) => cat,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Could not read cat {} to tell its waitlist: {}", cat_id, e.message);
+                return;
+            }
+        };
+        info!("📣 Cat {} is available; telling its waitlist ({} adopters)", cat_id, queue.len());
+        for (index, entry) in queue.iter().enumerate() {
+            let Some(session) = entry.session else {
+                continue;
+            };
+            let Some(peer) = self.connections.peer(session).await else {
+                continue;
+            };
+            let text = match self.templates.render_text("waitlist_available", context! {
+                cat_id => cat_id,
+                name => cat.name.as_str(),
+                adopter_name => entry.adopter_name.as_str(),
+                position => index + 1,
+            }) {
+                Ok(text) => text,
+                Err(e) => {
+                    tracing::warn!("Could not render the waitlist notification for cat {}: {}", cat_id, e.message);
+                    return;
+                }
+            };
+            let _ = peer
+                .notify_logging_message(LoggingMessageNotificationParam {
+                    level: LoggingLevel::Notice,
+                    logger: Some("cat-database-server".to_string()),
+                    data: serde_json::json!({ "event": "waitlist_available", "cat_id": cat_id, "position": index + 1, "message": text }),
+                })
+                .await;
+        }
+    }
+
+    /// Writes anything autosave has not yet, before the server exits.
+    async fn flush(&self) {
+        let Some(autosave) = &self.autosave else {
+            return;
+        };
+        if autosave.take_dirty() {
+            match self.save_now().await {
+                Ok(()) => info!("💾 Saved pending changes"),
+                Err(e) => tracing::error!("Could not save pending changes on shutdown: {}", e.message),
+            }
+        }
+    }
+
+    /// A cat or incident as `set_record` takes it: see [`records::Journal`].
+    async fn record_state(&self, record: &str, id: u32) -> Result<serde_json::Value, ErrorData> {
+        match record {
+            "cat" => self.cats.state(id).await,
+            _ => self.incidents.state(id).await,
+        }
+    }
+
+    /// Sets every record `operation` changed back to its state before it
+    /// (`undo`) or after it, once none of them has changed since.
+    async fn replay_operation(&self, operation: &undo::Operation, undo: bool) -> Result<(), ErrorData> {
+        let mut stale = Vec::new();
+        for (record, id, expected, _) in undo::steps(&operation.changes, undo) {
+            if self.record_state(record, id).await? != *expected {
+                stale.push(format!("{} {}", record, id));
+            }
+        }
+        if !stale.is_empty() {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!(
+                    "Cannot {} {} from {}: {} changed since",
+                    if undo { "undo" } else { "redo" },
+                    operation.tool,
+                    operation.at.format("%Y-%m-%d %H:%M:%S"),
+                    stale.join(", ")
+                )
+                .into(),
+                data: None,
+            });
+        }
+        for (record, id, _, state) in undo::steps(&operation.changes, undo) {
+            self.set_record(record, id, state).await?;
+        }
+        Ok(())
+    }
+
+    /// One `batch_update` operation, run as the tool it names.
+    async fn batch_operation(&self, connection: &Connection, operation: &serde_json::Value) -> Result<Vec<Content>, ErrorData> {
+        let mut arguments = operation.as_object().cloned().unwrap_or_default();
+        let tool = match arguments.remove("op").as_ref().and_then(|v| v.as_str()) {
+            Some("add") => "add_cat",
+            Some("update") => "update_cat",
+            Some("delete") => "delete_cat",
+            other => return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("op must be add, update or delete, got {}", other.unwrap_or("nothing")).into(),
+                data: None,
+            }),
+        };
+        self.check_enabled(tool)?;
+        Box::pin(self.dispatch_tool(connection, &CallToolRequestParam {
+            name: tool.into(),
+            arguments: Some(arguments),
+        }))
+        .await
+    }
+
+    /// Puts a cat or incident into `state` (as the journal records it), or
+    /// removes it when `state` is null.
+    async fn set_record(&self, record: &str, id: u32, state: &serde_json::Value) -> Result<(), ErrorData> {
+        let unreadable = |e: serde_json::Error| ErrorData {
+            code: ErrorCode::INTERNAL_ERROR,
+            message: format!("Could not restore {} {}: {}", record, id, e).into(),
+            data: None,
+        };
+        match record {
+            "cat" => {
+                self.cats.restore(id).await?;
+                if state.is_null() {
+                    self.cats.delete(id).await?;
+                } else if let Ok(archived) = serde_json::from_value::<repository::ArchivedCat>(state.clone()) {
+                    self.cats.replace(archived.cat).await?;
+                    self.cats.archive(id, archived.deleted_at).await?;
+                } else {
+                    self.cats.replace(serde_json::from_value(state.clone()).map_err(unreadable)?).await?;
+                }
+            }
+            _ => {
+                if state.is_null() {
+                    self.incidents.remove(id).await?;
+                } else {
+                    self.incidents.put(serde_json::from_value(state.clone()).map_err(unreadable)?).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The whole database as text in `format` (csv, json or ndjson), with the
+    /// number of cats and, for formats that include them, incidents.
+    async fn export(&self, format: &str) -> Result<(String, usize, Option<usize>), ErrorData> {
+        let cats = self.cats.list().await?;
+        let incidents = self.incidents.list().await?;
+        let serialization_error = |e: String| ErrorData {
+            code: ErrorCode::INTERNAL_ERROR,
+            message: format!("Serialization error: {}", e).into(),
+            data: None,
+        };
+        let (content, incident_count) = match format {
+            "csv" => (import::cats_to_csv(&cats).map_err(serialization_error)?, None),
+            "json" => (self.json.write(&serde_json::json!({ "cats": cats, "incidents": incidents }))?, Some(incidents.len())),
+            "ndjson" => {
+                let mut lines = Vec::new();
+                for cat in &cats {
+                    let mut record = to_value(cat)?;
+                    record["record_type"] = serde_json::json!("cat");
+                    lines.push(record.to_string());
+                }
+                for incident in &incidents {
+                    let mut record = to_value(incident)?;
+                    record["record_type"] = serde_json::json!("incident");
+                    lines.push(record.to_string());
+                }
+                (lines.join("\n"), Some(incidents.len()))
+            }
+            other => return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Unknown format \"{}\", expected csv, json or ndjson", other).into(),
+                data: None,
+            }),
+        };
+        Ok((content, cats.len(), incident_count))
+    }
+
+    /// The contents of a resource, whole.
+    async fn resource_contents(&self, uri: &str) -> Result<Vec<ResourceContents>, ErrorData> {
+        let not_found = || ErrorData {
+            code: ErrorCode::RESOURCE_NOT_FOUND,
+            message: format!("Resource {} not found", uri).into(),
+            data: None,
+        };
+        let cat_id = match parse_resource(uri).ok_or_else(not_found)? {
+            Resource::Export { format, mime_type } => {
+                return Ok(vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(mime_type.to_string()),
+                    text: self.export(format).await?.0,
+                }]);
+            }
+            Resource::AllCats => {
+                return Ok(vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some("application/json".to_string()),
+                    text: self.json.write(&self.cats.list().await?)?,
+                }]);
+            }
+            Resource::Breed(breed) => {
+                let breed = matching::normalize(breed.trim());
+                let cats: Vec<Cat> = self.cats.list().await?.into_iter().filter(|cat| matching::normalize(&cat.breed) == breed).collect();
+                if cats.is_empty() {
+                    return Err(not_found());
+                }
+                return Ok(vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some("application/json".to_string()),
+                    text: self.json.write(&cats)?,
+                }]);
+            }
+            Resource::Cat(cat_id) => {
+                let cat = self.cats.get(cat_id).await?.ok_or_else(not_found)?;
+                return Ok(vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some("application/json".to_string()),
+                    text: self.json.write(&cat)?,
+                }]);
+            }
+            Resource::Timeline(cat_id) => cat_id,
+        };
+        let cat = self.cats.get(cat_id).await?.ok_or_else(not_found)?;
+
+        let waitlist: Vec<waitlist::WaitlistEntry> = self
+            .waitlists
+            .read()
+            .await
+            .get(&cat_id)
+            .map(|queue| queue.iter().cloned().collect())
+            .unwrap_or_default();
+        let incidents = self.incidents.for_cat(cat_id).await?;
+        let for_cat: Vec<&Incident> = incidents.iter().collect();
+        let events = timeline::build(self.history.for_cat(cat_id).await, &for_cat, &waitlist);
+
+        Ok(vec![
+            ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: self.json.write(&serde_json::json!({ "cat": cat, "events": events }))?,
+            },
+            ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("text/markdown".to_string()),
+                text: timeline::markdown(&cat, &events),
+            },
+        ])
+    }
+
+    /// The prompt `name` filled in with the live records of the cats it names.
+    async fn prompt(&self, name: &str, arguments: &JsonObject) -> Result<GetPromptResult, ErrorData> {
+        let cat = |field: &'static str| async move {
+            let id = prompts::cat_id(arguments, field)?;
+            self.cats.get(id).await?.ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Cat with ID {} not found", id).into(),
+                data: None,
+            })
+        };
+        let incidents_of = |incidents: &[Incident], cat: &Cat| -> Vec<Incident> {
+            incidents.iter().filter(|incident| incident.cat_id == cat.id).cloned().collect()
+        };
+        let kind = prompts::Kind::parse(name).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Unknown prompt: {}", name).into(),
+            data: None,
+        })?;
+        let text = match kind {
+            prompts::Kind::SummarizeProfile | prompts::Kind::AdoptionListing => {
+                let cat = cat("cat_id").await?;
+                let incidents = self.incidents.for_cat(cat.id).await?;
+                let template = match kind {
+                    prompts::Kind::SummarizeProfile => "prompt_summarize_profile",
+                    _ => "prompt_adoption_listing",
+                };
+                self.templates.render_text(template, context! {
+                    id => cat.id,
+                    name => cat.name,
+                    tone => arguments.get("tone").and_then(|v| v.as_str()).map(str::trim).filter(|tone| !tone.is_empty()),
+                    cat => self.json.write(&cat)?,
+                    incident_count => incidents.len(),
+                    incidents => self.json.write(&incidents)?,
+                })?
+            }
+            prompts::Kind::CompareCats => {
+                let (first, second) = (cat("first_cat_id").await?, cat("second_cat_id").await?);
+                let incidents = self.incidents.list().await?;
+                self.templates.render_text("prompt_compare_cats", context! {
+                    first_id => first.id,
+                    first_name => first.name,
+                    first => self.json.write(&first)?,
+                    first_incidents => self.json.write(&incidents_of(&incidents, &first))?,
+                    second_id => second.id,
+                    second_name => second.name,
+                    second => self.json.write(&second)?,
+                    second_incidents => self.json.write(&incidents_of(&incidents, &second))?,
+                })?
+            }
+        };
+        Ok(GetPromptResult {
+            description: prompts::prompts().into_iter().find(|prompt| prompt.name == name).and_then(|prompt| prompt.description),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+
+    /// Reads the cat ID argument `field` and checks that the cat exists.
+    async fn existing_cat_id(&self, arguments: Option<&serde_json::Map<String, serde_json::Value>>, field: &str) -> Result<u32, ErrorData> {
+        let id = arguments::required_id(arguments, field)?;
+        if !self.cats.contains(id).await? {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Cat with ID {} not found", id).into(),
+                data: None,
+            });
+        }
+        Ok(id)
+    }
+
+    /// Returns only what changed since the previous identical listing call on
+    /// this connection, or `None` when the full listing should be sent. The
+    /// stored snapshot is refreshed either way.
+    async fn listing_delta(&self, connection: &Connection, request: &CallToolRequestParam, cats: &[&Cat]) -> Result<Option<Content>, ErrorData> {
+        let full = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("full"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let key = delta::listing_key(&request.name, request.arguments.as_ref());
+        let previous = connection.listing_snapshots.write().await.insert(key, delta::snapshot(cats));
+
+        match previous {
+            Some(previous) if !full => {
+                let delta = delta::diff(&previous, cats);
+                if delta.is_empty() {
+                    return Ok(Some(self.templates.render("listing_unchanged", context! {})?));
+                }
+                let content = self.json.write(&delta)?;
+                Ok(Some(self.templates.render("listing_delta", context! {
+                    added => delta.added.len(),
+                    changed => delta.changed.len(),
+                    removed => delta.removed.len(),
+                    content => content,
+                })?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Reports an anomaly the first time it trips, to the server log, the audit
+    /// log and the MCP client (if the call came from one), and builds the error
+    /// returned to the connection's calls refused while it lasts.
+    async fn throttle(&self, connection: &Connection, peer: Option<&Peer<RoleServer>>, client: Option<String>, alert: anomaly::Alert) -> ErrorData {
+        let message = format!(
+            "Unusual activity: {} {} in the last minute. Please slow down and try again shortly",
+            alert.count,
+            alert.signal.describe()
+        );
+        if alert.newly_raised {
+            tracing::warn!(
+                "🚨 Session {} over {} ({}) made {} {} in the last minute; refusing its calls until it slows down",
+                connection.id,
+                connection.transport,
+                client.as_deref().unwrap_or("unnamed client"),
+                alert.count,
+                alert.signal.describe()
+            );
+            self.audit.record(audit::Entry {
+                at: chrono::Local::now().naive_local(),
+                tool: anomaly::AUDIT_TOOL.to_string(),
+                client,
+                arguments: serde_json::json!({
+                    "session": connection.id,
+                    "transport": connection.transport,
+                    "signal": alert.signal.describe(),
+                    "count": alert.count,
+                }),
+                changes: Vec::new(),
+            }).await;
+            if let Some(peer) = peer {
+                let _ = peer
+                    .notify_logging_message(LoggingMessageNotificationParam {
+                        level: LoggingLevel::Alert,
+                        logger: Some("cat-database-server".to_string()),
+                        data: serde_json::json!(message),
+                    })
+                    .await;
+            }
+        }
+        ErrorData {
+            code: ErrorCode::INVALID_REQUEST,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Refuses `name`, as the client called it, when the tool filter leaves it out.
+    fn check_enabled(&self, name: &str) -> Result<(), ErrorData> {
+        if self.tool_filter.allows(name) {
+            return Ok(());
+        }
+        Err(ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("{} is disabled on this server", name).into(),
+            data: None,
+        })
+    }
+
+    /// Refuses arguments that do not match the tool's schema, and records why.
+    async fn check_arguments(&self, request: &CallToolRequestParam) -> Result<(), ErrorData> {
+        if let Some(tool) = self.advertised_tools().iter().find(|tool| tool.name == request.name) {
+            let mismatches = schema::check(&tool.input_schema, request.arguments.as_ref(), self.argument_mode);
+            if !mismatches.is_empty() {
+                self.validation_failures.record(&request.name, &mismatches).await;
+                let details: Vec<String> = mismatches
+                    .iter()
+                    .map(|m| format!("{} (expected {}, got {})", m.field, m.expected, m.received))
+                    .collect();
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("Invalid arguments for {}: {}", request.name, details.join("; ")).into(),
+                    data: Some(to_value(&mismatches)?),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn dispatch_tool(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        self.check_arguments(request).await?;
+        let Some(handle) = handler(&request.name) else {
+            for entity in &self.entities {
+                if let Some(result) = entity.call(&request.name, request.arguments.as_ref()).await {
+                    return result;
+                }
+            }
+            return Err(ErrorData {
+                code: ErrorCode::METHOD_NOT_FOUND,
+                message: format!("Unknown tool: {}", request.name).into(),
+                data: None,
+            });
+        };
+        handle(self, connection, request).await
+    }
+
+    async fn list_all_cats(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let store = self.cats.list().await?;
+        let cats: Vec<&Cat> = store.iter().collect();
+        if let Some(delta) = self.listing_delta(connection, request, &cats).await? {
+            return Ok(vec![delta]);
+        }
+        let content = self.json.write(&cats)?;
+        
+        Ok(vec![self.templates.render("all_cats", context! { count => cats.len(), content => content })?])
+    }
+
+    async fn get_cat_by_id(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let id: u32 = arguments::required_id(request.arguments.as_ref(), "id")?;
+        
+        Ok(if let Some(cat) = self.cats.get(id).await? {
+            let content = self.json.write(&cat)?;
+            vec![self.templates.render("cat_details", context! { id => id, content => content })?]
+        } else {
+            vec![self.templates.render("cat_not_found", context! { id => id })?]
+        })
+    }
+
+    async fn search_cats(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let breed = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("breed"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Breed is required".into(),
+                data: None,
+            })?;
+        
+        let store = self.cats.search(&|cat: &Cat| self.text_search.matches(&cat.breed, breed)).await?;
+        let matching_cats: Vec<&Cat> = store.iter().collect();
+        if let Some(delta) = self.listing_delta(connection, request, &matching_cats).await? {
+            return Ok(vec![delta]);
+        }
+        
+        Ok(if matching_cats.is_empty() {
+            vec![self.templates.render("breed_not_found", context! { breed => breed })?]
+        } else {
+            let content = self.json.write(&matching_cats)?;
+            vec![self.templates.render("breed_results", context! { breed => breed, count => matching_cats.len(), content => content })?]
+        })
+    }
+
+    async fn get_indoor_cats(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let indoor_cats = self.cats.search(&|cat: &Cat| cat.is_indoor).await?;
+        
+        let content = self.json.write(&indoor_cats)?;
+        Ok(vec![self.templates.render("indoor_cats", context! { count => indoor_cats.len(), content => content })?])
+    }
+
+    async fn add_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let value = |field: &str| args.and_then(|args| args.get(field)).filter(|v| !v.is_null());
+        let text = |field: &str| value(field).and_then(|v| v.as_str()).map(|v| v.to_string()).unwrap_or_default();
+        let mut violations: Vec<validation::Violation> = ["name", "age", "breed", "color"]
+            .into_iter()
+            .filter(|field| value(field).is_none())
+            .map(|field| validation::Violation::new(field, "is required"))
+            .collect();
+        let age = match value("age").map(|v| v.as_u64()) {
+            Some(Some(age)) => age.min(u32::MAX as u64) as u32,
+            Some(None) => {
+                violations.push(validation::Violation::new("age", "must be a whole number"));
+                0
+            }
+            None => 0,
+        };
+
+        let cat = Cat {
+            id: 0,
+            name: text("name"),
+            name_variants: vec![],
+            age,
+            breed: text("breed"),
+            color: text("color"),
+            is_indoor: value("is_indoor").and_then(|v| v.as_bool()).unwrap_or(true),
+            favorite_toy: text("favorite_toy"),
+            allergies: vec![],
+            prescribed_diets: vec![],
+            temperament: vec![],
+            photos: vec![],
+            version: 0,
+        };
+        let more: Vec<validation::Violation> = validation::check_cat(&cat)
+            .into_iter()
+            .filter(|v| !violations.iter().any(|known| known.field == v.field))
+            .collect();
+        violations.extend(more);
+        if !violations.is_empty() {
+            return Err(validation::error(&violations));
+        }
+        if !value("force").and_then(|v| v.as_bool()).unwrap_or(false) {
+            let cats = self.cats.list().await?;
+            let duplicates = duplicate::find(&cat, &cats);
+            if let Some(first) = duplicates.first() {
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!(
+                        "{} ({}) looks like cat {}, {} ({}); if this is a different cat, call again with force: true",
+                        cat.name, cat.breed, first.id, first.name, first.breed
+                    )
+                    .into(),
+                    data: Some(serde_json::json!({ "duplicates": duplicates })),
+                });
+            }
+        }
+        let cat = self.cats.insert(cat).await?;
+        self.history.created(&cat, "with add_cat").await;
+        let content = self.json.write(&cat)?;
+
+        Ok(vec![self.templates.render("cat_added", context! { id => cat.id, content => content })?])
+    }
+
+    async fn patch_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let id = self.existing_cat_id(args, "id").await?;
+        let patch = args
+            .and_then(|args| args.get("patch"))
+            .filter(|patch| patch.is_object())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "patch must be a JSON object".into(),
+                data: None,
+            })?;
+        let not_found = || ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Cat with ID {} not found", id).into(),
+            data: None,
+        };
+
+        let before = self.cats.get(id).await?.ok_or_else(not_found)?;
+        check_version(&before, args)?;
+        let mut value = to_value(&before)?;
+        let mut violations = Vec::new();
+        for (field, change) in patch.as_object().into_iter().flatten() {
+            if value.get(field).is_none() {
+                violations.push(validation::Violation::new(field, "is not a cat field"));
+            } else if field == "version" {
+                violations.push(validation::Violation::new(field, "is set by the server; send expected_version instead"));
+            } else if field == "id" && change.as_u64() != Some(id as u64) {
+                violations.push(validation::Violation::new(field, "cannot be changed"));
+            } else if change.is_null() && ["id", "name", "age", "breed", "color", "is_indoor"].contains(&field.as_str()) {
+                violations.push(validation::Violation::new(field, "is required and cannot be cleared"));
+            }
+        }
+        if !violations.is_empty() {
+            return Err(validation::error(&violations));
+        }
+        merge_patch::apply(&mut value, patch);
+        let mut after: Cat = serde_json::from_value(value).map_err(|e| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Invalid cat fields: {}", e).into(),
+            data: None,
+        })?;
+        validation::validate(&after)?;
+        self.cats.update(after.clone()).await?.ok_or_else(not_found)?;
+        after.version = before.version + 1;
+        self.history.changed(&before, &after).await;
+
+        let content = self.json.write(&serde_json::json!({ "before": before, "after": after }))?;
+        Ok(vec![self.templates.render("cat_patched", context! { id => id, content => content })?])
+    }
+
+    async fn update_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let id = arguments::required_id(args, "id")?;
+
+        let not_found = || ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Cat with ID {} not found", id).into(),
+            data: None,
+        };
+        let existing = self.cats.get(id).await?.ok_or_else(not_found)?;
+        check_version(&existing, args)?;
+        let mut value = to_value(&existing)?;
+        for (key, field) in args.into_iter().flatten().filter(|(key, _)| !["id", "expected_version"].contains(&key.as_str())) {
+            if key == "version" {
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: "version is set by the server; send expected_version instead".into(),
+                    data: None,
+                });
+            }
+            match value.get_mut(key) {
+                Some(slot) => *slot = field.clone(),
+                None => return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("Unknown cat field \"{}\"", key).into(),
+                    data: None,
+                }),
+            }
+        }
+        let mut cat: Cat = serde_json::from_value(value).map_err(|e| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Invalid cat fields: {}", e).into(),
+            data: None,
+        })?;
+        validation::validate(&cat)?;
+        let previous = self.cats.update(cat.clone()).await?.ok_or_else(not_found)?;
+        cat.version = previous.version + 1;
+        self.history.changed(&previous, &cat).await;
+        let content = self.json.write(&cat)?;
+
+        Ok(vec![self.templates.render("cat_updated", context! { id => id, content => content })?])
+    }
+
+    async fn delete_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let id = arguments::required_id(args, "id")?;
+        // Deleting is idempotent: a repeat of a delete that went through
+        // (say, retried after a lost response) finds the cat archived and
+        // succeeds without changing anything.
+        if !self.cats.contains(id).await? {
+            if let Some(archived) = self.cats.archived().await?.into_iter().find(|entry| entry.cat.id == id) {
+                let content = self.json.write(&archived)?;
+                return Ok(vec![self.templates.render("cat_already_deleted", context! { id => id, content => content })?]);
+            }
+        }
+        let id = self.existing_cat_id(args, "id").await?;
+        if let Some(cat) = self.cats.get(id).await? {
+            check_version(&cat, args)?;
+        }
+        let confirmed = args
+            .and_then(|args| args.get("confirm"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if self.confirm_deletes && !confirmed {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Deleting cat {} takes it out of every listing until restore_cat; call again with confirm: true to proceed", id).into(),
+                data: None,
+            });
+        }
+
+        let Some(archived) = self.cats.archive(id, chrono::Local::now().naive_local()).await? else {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Cat with ID {} not found", id).into(),
+                data: None,
+            });
+        };
+        self.history.archived(id).await;
+
+        let content = self.json.write(&archived)?;
+        Ok(vec![self.templates.render("cat_deleted", context! { id => id, content => content })?])
+    }
+
+    async fn restore_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let id = arguments::required_id(request.arguments.as_ref(), "id")?;
+        let Some(cat) = self.cats.restore(id).await? else {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("No archived cat with ID {}; list_archived_cats shows them", id).into(),
+                data: None,
+            });
+        };
+        self.history.restored(id).await;
+
+        let content = self.json.write(&cat)?;
+        Ok(vec![self.templates.render("cat_restored", context! { id => id, content => content })?])
+    }
+
+    async fn list_archived_cats(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let archived = self.cats.archived().await?;
+        Ok(vec![self.templates.render("archived_cats", context! { count => archived.len(), content => self.json.write(&archived)? })?])
+    }
+
+    async fn undo_or_redo(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let undo = request.name == "undo_last_change";
+        let mut stack = connection.undo.write().await;
+        let operation = match undo {
+            true => stack.last_done(),
+            false => stack.last_undone(),
+        }
+        .cloned()
+        .ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Nothing to {} on this connection", if undo { "undo" } else { "redo" }).into(),
+            data: None,
+        })?;
+        self.replay_operation(&operation, undo).await?;
+        if undo { stack.undid() } else { stack.redid() }
+
+        let content = self.json.write(&operation.changes)?;
+        Ok(vec![self.templates.render(if undo { "change_undone" } else { "change_redone" }, context! {
+            tool => operation.tool,
+            at => operation.at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            count => operation.changes.len(),
+            content => content,
+        })?])
+    }
+
+    async fn batch_update(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let operations = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("operations"))
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if operations.is_empty() || operations.len() > MAX_BATCH_OPERATIONS {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("operations must hold between 1 and {} operations, got {}", MAX_BATCH_OPERATIONS, operations.len()).into(),
+                data: None,
+            });
+        }
+        let (mark, history) = (self.journal.mark(), self.history.checkpoint().await);
+        let mut results = Vec::new();
+        for (index, operation) in operations.iter().enumerate() {
+            let op = operation.get("op").cloned().unwrap_or_default();
+            match self.batch_operation(connection, operation).await {
+                Ok(content) => {
+                    let text: Vec<&str> = content.iter().filter_map(|content| content.as_text()).map(|text| text.text.as_str()).collect();
+                    results.push(serde_json::json!({ "index": index, "op": op, "status": "applied", "result": text.join("\n") }));
+                }
+                Err(e) => {
+                    self.roll_back(&self.journal.since(mark), &history).await?;
+                    for result in &mut results {
+                        result["status"] = serde_json::json!("rolled_back");
+                    }
+                    results.push(serde_json::json!({ "index": index, "op": op, "status": "failed", "error": e.message, "data": e.data }));
+                    results.extend((index + 1..operations.len()).map(|index| serde_json::json!({ "index": index, "status": "not_run" })));
+                    return Err(ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: format!("Operation {} failed: {}. None of the {} operations were applied", index, e.message, operations.len()).into(),
+                        data: Some(serde_json::json!({ "operations": results })),
+                    });
+                }
+            }
+        }
+        // Saved here rather than by call_tool, so a failed save can be
+        // reported per operation like a failed operation.
+        if let Err(e) = self.persist().await {
+            self.roll_back(&self.journal.since(mark), &history).await?;
+            for result in &mut results {
+                result["status"] = serde_json::json!("rolled_back");
+            }
+            return Err(ErrorData {
+                code: e.code,
+                message: format!("{} None of the {} operations were applied", e.message, operations.len()).into(),
+                data: Some(serde_json::json!({ "operations": results })),
+            });
+        }
+
+        let content = self.json.write(&results)?;
+        Ok(vec![self.templates.render("batch_applied", context! { count => results.len(), content => content })?])
+    }
+
+    async fn import_cats_csv(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let text_arg = |field: &str| args.and_then(|args| args.get(field)).and_then(|v| v.as_str());
+        let text = match (text_arg("csv"), text_arg("path")) {
+            (Some(text), None) => text.to_string(),
+            (None, Some(path)) => {
+                let resolved = config::resolve_inside(path).map_err(|message| ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: message.into(),
+                    data: None,
+                })?;
+                tokio::fs::read_to_string(&resolved).await.map_err(|e| ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("Could not read {}: {}", path, e).into(),
+                    data: None,
+                })?
+            }
+            _ => return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Pass either csv or path".into(),
+                data: None,
+            }),
+        };
+        let dry_run = args
+            .and_then(|args| args.get("dry_run"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let parsed = import::parse_cats(&text, &self.sanitizer).map_err(|e| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: e.into(),
+            data: None,
+        })?;
+        let total = parsed.cats.len() + parsed.errors.len();
+        Ok(if dry_run {
+            let content = self.json.write(&parsed.errors)?;
+            vec![self.templates.render("import_checked", context! {
+                total => total,
+                valid => parsed.cats.len(),
+                failed => parsed.errors.len(),
+                content => content,
+            })?]
+        } else {
+            let mut imported = Vec::new();
+            for cat in parsed.cats {
+                let cat = self.cats.insert(cat).await?;
+                self.history.created(&cat, "from a CSV import").await;
+                imported.push(cat);
+            }
+            if let Some(store) = self.store.as_ref().filter(|_| !imported.is_empty()) {
+                // Keeps query plans good as the dataset grows; the import itself is done.
+                if let Err(e) = store.analyze().await {
+                    tracing::warn!("Could not refresh the query planner statistics after an import: {}", e);
+                }
+            }
+            let content = self.json.write(&serde_json::json!({
+                "imported": imported,
+                "errors": parsed.errors,
+            }))?;
+            vec![self.templates.render("cats_imported", context! {
+                imported => imported.len(),
+                total => total,
+                failed => parsed.errors.len(),
+                content => content,
+            })?]
+        })
+    }
+
+    async fn export_cats(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let format = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("format"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("json");
+        let (content, cats, incident_count) = self.export(format).await?;
+        Ok(vec![self.templates.render("export", context! {
+            cats => cats,
+            incidents => incident_count,
+            format => format,
+            content => content,
+        })?])
+    }
+
+    async fn log_incident(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let cat_id = self.existing_cat_id(args, "cat_id").await?;
+        let kind = args
+            .and_then(|args| args.get("type"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Incident type is required".into(),
+                data: None,
+            })?;
+        let severity = args
+            .and_then(|args| args.get("severity"))
+            .and_then(|v| v.as_str())
+            .and_then(Severity::parse)
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Severity must be one of: low, medium, high".into(),
+                data: None,
+            })?;
+        let description = args
+            .and_then(|args| args.get("description"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Description is required".into(),
+                data: None,
+            })?;
+        let datetime = match args.and_then(|args| args.get("datetime")).and_then(|v| v.as_str()) {
+            Some(value) => incident::parse_datetime(value).ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Invalid datetime \"{}\", expected YYYY-MM-DDTHH:MM[:SS]", value).into(),
+                data: None,
+            })?,
+            None => chrono::Local::now().naive_local(),
+        };
+
+        let incident = self.incidents.insert(Incident {
+            id: 0,
+            cat_id,
+            datetime,
+            kind: kind.to_string(),
+            severity,
+            description: description.to_string(),
+        }).await?;
+        let content = self.json.write(&incident)?;
+
+        Ok(vec![self.templates.render("incident_logged", context! { cat_id => cat_id, content => content })?])
+    }
+
+    async fn list_incidents(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let cat_id = arguments::id(request.arguments.as_ref(), "cat_id")?;
+
+        let incidents = self.incidents.list().await?;
+        let mut matching: Vec<&Incident> = incidents
+            .iter()
+            .filter(|incident| cat_id.is_none_or(|id| incident.cat_id == id))
+            .collect();
+        matching.sort_by_key(|incident| incident.datetime);
+
+        let content = self.json.write(&matching)?;
+        Ok(vec![self.templates.render("incidents", context! { count => matching.len(), content => content })?])
+    }
+
+    async fn behavior_patterns(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let cat_ids: Vec<u32> = match arguments::id(args, "cat_id")? {
+            Some(_) => vec![self.existing_cat_id(args, "cat_id").await?],
+            None => self.cats.list().await?.iter().map(|cat| cat.id).collect(),
+        };
+        let incidents = self.incidents.list().await?;
+
+        let patterns: Vec<incident::BehaviorPattern> = cat_ids
+            .into_iter()
+            .map(|id| {
+                let for_cat: Vec<&Incident> = incidents
+                    .iter()
+                    .filter(|incident| incident.cat_id == id)
+                    .collect();
+                incident::analyze_patterns(id, &for_cat)
+            })
+            .collect();
+
+        let content = self.json.write(&patterns)?;
+        Ok(vec![self.templates.render("behavior_patterns", context! { count => patterns.len(), content => content })?])
+    }
+
+    async fn check_food_compatibility(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let ingredients: Vec<String> = args
+            .and_then(|args| args.get("ingredients"))
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|v| v.as_str()).map(str::to_string).collect())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Ingredients are required".into(),
+                data: None,
+            })?;
+        let mut cat_ids: Vec<u32> = match arguments::ids(args, "cat_ids")? {
+            Some(ids) => ids,
+            None => self.cats.list().await?.iter().map(|cat| cat.id).collect(),
+        };
+        cat_ids.sort();
+
+        let mut reports = Vec::new();
+        for id in cat_ids {
+            let cat = self.cats.get(id).await?.ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Cat with ID {} not found", id).into(),
+                data: None,
+            })?;
+            reports.push(diet::check_compatibility(
+                cat.id,
+                &cat.name,
+                &cat.allergies,
+                &cat.prescribed_diets,
+                &ingredients,
+            ));
+        }
+
+        let incompatible = reports.iter().filter(|report| !report.compatible).count();
+        let content = self.json.write(&reports)?;
+        Ok(vec![self.templates.render("food_compatibility", context! {
+            incompatible => incompatible,
+            count => reports.len(),
+            content => content,
+        })?])
+    }
+
+    async fn suggest_companion(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let cat_id = arguments::id(args, "cat_id")?;
+        let limit = args
+            .and_then(|args| args.get("limit"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(5) as usize;
+
+        if let Some(id) = cat_id {
+            if !self.cats.contains(id).await? {
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("Cat with ID {} not found", id).into(),
+                    data: None,
+                });
+            }
+        }
+
+        let incidents = self.incidents.list().await?;
+        let store = self.cats.list().await?;
+        let cats: Vec<&Cat> = store.iter().collect();
+        let candidates = match_candidates(&cats, &incidents);
+
+        let mut matches = Vec::new();
+        for (i, a) in candidates.iter().enumerate() {
+            for b in &candidates[i + 1..] {
+                match cat_id {
+                    Some(id) if b.id == id => matches.push(matchmaking::score_pair(b, a)),
+                    Some(id) if a.id != id => {}
+                    _ => matches.push(matchmaking::score_pair(a, b)),
+                }
+            }
+        }
+        matches.sort_by(|x, y| y.score.cmp(&x.score));
+        matches.truncate(limit);
+
+        let content = self.json.write(&matches)?;
+        Ok(vec![self.templates.render("companions", context! { count => matches.len(), content => content })?])
+    }
+
+    async fn match_adopters(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.clone().unwrap_or_default();
+        let profile: matchmaking::AdopterProfile = serde_json::from_value(serde_json::Value::Object(args))
+            .map_err(|e| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Invalid adopter profile: {}", e).into(),
+                data: None,
+            })?;
+
+        let incidents = self.incidents.list().await?;
+        let store = self.cats.list().await?;
+        let cats: Vec<&Cat> = store.iter().collect();
+        let mut matches: Vec<matchmaking::AdopterMatch> = match_candidates(&cats, &incidents)
+            .iter()
+            .map(|candidate| matchmaking::score_adopter_fit(candidate, &profile, &self.adopter_weights))
+            .collect();
+        matches.sort_by(|x, y| y.score.cmp(&x.score));
+
+        let content = self.json.write(&serde_json::json!({
+            "weights": self.adopter_weights,
+            "matches": matches,
+        }))?;
+        Ok(vec![self.templates.render("adopter_matches", context! { count => matches.len(), content => content })?])
+    }
+
+    async fn find_similar_cats(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let id = self.existing_cat_id(args, "id").await?;
+        let limit = args
+            .and_then(|args| args.get("limit"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(3) as usize;
+        let weights: similarity::SimilarityWeights = match args.and_then(|args| args.get("weights")) {
+            Some(weights) => serde_json::from_value(weights.clone()).map_err(|e| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Invalid weights: {}", e).into(),
+                data: None,
+            })?,
+            None => similarity::SimilarityWeights::default(),
+        };
+
+        let cats = self.cats.list().await?;
+        let target = cats.iter().find(|cat| cat.id == id).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Cat with ID {} not found", id).into(),
+            data: None,
+        })?;
+        let mut similar: Vec<similarity::SimilarCat> = cats
+            .iter()
+            .filter(|cat| cat.id != id)
+            .map(|cat| similarity::compare(target, cat, &weights))
+            .collect();
+        similar.sort_by(|x, y| y.score.cmp(&x.score).then_with(|| x.cat_id.cmp(&y.cat_id)));
+        similar.truncate(limit);
+
+        let content = self.json.write(&serde_json::json!({
+            "weights": weights,
+            "similar": similar,
+        }))?;
+        Ok(vec![self.templates.render("similar_cats", context! {
+            name => &target.name,
+            id => id,
+            count => similar.len(),
+            content => content,
+        })?])
+    }
+
+    async fn join_waitlist(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let cat_id = self.existing_cat_id(args, "cat_id").await?;
+        let adopter_name = args
+            .and_then(|args| args.get("adopter_name"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "adopter_name is required".into(),
+                data: None,
+            })?;
+        let contact = args
+            .and_then(|args| args.get("contact"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "contact is required".into(),
+                data: None,
+            })?;
+
+        let mut waitlists = self.waitlists.write().await;
+        let queue = waitlists.entry(cat_id).or_default();
+        queue.push_back(waitlist::WaitlistEntry {
+            adopter_name: adopter_name.to_string(),
+            contact: contact.to_string(),
+            joined_at: chrono::Local::now().naive_local(),
+            session: Some(connection.id),
+        });
+
+        Ok(vec![self.templates.render("waitlist_joined", context! {
+            adopter_name => adopter_name,
+            cat_id => cat_id,
+            position => queue.len(),
+        })?])
+    }
+
+    async fn list_waitlist(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let cat_id = self.existing_cat_id(request.arguments.as_ref(), "cat_id").await?;
+
+        let waitlists = self.waitlists.read().await;
+        let empty = VecDeque::new();
+        let queue = waitlists.get(&cat_id).unwrap_or(&empty);
+        let content = self.json.write(&waitlist::positions(queue))?;
+        Ok(vec![self.templates.render("waitlist", context! { cat_id => cat_id, count => queue.len(), content => content })?])
+    }
+
+    async fn advance_waitlist(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let cat_id = self.existing_cat_id(request.arguments.as_ref(), "cat_id").await?;
+
+        let mut waitlists = self.waitlists.write().await;
+        let queue = waitlists.entry(cat_id).or_default();
+        Ok(match queue.pop_front() {
+            Some(next) => {
+                let content = self.json.write(&next)?;
+                vec![self.templates.render("waitlist_next", context! {
+                    cat_id => cat_id,
+                    remaining => queue.len(),
+                    content => content,
+                })?]
+            }
+            None => vec![self.templates.render("waitlist_empty", context! { cat_id => cat_id })?],
+        })
+    }
+
+    async fn intake_cat(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let intake_id = arguments::id(args, "intake_id")?;
+        let step = args.and_then(|args| args.get("step")).and_then(|v| v.as_str());
+
+        let mut intakes = self.intakes.write().await;
+        let intake_id = match intake_id {
+            Some(id) => id,
+            None => {
+                let id = self.intake_ids.next();
+                intakes.insert(id, intake::IntakeDraft::new(id));
+                id
+            }
+        };
+        let draft = intakes.get_mut(&intake_id).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Intake {} not found", intake_id).into(),
+            data: None,
+        })?;
+
+        if let Some(step) = step {
+            if draft.cat_id.is_some() {
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("Intake {} is already complete", intake_id).into(),
+                    data: None,
+                });
+            }
+            let data = args
+                .and_then(|args| args.get("data"))
+                .cloned()
+                .ok_or_else(|| ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("data is required for step \"{}\"", step).into(),
+                    data: None,
+                })?;
+            draft.apply(step, data).map_err(|e| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Invalid {} data: {}", step, e).into(),
+                data: None,
+            })?;
+        }
+
+        let mut published = None;
+        if draft.cat_id.is_none() {
+            if let Some(cat) = draft.to_cat(0) {
+                validation::validate(&cat)?;
+                let cat = self.cats.insert(cat).await?;
+                self.history.created(&cat, "through intake").await;
+                if let Some(check) = &draft.medical_check {
+                    self.history.medical_check(cat.id, check).await;
+                }
+                draft.cat_id = Some(cat.id);
+                published = Some(cat.id);
+            }
+        }
+
+        let content = self.json.write(&serde_json::json!({
+            "stage": draft.stage(),
+            "remaining_steps": draft.remaining_steps(),
+            "intake": draft,
+        }))?;
+        Ok(match published {
+            Some(cat_id) => vec![self.templates.render("intake_complete", context! {
+                intake_id => intake_id,
+                cat_id => cat_id,
+                content => content,
+            })?],
+            None => vec![self.templates.render("intake_progress", context! { intake_id => intake_id, content => content })?],
+        })
+    }
+
+    async fn save_draft(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let fields = args
+            .and_then(|args| args.get("fields"))
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut drafts = self.drafts.write().await;
+        let draft_id = match arguments::id(args, "draft_id")? {
+            Some(id) => id,
+            None => {
+                let record_type = args
+                    .and_then(|args| args.get("record_type"))
+                    .and_then(|v| v.as_str())
+                    .and_then(draft::RecordType::parse)
+                    .ok_or_else(|| ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: "record_type must be one of: cat, incident".into(),
+                        data: None,
+                    })?;
+                let record_id = arguments::id(args, "record_id")?;
+                if let Some(record_id) = record_id {
+                    let exists = match record_type {
+                        draft::RecordType::Cat => self.cats.contains(record_id).await?,
+                        draft::RecordType::Incident => {
+                            self.incidents.get(record_id).await?.is_some()
+                        }
+                    };
+                    if !exists {
+                        return Err(ErrorData {
+                            code: ErrorCode::INVALID_PARAMS,
+                            message: format!("Record {} not found", record_id).into(),
+                            data: None,
+                        });
+                    }
+                }
+
+                let id = self.draft_ids.next();
+                drafts.insert(id, draft::Draft::new(id, record_type, record_id));
+                id
+            }
+        };
+        let draft = drafts.get_mut(&draft_id).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Draft {} not found", draft_id).into(),
+            data: None,
+        })?;
+        draft.merge(&fields);
+
+        let content = self.json.write(draft)?;
+        Ok(vec![self.templates.render("draft_saved", context! { draft_id => draft_id, content => content })?])
+    }
+
+    async fn publish(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let draft_id = arguments::required_id(request.arguments.as_ref(), "draft_id")?;
+
+        let mut drafts = self.drafts.write().await;
+        let draft = drafts.get(&draft_id).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Draft {} not found", draft_id).into(),
+            data: None,
+        })?;
+        let incomplete = |e: serde_json::Error| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Draft {} cannot be published yet: {}", draft_id, e).into(),
+            data: None,
+        };
+        let missing_record = |id: u32| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Record {} no longer exists", id).into(),
+            data: None,
+        };
+
+        let record_type = draft.record_type;
+        let (id, content) = match record_type {
+            draft::RecordType::Cat => {
+                let base = match draft.record_id {
+                    Some(id) => to_value(&self.cats.get(id).await?.ok_or_else(|| missing_record(id))?)?,
+                    None => serde_json::json!({}),
+                };
+                let mut value = draft.apply_to(base);
+                value["id"] = serde_json::json!(draft.record_id.unwrap_or(0));
+                let mut cat: Cat = serde_json::from_value(value).map_err(incomplete)?;
+                validation::validate(&cat)?;
+                let cat = match draft.record_id {
+                    Some(id) => {
+                        let previous = self.cats.update(cat.clone()).await?.ok_or_else(|| missing_record(id))?;
+                        cat.version = previous.version + 1;
+                        self.history.changed(&previous, &cat).await;
+                        cat
+                    }
+                    None => {
+                        let cat = self.cats.insert(cat).await?;
+                        self.history.created(&cat, &format!("by publishing draft {}", draft_id)).await;
+                        cat
+                    }
+                };
+                (cat.id, self.json.write(&cat)?)
+            }
+            draft::RecordType::Incident => {
+                let base = match draft.record_id {
+                    Some(id) => to_value(&self.incidents.get(id).await?.ok_or_else(|| missing_record(id))?)?,
+                    None => serde_json::json!({ "datetime": chrono::Local::now().naive_local() }),
+                };
+                let mut value = draft.apply_to(base);
+                value["id"] = serde_json::json!(draft.record_id.unwrap_or(0));
+                let incident: Incident = serde_json::from_value(value).map_err(incomplete)?;
+                if !self.cats.contains(incident.cat_id).await? {
+                    return Err(ErrorData {
+                        code: ErrorCode::INVALID_PARAMS,
+                        message: format!("Cat with ID {} not found", incident.cat_id).into(),
+                        data: None,
+                    });
+                }
+                let incident = match draft.record_id {
+                    Some(_) => {
+                        self.incidents.put(incident.clone()).await?;
+                        incident
+                    }
+                    None => self.incidents.insert(incident).await?,
+                };
+                (incident.id, self.json.write(&incident)?)
+            }
+        };
+        drafts.remove(&draft_id);
+
+        Ok(vec![self.templates.render("draft_published", context! {
+            record_type => record_type,
+            id => id,
+            content => content,
+        })?])
+    }
+
+    async fn discard_draft(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let draft_id = arguments::required_id(request.arguments.as_ref(), "draft_id")?;
+
+        Ok(match self.drafts.write().await.remove(&draft_id) {
+            Some(_) => vec![self.templates.render("draft_discarded", context! { draft_id => draft_id })?],
+            None => vec![self.templates.render("draft_not_found", context! { draft_id => draft_id })?],
+        })
+    }
+
+    async fn verify_receipt(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let receipt: receipt::Receipt = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("receipt"))
+            .cloned()
+            .and_then(|v| serde_json::from_value(v).ok())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "A receipt with tool, payload, timestamp and signature is required".into(),
+                data: None,
+            })?;
+
+        Ok(if self.receipts.verify(&receipt) {
+            vec![self.templates.render("receipt_valid", context! {
+                tool => receipt.tool,
+                timestamp => receipt.timestamp,
+            })?]
+        } else {
+            vec![self.templates.render("receipt_invalid", context! {})?]
+        })
+    }
+
+    async fn summarize_session(&self, connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let summary = connection.session.read().await.summarize();
+        let content = self.json.write(&summary)?;
+        Ok(vec![self.templates.render("session_summary", context! {
+            total_calls => summary.total_calls,
+            changes => summary.changes,
+            content => content,
+        })?])
+    }
+
+    async fn global_search(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let query = args
+            .and_then(|args| args.get("query"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "query is required".into(),
+                data: None,
+            })?;
+        let limit = args
+            .and_then(|args| args.get("limit"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(5) as usize;
+        let kinds: Vec<&str> = match args.and_then(|args| args.get("types")).and_then(|v| v.as_array()) {
+            Some(types) => types.iter().filter_map(|v| v.as_str()).collect(),
+            None => global_search::KINDS.to_vec(),
+        };
+        if let Some(unknown) = kinds.iter().find(|kind| !global_search::KINDS.contains(kind)) {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Unknown type \"{}\", expected some of: {}", unknown, global_search::KINDS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        let waitlists = self.waitlists.read().await;
+        let intakes = self.intakes.read().await;
+        let incidents = self.incidents.list().await?;
+        let mut groups = serde_json::Map::new();
+        let mut count = 0;
+        for kind in global_search::KINDS.iter().filter(|kind| kinds.contains(kind)) {
+            let mut hits = Vec::new();
+            match *kind {
+                "cats" => {
+                    for cat in self.cats.list().await? {
+                        let mut fields = vec![("name", cat.name.as_str(), 5)];
+                        fields.extend(cat.name_variants.iter().map(|v| ("name_variants", v.as_str(), 5)));
+                        fields.extend([("breed", cat.breed.as_str(), 3), ("color", cat.color.as_str(), 2), ("favorite_toy", cat.favorite_toy.as_str(), 1)]);
+                        fields.extend(cat.temperament.iter().map(|v| ("temperament", v.as_str(), 1)));
+                        fields.extend(cat.allergies.iter().map(|v| ("allergies", v.as_str(), 1)));
+                        fields.extend(cat.prescribed_diets.iter().map(|v| ("prescribed_diets", v.as_str(), 1)));
+                        if let Some((score, matched)) = global_search::score(query, &fields) {
+                            hits.push(global_search::Hit { id: cat.id, title: cat.name.clone(), score, matched, record: to_value(&cat)? });
+                        }
+                    }
+                }
+                "incidents" => {
+                    for incident in incidents.iter() {
+                        let fields = [("type", incident.kind.as_str(), 2), ("description", incident.description.as_str(), 2)];
+                        if let Some((score, matched)) = global_search::score(query, &fields) {
+                            let title = format!("{} (cat {})", incident.kind, incident.cat_id);
+                            hits.push(global_search::Hit { id: incident.id, title, score, matched, record: to_value(incident)? });
+                        }
+                    }
+                }
+                "adopters" => {
+                    for (cat_id, queue) in waitlists.iter() {
+                        for entry in queue {
+                            if let Some((score, matched)) = global_search::score(query, &[("adopter_name", entry.adopter_name.as_str(), 5)]) {
+                                let title = format!("{} (waiting for cat {})", entry.adopter_name, cat_id);
+                                let record = serde_json::json!({ "cat_id": cat_id, "adopter_name": entry.adopter_name, "joined_at": entry.joined_at });
+                                hits.push(global_search::Hit { id: *cat_id, title, score, matched, record });
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    for intake in intakes.values() {
+                        let (Some(cat_id), Some(check)) = (intake.cat_id, &intake.medical_check) else {
+                            continue;
+                        };
+                        let mut fields = vec![("notes", check.notes.as_str(), 2)];
+                        fields.extend(check.allergies.iter().map(|v| ("allergies", v.as_str(), 1)));
+                        fields.extend(check.prescribed_diets.iter().map(|v| ("prescribed_diets", v.as_str(), 1)));
+                        if let Some((score, matched)) = global_search::score(query, &fields) {
+                            let title = format!("Intake medical check (cat {})", cat_id);
+                            hits.push(global_search::Hit { id: cat_id, title, score, matched, record: to_value(check)? });
+                        }
+                    }
+                }
+            }
+            let hits = global_search::rank(hits, limit);
+            count += hits.len();
+            groups.insert(kind.to_string(), to_value(&hits)?);
+        }
+
+        Ok(if count == 0 {
+            vec![self.templates.render("global_search_empty", context! { query => query })?]
+        } else {
+            let content = self.json.write(&groups)?;
+            vec![self.templates.render("global_search", context! { query => query, count => count, content => content })?]
+        })
+    }
+
+    async fn search_by_name(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let name = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("name"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Name is required".into(),
+                data: None,
+            })?;
+
+        let fold_romaji = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("fold_romaji"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let phonetic = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("phonetic"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let store = self.cats.list().await?;
+        if phonetic {
+            let min_confidence = request.arguments
+                .as_ref()
+                .and_then(|args| args.get("min_confidence"))
+                .and_then(|v| v.as_f64())
+                .unwrap_or(matching::DEFAULT_MIN_CONFIDENCE);
+
+            let mut candidates: Vec<serde_json::Value> = Vec::new();
+            for cat in &store {
+                let confidence = std::iter::once(&cat.name)
+                    .chain(&cat.name_variants)
+                    .map(|candidate| matching::phonetic_confidence(candidate, name))
+                    .fold(0.0, f64::max);
+                if confidence >= min_confidence {
+                    candidates.push(serde_json::json!({
+                        "confidence": (confidence * 100.0).round() / 100.0,
+                        "cat": cat,
+                    }));
+                }
+            }
+            candidates.sort_by(|a, b| {
+                let confidence = |v: &serde_json::Value| v["confidence"].as_f64().unwrap_or(0.0);
+                confidence(b).total_cmp(&confidence(a))
+            });
+
+            let content = self.json.write(&candidates)?;
+            return Ok(vec![self.templates.render("name_sounds_like", context! {
+                name => name,
+                count => candidates.len(),
+                content => content,
+            })?]);
+        }
+
+        let matching_cats: Vec<&Cat> = store
+            .iter()
+            .filter(|cat| {
+                std::iter::once(&cat.name)
+                    .chain(&cat.name_variants)
+                    .any(|candidate| matching::contains(candidate, name, fold_romaji))
+            })
+            .collect();
+
+        Ok(if matching_cats.is_empty() {
+            vec![self.templates.render("name_not_found", context! { name => name })?]
+        } else {
+            let content = self.json.write(&matching_cats)?;
+            vec![self.templates.render("name_results", context! { name => name, count => matching_cats.len(), content => content })?]
+        })
+    }
+
+    async fn transliterate_names(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let names: Vec<&str> = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("names"))
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|v| v.as_str()).collect())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "Names are required".into(),
+                data: None,
+            })?;
+
+        let forms: Vec<transliterate::NameForms> = names.into_iter().map(transliterate::name_forms).collect();
+        let content = self.json.write(&forms)?;
+        Ok(vec![self.templates.render("transliterated_names", context! { count => forms.len(), content => content })?])
+    }
+
+    async fn set_maintenance_mode(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let enabled = args
+            .and_then(|args| args.get("enabled"))
+            .and_then(|v| v.as_bool())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "enabled is required".into(),
+                data: None,
+            })?;
+        let message = args
+            .and_then(|args| args.get("message"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(maintenance::DEFAULT_MESSAGE);
+
+        // Only runtime state changes, so nothing is saved or receipted; the
+        // mutation in progress, if any, is let finish first, so a backup taken
+        // once this returns includes it.
+        let _writing = self.audit.begin().await;
+        let mut maintenance = self.maintenance.write().await;
+        *maintenance = enabled.then(|| message.to_string());
+        Ok(vec![self.templates.render("maintenance_mode", context! { enabled => enabled, message => maintenance.as_deref() })?])
+    }
+
+    async fn get_audit_log(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let args = request.arguments.as_ref();
+        let time = |field: &str| -> Result<Option<chrono::NaiveDateTime>, ErrorData> {
+            match args.and_then(|args| args.get(field)).and_then(|v| v.as_str()) {
+                Some(value) => incident::parse_datetime(value).map(Some).ok_or_else(|| ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("{} must look like 2024-05-01T18:30, got \"{}\"", field, value).into(),
+                    data: None,
+                }),
+                None => Ok(None),
+            }
+        };
+        let (since, until) = (time("since")?, time("until")?);
+        let tool = args.and_then(|args| args.get("tool")).and_then(|v| v.as_str());
+        let cat_id = arguments::id(args, "cat_id")?;
+        let limit = args
+            .and_then(|args| args.get("limit"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(50) as usize;
+
+        let mut entries = self.audit.query(since, until, tool, cat_id).await;
+        let total = entries.len();
+        entries.drain(..total.saturating_sub(limit));
+        Ok(vec![self.templates.render("audit_log", context! { shown => entries.len(), total => total, content => self.json.write(&entries)? })?])
+    }
+
+    async fn list_sessions(&self, connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let sessions = self.connections.list().await;
+        Ok(vec![self.templates.render("sessions", context! {
+            count => sessions.len(),
+            current => connection.id,
+            content => self.json.write(&sessions)?,
+        })?])
+    }
+
+    async fn disconnect_session(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let id = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("id"))
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: "id is required".into(),
+                data: None,
+            })?;
+        let transport = self.connections.disconnect(id).await.ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("No open session {}; list_sessions shows them", id).into(),
+            data: None,
+        })?;
+        Ok(vec![self.templates.render("session_disconnected", context! { id => id, transport => transport })?])
+    }
+
+    async fn repair(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let dry_run = request.arguments
+            .as_ref()
+            .and_then(|args| args.get("dry_run"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let (cats, archived) = (self.cats.list().await?, self.cats.archived().await?);
+        let incidents = self.incidents.list().await?;
+        let fixes = match request.name.as_ref() {
+            "fix_orphaned_records" => {
+                let ids = cats.iter().map(|cat| cat.id).chain(archived.iter().map(|entry| entry.cat.id)).collect();
+                repair::orphans(&ids, &incidents, &*self.waitlists.read().await, &*self.drafts.read().await)
+            }
+            "recompute_versions" => {
+                let entries = self.audit.query(None, None, None, None).await;
+                repair::versions(&cats, &archived, &repair::highest_versions(&entries))
+            }
+            _ => repair::text(&cats, &archived, &incidents),
+        };
+        if !dry_run {
+            for fix in &fixes {
+                match fix.record {
+                    "waitlist" => {
+                        self.waitlists.write().await.remove(&fix.id);
+                    }
+                    "draft" => {
+                        self.drafts.write().await.remove(&fix.id);
+                    }
+                    record => self.set_record(record, fix.id, &fix.after).await?,
+                }
+            }
+        }
+        let content = self.json.write(&fixes)?;
+        Ok(vec![self.templates.render("repairs", context! {
+            tool => request.name.as_ref(),
+            count => fixes.len(),
+            dry_run => dry_run,
+            content => content,
+        })?])
+    }
+
+    async fn server_stats(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let now = chrono::Local::now().naive_local();
+        let stats = serde_json::json!({
+            "name": self.identity.name,
+            "version": self.identity.version,
+            "started_at": self.started_at,
+            "uptime_secs": (now - self.started_at).num_seconds(),
+            "storage": self.store.as_ref().map(|store| store.describe()).unwrap_or_else(|| "memory only".to_string()),
+            "cats": self.cats.list().await?.len(),
+            "archived_cats": self.cats.archived().await?.len(),
+            "incidents": self.incidents.list().await?.len(),
+            "sessions": self.connections.list().await.len(),
+            "features": self.features,
+            "seed_rng": self.seed_rng,
+            "standby_of": self.standby.as_ref().filter(|standby| standby.following()).map(|standby| standby.primary()),
+            "query_cache": self.query_cache.stats(),
+        });
+        Ok(vec![self.templates.render("server_stats", context! { content => self.json.write(&stats)? })?])
+    }
+
+    async fn vacuum_database(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let not_sqlite = || ErrorData {
+            code: ErrorCode::INVALID_REQUEST,
+            message: format!(
+                "vacuum_database only applies to a SQLite store; this server uses {}",
+                self.store.as_ref().map(|store| store.describe()).unwrap_or_else(|| "memory only".to_string())
+            )
+            .into(),
+            data: None,
+        };
+        let store = self.store.as_ref().ok_or_else(not_sqlite)?;
+        let vacuumed = store
+            .vacuum()
+            .await
+            .map_err(|e| ErrorData {
+                code: ErrorCode::INTERNAL_ERROR,
+                message: format!("The database could not be vacuumed: {}", e).into(),
+                data: None,
+            })?
+            .ok_or_else(not_sqlite)?;
+        Ok(vec![self.templates.render("database_vacuumed", context! {
+            storage => store.describe(),
+            before => vacuumed.before_bytes,
+            after => vacuumed.after_bytes,
+            freed => vacuumed.before_bytes.saturating_sub(vacuumed.after_bytes),
+        })?])
+    }
+
+    async fn list_pending_changes(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let pending = self.review.list();
+        let content = self.json.write(&pending)?;
+        Ok(vec![self.templates.render("pending_changes", context! {
+            enabled => self.review.enabled(),
+            count => pending.len(),
+            content => content,
+        })?])
+    }
+
+    /// Runs a queued call in this one, like `batch_update` runs its operations,
+    /// so it is saved, audited and undoable as `approve_change`.
+    async fn approve_change(&self, connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let id = review::id_argument(request.arguments.as_ref())?;
+        let (claim, queued) = self.review.claim(id)?;
+        self.check_enabled(&queued.name)?;
+        let mut content = Box::pin(self.dispatch_tool(connection, &queued)).await?;
+        claim.applied();
+        content.insert(0, self.templates.render("change_approved", context! { id => id, tool => queued.name.as_ref() })?);
+        Ok(content)
+    }
+
+    async fn reject_change(&self, _connection: &Connection, request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let change = self.review.reject(review::id_argument(request.arguments.as_ref())?)?;
+        tracing::info!("Rejected change {} ({})", change.id, change.tool);
+        Ok(vec![self.templates.render("change_rejected", context! { id => change.id, tool => change.tool })?])
+    }
+
+    async fn promote(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let standby = self.standby.as_ref().filter(|standby| standby.following()).ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_REQUEST,
+            message: format!("This server is not following a primary; only one started with {} can be promoted", standby::FLAG).into(),
+            data: None,
+        })?;
+        let caught_up = standby.promote(self).await;
+        match &caught_up {
+            Ok(applied) => info!("⬆️ Promoted: no longer following {}, after applying its last {} changes", standby.primary(), applied),
+            Err(e) => tracing::warn!("⬆️ Promoted without catching up with {}: {}", standby.primary(), e),
+        }
+        Ok(vec![self.templates.render("promoted", context! {
+            primary => standby.primary(),
+            applied => caught_up.as_ref().ok(),
+            error => caught_up.as_ref().err(),
+        })?])
+    }
+
+    async fn validation_report(&self, _connection: &Connection, _request: &CallToolRequestParam) -> Result<Vec<Content>, ErrorData> {
+        let report = self.validation_failures.report().await;
+        let content = self.json.write(&report)?;
+        Ok(vec![self.templates.render("validation_report", context! { count => report.len(), content => content })?])
+    }
+}
+
+/// A built-in tool's handler, as [`HANDLERS`] holds it.
+type Handler = for<'a> fn(&'a CatServer, &'a Connection, &'a CallToolRequestParam) -> BoxFuture<'a, Result<Vec<Content>, ErrorData>>;
+
+/// The `CatServer` method of that name as a [`Handler`].
+macro_rules! handler {
+    ($method:ident) => {{
+        fn handle<'a>(server: &'a CatServer, connection: &'a Connection, request: &'a CallToolRequestParam) -> BoxFuture<'a, Result<Vec<Content>, ErrorData>> {
+            Box::pin(server.$method(connection, request))
+        }
+        handle as Handler
+    }};
+}
+
+/// Every built-in tool and what handles it; any other name goes to the
+/// entities. Checked against [`tools`] at startup and by the tests below.
+const HANDLERS: &[(&str, Handler)] = &[
+    ("list_all_cats", handler!(list_all_cats)),
+    ("get_cat_by_id", handler!(get_cat_by_id)),
+    ("search_cats", handler!(search_cats)),
+    ("get_indoor_cats", handler!(get_indoor_cats)),
+    ("add_cat", handler!(add_cat)),
+    ("update_cat", handler!(update_cat)),
+    ("patch_cat", handler!(patch_cat)),
+    ("delete_cat", handler!(delete_cat)),
+    ("restore_cat", handler!(restore_cat)),
+    ("list_archived_cats", handler!(list_archived_cats)),
+    ("undo_last_change", handler!(undo_or_redo)),
+    ("redo", handler!(undo_or_redo)),
+    ("batch_update", handler!(batch_update)),
+    ("import_cats_csv", handler!(import_cats_csv)),
+    ("export_cats", handler!(export_cats)),
+    ("log_incident", handler!(log_incident)),
+    ("list_incidents", handler!(list_incidents)),
+    ("behavior_patterns", handler!(behavior_patterns)),
+    ("check_food_compatibility", handler!(check_food_compatibility)),
+    ("suggest_companion", handler!(suggest_companion)),
+    ("match_adopters", handler!(match_adopters)),
+    ("find_similar_cats", handler!(find_similar_cats)),
+    ("join_waitlist", handler!(join_waitlist)),
+    ("list_waitlist", handler!(list_waitlist)),
+    ("advance_waitlist", handler!(advance_waitlist)),
+    ("intake_cat", handler!(intake_cat)),
+    ("save_draft", handler!(save_draft)),
+    ("publish", handler!(publish)),
+    ("discard_draft", handler!(discard_draft)),
+    ("verify_receipt", handler!(verify_receipt)),
+    ("summarize_session", handler!(summarize_session)),
+    ("search_by_name", handler!(search_by_name)),
+    ("global_search", handler!(global_search)),
+    ("transliterate_names", handler!(transliterate_names)),
+    ("set_maintenance_mode", handler!(set_maintenance_mode)),
+    ("validation_report", handler!(validation_report)),
+    ("get_audit_log", handler!(get_audit_log)),
+    ("list_sessions", handler!(list_sessions)),
+    ("disconnect_session", handler!(disconnect_session)),
+    ("fix_orphaned_records", handler!(repair)),
+    ("recompute_versions", handler!(repair)),
+    ("normalize_text_fields", handler!(repair)),
+    ("server_stats", handler!(server_stats)),
+    ("vacuum_database", handler!(vacuum_database)),
+    ("list_pending_changes", handler!(list_pending_changes)),
+    ("approve_change", handler!(approve_change)),
+    ("reject_change", handler!(reject_change)),
+    ("promote", handler!(promote)),
+];
+
+fn handler(name: &str) -> Option<Handler> {
+    HANDLERS.iter().find(|(tool, _)| *tool == name).map(|(_, handle)| *handle)
+}
+
+/// Every tool the server advertises.
+fn tools() -> Vec<Tool> {
+    deprecation::with_aliases(vec![
+        Tool {
+            name: "list_all_cats".into(),
+            description: Some("Get a list of all cats. Repeating the call returns only what changed since the last one unless full is set.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "full": {
+                        "type": "boolean",
+                        "description": "Return the complete listing even if it was already sent"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "get_cat_by_id".into(),
+            description: Some("Get information about a specific cat by ID".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "Cat ID"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "search_cats".into(),
+            description: Some("Search for cats by breed, with synonyms (e.g. \"ginger\" finds orange tabbies) and stemming. Repeating the same search returns only what changed since the last one unless full is set.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "breed": {
+                        "type": "string",
+                        "description": "Breed to search for"
+                    },
+                    "full": {
+                        "type": "boolean",
+                        "description": "Return the complete results even if they were already sent"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["breed"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "get_indoor_cats".into(),
+            description: Some("Get only indoor cats".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "add_cat".into(),
+            description: Some("Add a new cat to the database".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the cat"
+                    },
+                    "age": {
+                        "type": "number",
+                        "description": "Age in years (0-40)"
+                    },
+                    "breed": {
+                        "type": "string",
+                        "description": "Breed of the cat"
+                    },
+                    "color": {
+                        "type": "string",
+                        "description": "Coat color"
+                    },
+                    "is_indoor": {
+                        "type": "boolean",
+                        "description": "Whether the cat lives indoors (defaults to true)"
+                    },
+                    "favorite_toy": {
+                        "type": "string",
+                        "description": "Favorite toy (optional)"
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "description": "Add the cat even if one with the same breed and a similar name exists (default false)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["name", "age", "breed", "color"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "import_cats_csv".into(),
+            description: Some("Import cats from CSV with a header row. Valid rows are added with new IDs; rows with problems are skipped and reported by line number".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "csv": {
+                        "type": "string",
+                        "description": "CSV text. Columns: name, age, breed, color (required); is_indoor, favorite_toy, and ;-separated name_variants, allergies, prescribed_diets, temperament, photos"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "Path of a CSV file in the server's data directory, instead of csv"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only validate the rows and report errors, without importing"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "export_cats".into(),
+            description: Some("Export the whole database as text. json and ndjson include cats and incidents; csv holds cats in the layout import_cats_csv reads".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "format": {
+                        "type": "string",
+                        "enum": ["csv", "json", "ndjson"],
+                        "description": "Output format (default json)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "update_cat".into(),
+            description: Some("Update some fields of an existing cat, leaving the rest unchanged".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "ID of the cat to update"
+                    },
+                    "expected_version": {
+                        "type": "number",
+                        "description": "The cat's version as last read; the call is refused if it has changed since"
+                    },
+                    "name": { "type": "string" },
+                    "name_variants": { "type": "array", "items": { "type": "string" } },
+                    "age": { "type": "number" },
+                    "breed": { "type": "string" },
+                    "color": { "type": "string" },
+                    "is_indoor": { "type": "boolean" },
+                    "favorite_toy": { "type": "string" },
+                    "allergies": { "type": "array", "items": { "type": "string" } },
+                    "prescribed_diets": { "type": "array", "items": { "type": "string" } },
+                    "temperament": { "type": "array", "items": { "type": "string" } },
+                    "photos": { "type": "array", "items": { "type": "string" } }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id", "expected_version"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "patch_cat".into(),
+            description: Some("Change a cat with a JSON Merge Patch (RFC 7386): listed fields are replaced, null clears an optional field, others are left alone. Returns the cat before and after".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "ID of the cat to patch"
+                    },
+                    "expected_version": {
+                        "type": "number",
+                        "description": "The cat's version as last read; the call is refused if it has changed since"
+                    },
+                    "patch": {
+                        "type": "object",
+                        "description": "Merge patch, e.g. {\"age\": 4, \"favorite_toy\": null}. Lists are replaced whole; null on favorite_toy or a list clears it"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id", "expected_version", "patch"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "delete_cat".into(),
+            description: Some("Archive a cat: it disappears from listings and searches until restore_cat. Its waitlist and logged incidents are kept".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "ID of the cat to delete"
+                    },
+                    "expected_version": {
+                        "type": "number",
+                        "description": "The cat's version as last read; the call is refused if it has changed since"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Must be true to confirm the deletion (unless the server is configured otherwise)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id", "expected_version"]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                destructive_hint: Some(true),
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "restore_cat".into(),
+            description: Some("Bring back a deleted (archived) cat under its original ID".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "ID of the archived cat"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "list_archived_cats".into(),
+            description: Some("List deleted cats with when they were deleted".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "undo_last_change".into(),
+            description: Some("Revert this connection's most recent add, update, patch, delete, restore, batch, import or incident log. Refused if another change has touched the same records since".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "redo".into(),
+            description: Some("Apply again the change most recently reverted by undo_last_change on this connection".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "batch_update".into(),
+            description: Some("Add, update and delete several cats in one call. Operations run in order; if any fails, none of them are applied. Returns the result of each operation".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "operations": {
+                        "type": "array",
+                        "description": "Up to 100 operations. Each has op (add, update or delete) plus the arguments of add_cat, update_cat or delete_cat, e.g. {\"op\": \"update\", \"id\": 2, \"expected_version\": 3, \"age\": 6}",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "op": {
+                                    "type": "string",
+                                    "enum": ["add", "update", "delete"]
+                                }
+                            },
+                            "required": ["op"]
+                        }
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["operations"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "log_incident".into(),
+            description: Some("Log a behavioral incident for a cat".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "ID of the cat involved"
+                    },
+                    "type": {
+                        "type": "string",
+                        "description": "Kind of incident (e.g. scratching, hissing, litter box)"
+                    },
+                    "severity": {
+                        "type": "string",
+                        "enum": ["low", "medium", "high"],
+                        "description": "Severity of the incident"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "What happened"
+                    },
+                    "datetime": {
+                        "type": "string",
+                        "description": "When it happened (YYYY-MM-DDTHH:MM[:SS]); defaults to now"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["cat_id", "type", "severity", "description"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "list_incidents".into(),
+            description: Some("List logged behavioral incidents, optionally for a single cat".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Only list incidents for this cat"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "behavior_patterns".into(),
+            description: Some("Analyze incident frequency trends and time-of-day clustering per cat".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Only analyze this cat"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "check_food_compatibility".into(),
+            description: Some("Check a food's ingredient list against cats' allergies and prescribed diets".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "ingredients": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Ingredients of the proposed food"
+                    },
+                    "cat_ids": {
+                        "type": "array",
+                        "items": { "type": "number" },
+                        "description": "Cats to check (defaults to all cats)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["ingredients"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "suggest_companion".into(),
+            description: Some("Rank cat pairs by compatibility for co-adoption, with reasoning".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Only suggest companions for this cat (defaults to ranking all pairs)"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "Maximum number of matches to return (default 5)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "match_adopters".into(),
+            description: Some("Rank cats by fit for an adopter profile using a transparent scoring function".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "home_type": {
+                        "type": "string",
+                        "enum": ["apartment", "house", "house_with_garden"],
+                        "description": "Adopter's home type"
+                    },
+                    "other_pets": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Other pets in the home (e.g. dog, cat)"
+                    },
+                    "activity_level": {
+                        "type": "string",
+                        "enum": ["low", "medium", "high"],
+                        "description": "Adopter's activity level"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["home_type", "activity_level"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "find_similar_cats".into(),
+            description: Some("Find the cats most like a given cat by breed, age, color and temperament. Each result explains how its score was made up".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "ID of the cat to compare against"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "Maximum number of cats to return (default 3)"
+                    },
+                    "weights": {
+                        "type": "object",
+                        "description": "Relative importance of each feature; omitted ones keep their defaults (breed 30, age 20, color 25, temperament 25)",
+                        "properties": {
+                            "breed": { "type": "number" },
+                            "age": { "type": "number" },
+                            "color": { "type": "number" },
+                            "temperament": { "type": "number" }
+                        }
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "join_waitlist".into(),
+            description: Some("Add an adopter to a cat's adoption waitlist. Waitlists are kept in memory only and are lost when the server restarts.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Cat ID"
+                    },
+                    "adopter_name": {
+                        "type": "string",
+                        "description": "Name of the prospective adopter"
+                    },
+                    "contact": {
+                        "type": "string",
+                        "description": "How to reach the adopter"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["cat_id", "adopter_name", "contact"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "list_waitlist".into(),
+            description: Some("Show a cat's adoption waitlist with positions".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Cat ID"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["cat_id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "advance_waitlist".into(),
+            description: Some("Remove the adopter at the front of a cat's waitlist and return them as the next to contact. Waitlists are kept in memory only and are lost when the server restarts.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Cat ID"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["cat_id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "intake_cat".into(),
+            description: Some("Take in a new cat step by step (basic_info → medical_check → photos → ready). The cat only appears in normal queries once every step is done; until then the intake is kept in memory only and is lost when the server restarts.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "intake_id": {
+                        "type": "number",
+                        "description": "Intake to continue (omit to start a new one)"
+                    },
+                    "step": {
+                        "type": "string",
+                        "enum": intake::STEPS,
+                        "description": "Step to record (omit to only report progress)"
+                    },
+                    "data": {
+                        "type": "object",
+                        "description": "Step data. basic_info: name, name_variants, age, breed, color, is_indoor, favorite_toy, temperament. medical_check: allergies, prescribed_diets, notes. photos: urls."
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "save_draft".into(),
+            description: Some("Create or update a draft record. Drafts are never returned by list or search tools until published, and are kept in memory only, so they are lost when the server restarts.".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "draft_id": {
+                        "type": "number",
+                        "description": "Draft to update (omit to start a new draft)"
+                    },
+                    "record_type": {
+                        "type": "string",
+                        "enum": ["cat", "incident"],
+                        "description": "Kind of record (required for a new draft)"
+                    },
+                    "record_id": {
+                        "type": "number",
+                        "description": "Published record this draft edits (omit to create a new record)"
+                    },
+                    "fields": {
+                        "type": "object",
+                        "description": "Record fields to set; merged into the draft"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "publish".into(),
+            description: Some("Publish a draft, creating or updating the real record".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "draft_id": {
+                        "type": "number",
+                        "description": "Draft ID"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["draft_id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "discard_draft".into(),
+            description: Some("Throw away a draft without publishing it".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "draft_id": {
+                        "type": "number",
+                        "description": "Draft ID"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["draft_id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "verify_receipt".into(),
+            description: Some("Verify that a change receipt was signed by this server".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "receipt": {
+                        "type": "object",
+                        "description": "Receipt as returned by a mutating tool (tool, payload, timestamp, signature)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["receipt"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "summarize_session".into(),
+            description: Some("Summarize the tool calls made in this session: what was read and changed".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "global_search".into(),
+            description: Some("Search cats, incidents, waitlisted adopters and intake medical records at once. Hits are grouped by type and ranked by how well and where they match".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "query": {
+                        "type": "string",
+                        "description": "Text to look for (romanized or Japanese)"
+                    },
+                    "types": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": ["cats", "incidents", "adopters", "medical"] },
+                        "description": "Only search these collections (default: all)"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "Maximum hits per type (default 5)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["query"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "search_by_name".into(),
+            description: Some("Search for cats by name in any script (e.g. \"Shiro\" or \"シロ\")".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "name": {
+                        "type": "string",
+                        "description": "Name to search for (full-width, half-width and hiragana forms are folded)"
+                    },
+                    "fold_romaji": {
+                        "type": "boolean",
+                        "description": "Also match across kana and romaji, so \"shiro\" finds \"シロ\" (default true)"
+                    },
+                    "phonetic": {
+                        "type": "boolean",
+                        "description": "Match names that sound alike (e.g. \"Miko\" finds \"Mike\") and return confidence scores"
+                    },
+                    "min_confidence": {
+                        "type": "number",
+                        "description": "Lowest confidence (0-1) to return in phonetic mode (default 0.6)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["name"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "transliterate_names".into(),
+            description: Some("Convert names between romaji and katakana".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "names": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Names in romaji, hiragana or katakana"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["names"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "set_maintenance_mode".into(),
+            description: Some("Admin: turn maintenance mode on or off. While on, all non-admin tool calls fail with a temporarily unavailable message".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Whether maintenance mode should be on"
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "Message returned to refused calls (optional)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["enabled"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "validation_report".into(),
+            description: Some("Admin: argument validation failures so far, grouped by tool, field and received type, most frequent first".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "list_sessions".into(),
+            description: Some("Admin: every open client connection with its transport, client name and version, when it connected, and how many calls it has made".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "disconnect_session".into(),
+            description: Some("Admin: close a client connection. Its in-flight calls are cancelled; the data it changed stays".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "number",
+                        "description": "Session ID from list_sessions"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                destructive_hint: Some(true),
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "get_audit_log".into(),
+            description: Some("Admin: every successful change with its tool, arguments, client and the records before and after, oldest first".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "since": {
+                        "type": "string",
+                        "description": "Only changes at or after this time, e.g. 2024-05-01T18:30"
+                    },
+                    "until": {
+                        "type": "string",
+                        "description": "Only changes at or before this time"
+                    },
+                    "tool": {
+                        "type": "string",
+                        "description": "Only changes made by this tool"
+                    },
+                    "cat_id": {
+                        "type": "number",
+                        "description": "Only changes to this cat"
+                    },
+                    "limit": {
+                        "type": "number",
+                        "description": "Show at most this many of the latest matching changes (default 50)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "fix_orphaned_records".into(),
+            description: Some("Admin: remove incidents and waitlists for cats that no longer exist (live or archived), and drafts editing a cat or incident that is gone. Returns each fixed record and why".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only report what would be fixed, without changing anything (default false)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                destructive_hint: Some(true),
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "recompute_versions".into(),
+            description: Some("Admin: give a version above any it has had to every cat whose version was never set or went backwards (per the audit log), so stale expected_version values are refused. Returns each fixed cat and why".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only report what would be fixed, without changing anything (default false)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "normalize_text_fields".into(),
+            description: Some("Admin: normalize cat and incident text to NFC, trim and collapse stray whitespace, and drop empty or repeated list items. Normalized cats go up a version. Returns each changed field before and after".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only report what would be fixed, without changing anything (default false)"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "server_stats".into(),
+            description: Some("Admin: uptime, record and session counts, the store in use, and which optional subsystems (sqlite, postgres, http, unix-socket) are compiled in, enabled and active".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "vacuum_database".into(),
+            description: Some("Admin: compact the SQLite database file (VACUUM), reporting its size before and after. Records are not changed; other stores refuse the call".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                idempotent_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "list_pending_changes".into(),
+            description: Some("Admin: changes from sessions without admin access that wait for review, oldest first, with the tool and arguments each would run".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                read_only_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "approve_change".into(),
+            description: Some("Admin: run a pending change now, returning what its tool returns. If it fails it stays pending".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "integer",
+                        "description": "The pending change's ID, from list_pending_changes"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+        Tool {
+            name: "reject_change".into(),
+            description: Some("Admin: drop a pending change without running it".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({
+                    "id": {
+                        "type": "integer",
+                        "description": "The pending change's ID, from list_pending_changes"
+                    }
+                }));
+                map.insert("required".to_string(), serde_json::json!(["id"]));
+                Arc::new(map)
+            },
+            annotations: Some(ToolAnnotations {
+                destructive_hint: Some(true),
+                ..Default::default()
+            }),
+        },
+        Tool {
+            name: "promote".into(),
+            description: Some("Admin: on a standby (--standby-of), apply the primary's last changes if it still answers, stop following it and start taking changes here. Fails on a server that is not following a primary".into()),
+            input_schema: {
+                let mut map = serde_json::Map::new();
+                map.insert("type".to_string(), serde_json::Value::String("object".to_string()));
+                map.insert("properties".to_string(), serde_json::json!({}));
+                map.insert("required".to_string(), serde_json::json!([]));
+                Arc::new(map)
+            },
+            annotations: None,
+        },
+    ])
+}
+
+impl ServerHandler for Connection {
+    fn get_info(&self) -> rmcp::model::ServerInfo {
+        self.server_info()
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let shims = self.compat.shims_for(context.peer.peer_info().map(|info| &info.client_info));
+        Ok(ListToolsResult {
+            tools: compat::adjust_tools(&shims, self.enabled_tools()),
+            next_cursor: None,
+        })
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, ErrorData> {
+        let cats = self.cats.list().await?;
+        let mut all = RawResource::new(ALL_CATS_URI, "All cats");
+        all.description = Some(format!("The records of all {} cats", cats.len()));
+        all.mime_type = Some("application/json".to_string());
+        let mut resources = vec![all.no_annotation()];
+        for cat in &cats {
+            let mut record = RawResource::new(cat_uri(cat.id), cat.name.clone());
+            record.description = Some(format!("{}'s record: a {} year old {} {}", cat.name, cat.age, cat.color, cat.breed));
+            record.mime_type = Some("application/json".to_string());
+            resources.push(record.no_annotation());
+            let mut history = RawResource::new(timeline::uri(cat.id), format!("{} timeline", cat.name));
+            history.description = Some(format!("Everything recorded about {}, oldest first", cat.name));
+            history.mime_type = Some("application/json".to_string());
+            resources.push(history.no_annotation());
+        }
+        for (format, mime_type) in EXPORT_FORMATS {
+            let (content, cats, _) = self.export(format).await?;
+            let mut resource = RawResource::new(export_uri(format), format!("Export as {}", format));
+            resource.description = Some(format!(
+                "All {} cats as export_cats returns them. Add ?offset=<byte>&length=<bytes> to the URI to read it in pieces",
+                cats
+            ));
+            resource.mime_type = Some(mime_type.to_string());
+            resource.size = u32::try_from(content.len()).ok();
+            resources.push(resource.no_annotation());
+        }
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: None,
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, ErrorData> {
+        Ok(ListPromptsResult {
+            prompts: prompts::prompts(),
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, ErrorData> {
+        self.prompt(&request.name, &request.arguments.unwrap_or_default()).await
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, ErrorData> {
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resource_templates(),
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, ErrorData> {
+        let (uri, range) = chunk::parse(&request.uri)?;
+        let contents = self.resource_contents(uri).await?;
+        Ok(ReadResourceResult {
+            contents: match range {
+                Some(range) => chunk::apply(uri, contents, range)?,
+                None => contents,
+            },
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        // Only resources that can be read, so a typo fails now rather than never notifying.
+        let (uri, _) = chunk::parse(&request.uri)?;
+        self.resource_contents(uri).await?;
+        self.connections.subscribe(self.id, uri).await;
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), ErrorData> {
+        let (uri, _) = chunk::parse(&request.uri)?;
+        if !self.connections.unsubscribe(self.id, uri).await {
+            return Err(ErrorData {
+                code: ErrorCode::INVALID_PARAMS,
+                message: format!("Not subscribed to {}", uri).into(),
+                data: None,
+            });
+        }
+        Ok(())
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let client = context.peer.peer_info().map(|info| info.client_info.name.clone());
+        let (result, _) = self.call(request, Some(&context.peer), client).await?;
+        Ok(result)
+    }
+}
+
+impl Connection {
+    /// Runs a tool call from this connection, returning its result and the
+    /// records it changed. `peer` is the MCP client that made it, for
+    /// notifications and compatibility shims; calls through the REST API have
+    /// none. `client` is the caller's name as the audit log records it.
+    pub async fn call(
+        &self,
+        mut request: CallToolRequestParam,
+        peer: Option<&Peer<RoleServer>>,
+        client: Option<String>,
+    ) -> Result<(CallToolResult, Vec<audit::Change>), ErrorData> {
+        let Some(_in_flight) = self.drain.try_begin() else {
+            return Err(ErrorData {
+                code: ErrorCode::INTERNAL_ERROR,
+                message: "Server is shutting down and no longer accepts tool calls".into(),
+                data: None,
+            });
+        };
+
+        self.connections.count_call(self.id).await;
+        // Filtered by the name the client used, so `[tools]` may name an alias.
+        self.check_enabled(&request.name)?;
+        if let Some(target) = deprecation::resolve(&request.name) {
+            tracing::warn!("Deprecated tool {} called; forwarding to {}", request.name, target);
+            request.name = target.into();
+        }
+        // Taken out before anything else looks at the arguments, so the token is
+        // never logged, audited or checked against the schema.
+        let admin_token = request.arguments.as_mut().and_then(|arguments| arguments.remove(admin::ARGUMENT));
+        if admin::TOOLS.contains(&request.name.as_ref()) {
+            self.admin.check(&request.name, self.transport, admin_token.as_ref())?;
+        }
+        if let Some(chaos) = &self.chaos {
+            chaos.before_call(&request.name).await?;
+        }
+        let mut argument_notes = Vec::new();
+        if let Some(arguments) = request.arguments.as_mut() {
+            if let Some(tool) = self.advertised_tools().iter().find(|tool| tool.name == request.name) {
+                self.field_aliases.resolve(&tool.input_schema, arguments);
+                if self.argument_mode == schema::Mode::Lenient {
+                    argument_notes = schema::coerce(&tool.input_schema, arguments);
+                }
+            }
+        }
+
+        if !maintenance::allows(&request.name) {
+            if let Some(message) = self.maintenance.read().await.clone() {
+                return Err(ErrorData {
+                    code: ErrorCode::INTERNAL_ERROR,
+                    message: message.into(),
+                    data: None,
+                });
+            }
+        }
+        // Admins are trusted (and may be the ones sorting the anomaly out).
+        let watched = !admin::TOOLS.contains(&request.name.as_ref());
+        if let Some(alert) = self.anomalies.check(self.id, anomaly::Signal::Failure).await.filter(|_| watched) {
+            return Err(self.throttle(self, peer, client.clone(), alert).await);
+        }
+        if let Some(signal) = anomaly::Signal::for_tool(&request.name) {
+            if let Some(alert) = self.anomalies.record(self.id, signal).await {
+                return Err(self.throttle(self, peer, client.clone(), alert).await);
+            }
+        }
+
+        // Taken before any lock, so a call waiting for its lane holds nothing up.
+        let _slot = self.lanes.enter(&request.name).await;
+        let mutating = receipt::MUTATING_TOOLS.contains(&request.name.as_ref());
+        let in_memory = receipt::IN_MEMORY_TOOLS.contains(&request.name.as_ref());
+        let outage = self.backend.outage().await;
+        if let Some(outage) = outage.as_ref().filter(|_| mutating && !maintenance::allows(&request.name)) {
+            return Err(outage::refusal(outage));
+        }
+        if let Some(standby) = self.standby.as_ref().filter(|standby| mutating && standby.following()) {
+            return Err(standby.refusal());
+        }
+        if mutating || in_memory {
+            for (name, value) in request.arguments.iter_mut().flatten() {
+                if !sanitize::BULK_ARGUMENTS.contains(&(request.name.as_ref(), name.as_str())) {
+                    self.sanitizer.clean_value(value);
+                }
+            }
+        }
+        let shims = self.compat.shims_for(peer.and_then(|peer| peer.peer_info()).map(|info| &info.client_info));
+        if self.review.holds(&request.name) && self.admin.check(&request.name, self.transport, admin_token.as_ref()).is_err() {
+            self.check_arguments(&request).await?;
+            let change = self.review.submit(&request, self.id, client.clone());
+            self.session.write().await.record(&request, true);
+            tracing::info!("Queued {} from session {} for review as change {}", change.tool, self.id, change.id);
+            let queued = self.templates.render("change_queued", context! { id => change.id, tool => change.tool })?;
+            let result = CallToolResult {
+                content: compat::adjust_content(&shims, vec![queued]),
+                is_error: Some(false),
+            };
+            return Ok((result, Vec::new()));
+        }
+        // Taken out before dispatch, so handlers and the schema check never see it.
+        let idempotency_key = match request.arguments.as_mut().filter(|_| mutating).and_then(|arguments| arguments.remove(idempotency::ARGUMENT)) {
+            Some(serde_json::Value::String(key)) => Some(key),
+            Some(_) => {
+                return Err(ErrorData {
+                    code: ErrorCode::INVALID_PARAMS,
+                    message: format!("{} must be a string", idempotency::ARGUMENT).into(),
+                    data: None,
+                });
+            }
+            None => None,
+        };
+        let _keyed = match &idempotency_key {
+            Some(_) => Some(self.idempotency.begin().await),
+            None => None,
+        };
+        if let Some(key) = &idempotency_key {
+            if let Some(replayed) = self.idempotency.replay(&request.name, key, &request.arguments).await? {
+                tracing::info!("Replaying {} for idempotency key {}", request.name, key);
+                let result = CallToolResult {
+                    content: compat::adjust_content(&shims, replayed),
+                    is_error: Some(false),
+                };
+                return Ok((result, Vec::new()));
+            }
+        }
+
+        let audited = match mutating {
+            true => {
+                let writing = self.audit.begin().await;
+                self.journal.start();
+                Some((writing, self.history.checkpoint().await))
+            }
+            false => None,
+        };
+        let outcome = match self.query_cache.lookup(&request.name, request.arguments.as_ref()) {
+            Some(Ok(cached)) => Ok(cached),
+            Some(Err(miss)) => {
+                let outcome = self.dispatch_tool(self, &request).await;
+                if let Ok(content) = &outcome {
+                    self.query_cache.store(miss, content);
+                }
+                outcome
+            }
+            None => self.dispatch_tool(self, &request).await,
+        };
+        if mutating || in_memory {
+            self.query_cache.invalidate();
+        }
+        self.session.write().await.record(&request, outcome.is_ok());
+        if outcome.is_err() && watched {
+            if let Some(alert) = self.anomalies.record(self.id, anomaly::Signal::Failure).await {
+                self.throttle(self, peer, client.clone(), alert).await;
+            }
+        }
+        let mut result = outcome?;
+        let mut changes = Vec::new();
+        if let Some((_writing, history)) = audited {
+            let at = chrono::Local::now().naive_local();
+            changes = self.journal.take();
+            // Saved before anything hears of the change, and undone if that
+            // fails, so a call never reports failure with its change applied.
+            let saved = match SAVES_ITSELF.contains(&request.name.as_ref()) {
+                true => Ok(()),
+                false => self.persist().await,
+            };
+            if let Err(e) = saved {
+                let rolled_back = self.roll_back(&changes, &history).await;
+                self.query_cache.invalidate();
+                if let Err(rollback) = rolled_back {
+                    return Err(ErrorData {
+                        code: ErrorCode::INTERNAL_ERROR,
+                        message: format!("{} Undoing the change in memory also failed, so it may be partly applied: {}", e.message, rollback.message).into(),
+                        data: None,
+                    });
+                }
+                return Err(e);
+            }
+            if undo::TOOLS.contains(&request.name.as_ref()) {
+                self.undo.write().await.push(undo::Operation {
+                    tool: request.name.to_string(),
+                    at,
+                    changes: changes.clone(),
+                });
+            }
+            // Written before the lock is released, so the log has every
+            // mutation in the order it was applied.
+            self.audit
+                .record(audit::Entry {
+                    at,
+                    tool: request.name.to_string(),
+                    client: client.clone(),
+                    arguments: request.arguments.clone().map(serde_json::Value::Object).unwrap_or(serde_json::Value::Null),
+                    changes: changes.clone(),
+                })
+                .await;
+            self.events.publish(events::Mutation { changes: changes.clone() });
+        }
+        // Everything a tool returns passes through here; receipts are added afterwards
+        // because their signature covers the payload exactly as it is sent.
+        self.redactor.apply(&mut result);
+        let mut suspicious: Vec<&str> = result
+            .iter()
+            .filter_map(|content| content.as_text())
+            .flat_map(|text| self.sanitizer.suspicious_phrases(&text.text))
+            .collect();
+        suspicious.sort();
+        suspicious.dedup();
+        if !suspicious.is_empty() {
+            result.push(self.templates.render("suspicious_text", context! { phrases => suspicious })?);
+        }
+        if !argument_notes.is_empty() {
+            result.push(self.templates.render("argument_notes", context! { notes => argument_notes })?);
+        }
+        if let Some(outage) = outage.filter(|_| !mutating && !self.store.as_ref().is_some_and(store::Store::writes_rows)) {
+            result.push(self.templates.render("stale_data", context! {
+                since => outage.since.format("%Y-%m-%d %H:%M:%S").to_string(),
+            })?);
+        }
+        if mutating {
+            // The receipt vouches for what was stored rather than what was asked
+            // for: every record the call changed, before and after, masked like
+            // the rest of the result.
+            let payload = self.redactor.apply_value(serde_json::json!({ "changes": changes }));
+            let receipt = self.receipts.sign(&request.name, payload);
+            result.push(self.templates.render("receipt", context! { content => self.json.write(&receipt)? })?);
+        }
+        if let Some(key) = &idempotency_key {
+            self.idempotency.remember(&request.name, key, request.arguments.clone(), &result).await;
+        }
+
+        if let Some(chaos) = &self.chaos {
+            result = chaos.after_call(&request.name, result);
+        }
+
+        let result = CallToolResult {
+            content: compat::adjust_content(&shims, result),
+            is_error: Some(false),
+        };
+        Ok((result, changes))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    config::load()?;
+    tracing_subscriber::fmt().with_env_filter(config::log_filter()).init();
+
+    if std::env::args().nth(1).as_deref() == Some(benchmark::SUBCOMMAND) {
+        return benchmark::run();
+    }
+
+    if std::env::args().skip(1).any(|arg| arg == migration::FLAG) {
+        let Some(store) = store::from_args().await? else {
+            anyhow::bail!("{} needs a store to check: --db, --data-file or DATABASE_URL", migration::FLAG);
+        };
+        return migration::dry_run(&store).await;
+    }
+
+    info!("🐱 Starting Cat Database MCP Server...");
+    info!("📂 Data directory: {}", config::data_dir().display());
+
+    let unknown = features::Switches::from_config().unknown();
+    if !unknown.is_empty() {
+        anyhow::bail!("The feature configuration names features that do not exist: {} (known: {})", unknown.join(", "), features::ALL.join(", "));
+    }
+    let transports = transport::Transports::from_args()?;
+    let adopter_weights = matchmaking::AdopterWeights::from_config()?;
+    let query_cache = cache::QueryCache::from_config()?;
+    let admin_access = admin::Access::from_config()?;
+    let standby = standby::Standby::from_args(admin_access.token())?;
+    let review = review::Queue::from_config()?;
+    let cats = seed::from_args()?;
+    let seed_rng = rng::from_args()?;
+    let mut server = match store::from_args().await? {
+        Some(store) => {
+            info!("💾 Persisting to {}", store.describe());
+            CatServer::new(cats).with_store(store).await?
+        }
+        None => {
+            if cats.is_empty() {
+                info!("🌱 Starting without cats; pass {} for the sample cats, or --seed <file>", seed::SAMPLES_FLAG);
+            }
+            CatServer::new(cats)
+        }
+    };
+    server.features = features::report(&transports, server.store.as_ref());
+    server.adopter_weights = adopter_weights;
+    server.query_cache = query_cache;
+    server.admin = admin_access;
+    server.review = review;
+    if server.review.enabled() {
+        info!("📝 Changes from sessions without admin access wait for review");
+    }
+    server.seed_rng = seed_rng;
+    server.standby = standby;
+    server.chaos = chaos::Chaos::from_args(seed_rng);
+    if server.chaos.is_some() {
+        tracing::warn!("🌀 Chaos mode: tool calls will be delayed, fail or return malformed content on purpose");
+    }
+    let entity_tools: Vec<Tool> = server.entities.iter().flat_map(|entity| entity.tools()).collect();
+    let handled: Vec<&str> = HANDLERS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(entity_tools.iter().map(|tool| tool.name.as_ref()))
+        .collect();
+    let handlers = selfcheck::Handlers {
+        tools: &handled,
+        prompt: &|name| prompts::Kind::parse(name).is_some(),
+        resource: &|uri| parse_resource(uri).is_some(),
+    };
+    let templates = resource_templates();
+    let template_uris: Vec<&str> = templates.iter().map(|template| template.uri_template.as_str()).collect();
+    let problems = selfcheck::check(&server.server_info().capabilities, server.advertised_tools(), &handlers, &prompts::prompts(), &template_uris);
+    if !problems.is_empty() {
+        anyhow::bail!("Startup self-check failed:\n  - {}", problems.join("\n  - "));
+    }
+    let unknown: Vec<&str> = server
+        .tool_filter
+        .named()
+        .filter(|name| !handled.contains(name) && deprecation::resolve(name).is_none())
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!("The tool configuration names tools that do not exist: {}", unknown.join(", "));
+    }
+
+    let server = Arc::new(server);
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    if server.store.is_some() {
+        tokio::spawn(server.clone().reconnect(shutdown.clone()));
+    }
+    let subscribers = server.spawn_subscribers(&shutdown);
+    if let Some(standby) = &server.standby {
+        info!("🔁 Standby of {}: applying its changes and refusing others until promoted", standby.primary());
+        tokio::spawn(standby::Standby::follow(server.clone(), shutdown.clone()));
+    }
+    if let Some(autosave) = &server.autosave {
+        info!("💾 Autosaving every {:?}", autosave.interval);
+        tokio::spawn(server.clone().autosave(shutdown.clone()));
+    }
+    #[cfg(feature = "http")]
+    if let Some(addr) = transports.http {
+        transport::serve_http(addr, server.clone(), shutdown.clone()).await?;
+        info!("📡 Serving MCP over HTTP at http://{}/sse", addr);
+    }
+    #[cfg(all(unix, feature = "unix-socket"))]
+    if let Some(path) = &transports.unix_socket {
+        transport::serve_unix_socket(path.clone(), server.clone(), shutdown.clone()).await?;
+        info!("📡 Serving MCP on Unix socket {}", path.display());
+    }
+    let mut stdio_closed = None;
+    if transports.stdio {
+        info!("📡 Serving MCP over stdio");
+        let connection = Connection::new(server.clone(), "stdio");
+        let (id, cancel) = (connection.id, shutdown.child_token());
+        let service = connection
+            .serve_with_ct(stdio(), cancel.clone())
+            .await
+            .inspect_err(|e| tracing::error!("serving error: {:?}", e))?;
+        let server = server.clone();
+        stdio_closed = Some(tokio::spawn(async move { server.connections.run(id, "stdio", service, cancel).await }));
+    }
+
+    // With stdio alone the server lives as long as its one client; otherwise
+    // it runs until it is told to stop.
+    let stdio_only_closed = async {
+        match stdio_closed {
+            Some(closed) if transports.only_stdio() => {
+                let _ = closed.await;
+            }
+            _ => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = stdio_only_closed => {
+            shutdown.cancel();
+            for subscriber in subscribers {
+                let _ = subscriber.await;
+            }
+            server.flush().await;
+            return Ok(());
+        }
+        _ = shutdown::signal() => {}
+    }
+
+    let timeout = shutdown::drain_timeout();
+    info!("🛑 Shutdown requested, draining in-flight tool calls (timeout {:?})", timeout);
+    server.drain.start_draining();
+    for peer in server.connections.peers().await {
+        let _ = peer
+            .notify_logging_message(LoggingMessageNotificationParam {
+                level: LoggingLevel::Warning,
+                logger: Some("cat-database-server".to_string()),
+                data: serde_json::json!("Server is shutting down; new tool calls will be rejected"),
+            })
+            .await;
+    }
+
+    if !server.drain.wait_idle(timeout).await {
+        tracing::warn!("Drain timeout reached with {} tool call(s) still running", server.drain.in_flight());
+    }
+
+    shutdown.cancel();
+    for subscriber in subscribers {
+        let _ = subscriber.await;
+    }
+    server.flush().await;
+    info!("👋 Server stopped");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_tool_has_one_handler() {
+        let names: Vec<&str> = HANDLERS.iter().map(|(name, _)| *name).collect();
+        let unique: std::collections::HashSet<&str> = names.iter().copied().collect();
+        assert_eq!(unique.len(), names.len(), "a tool is in HANDLERS more than once");
+        let advertised = tools();
+        for tool in advertised.iter().filter(|tool| deprecation::resolve(&tool.name).is_none()) {
+            assert!(handler(&tool.name).is_some(), "{} is advertised but has no handler", tool.name);
+        }
+        for name in names {
+            assert!(advertised.iter().any(|tool| tool.name == name), "{} has a handler but is not advertised", name);
+        }
+    }
+
+    #[test]
+    fn every_resource_template_is_served() {
+        for template in resource_templates() {
+            let uri = template.uri_template.replace("{id}", "1").replace("{breed}", "Persian");
+            assert!(parse_resource(&uri).is_some(), "{} is not served", uri);
+        }
+    }
+
+    #[test]
+    fn every_profile_is_a_valid_configuration() {
+        for (name, text) in config::PROFILES {
+            let profile: config::FileConfig = toml::from_str(text).unwrap_or_else(|e| panic!("profile {} is not valid: {}", name, e));
+            for tool in profile.tools.disabled.iter().chain(profile.tools.enabled.iter().flatten()) {
+                assert!(handler(tool).is_some(), "profile {} names unknown tool {}", name, tool);
+            }
+            for feature in &profile.features.disabled {
+                assert!(features::ALL.contains(&feature.as_str()), "profile {} names unknown feature {}", name, feature);
+            }
+        }
+    }
+
+    #[test]
+    fn every_prompt_has_a_handler() {
+        for prompt in prompts::prompts() {
+            assert!(prompts::Kind::parse(&prompt.name).is_some(), "{} has no handler", prompt.name);
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn a_store_first_started_without_seed_cats_can_still_be_seeded() {
+        let path = std::env::temp_dir().join(format!("cat-server-seed-test-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let open = || store::Store::Sqlite(cat_mcp_core::store::SqliteStore::open(&path).expect("the database opens"));
+        let samples = seed::samples().expect("the samples parse");
+
+        let server = CatServer::new(Vec::new()).with_store(open()).await.expect("the first start succeeds");
+        assert!(server.cats.list().await.expect("cats can be listed").is_empty());
+        drop(server);
+
+        let server = CatServer::new(samples.clone()).with_store(open()).await.expect("the start with samples succeeds");
+        assert_eq!(server.cats.list().await.expect("cats can be listed"), samples);
+        drop(server);
+
+        let server = CatServer::new(Vec::new()).with_store(open()).await.expect("the next start succeeds");
+        assert_eq!(server.cats.list().await.expect("cats can be listed"), samples);
+        drop(server);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,12 @@
+//! Maintenance mode, for backups and migrations without stopping the process.
+
+use crate::admin;
+
+pub const DEFAULT_MESSAGE: &str =
+    "The cat database is temporarily unavailable for maintenance. Please try again in a few minutes";
+
+/// Whether `tool` keeps working while maintenance mode is on: the admin tools,
+/// and `summarize_session` so a client can still hand its work over.
+pub fn allows(tool: &str) -> bool {
+    tool == "summarize_session" || admin::TOOLS.contains(&tool)
+}
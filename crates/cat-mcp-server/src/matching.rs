@@ -0,0 +1,212 @@
+//! Text normalization shared by the search tools.
+
+use crate::transliterate;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// NFKC (which also folds half-width katakana and full-width Latin),
+/// lowercasing, and hiragana → katakana.
+pub fn normalize(text: &str) -> String {
+    let nfkc: String = text.nfkc().collect();
+    transliterate::hiragana_to_katakana(&nfkc.to_lowercase())
+}
+
+/// Like [`normalize`], then spells any kana in romaji so "シロ" and "shiro" compare equal.
+pub fn normalize_romaji(text: &str) -> String {
+    transliterate::to_romaji(&normalize(text))
+}
+
+/// Substring match on normalized text, optionally folding kana to romaji.
+pub fn contains(candidate: &str, query: &str, fold_romaji: bool) -> bool {
+    if fold_romaji {
+        normalize_romaji(candidate).contains(&normalize_romaji(query))
+    } else {
+        normalize(candidate).contains(&normalize(query))
+    }
+}
+
+/// Built-in synonyms. Values may be phrases; lookups work in both directions.
+const DEFAULT_SYNONYMS: &[(&str, &[&str])] = &[
+    ("kitty", &["cat"]),
+    ("kitten", &["cat"]),
+    ("tuxedo", &["bicolor", "black and white", "black/white"]),
+    ("ginger", &["orange"]),
+    ("marmalade", &["orange tabby"]),
+    ("tortie", &["tortoiseshell"]),
+    ("calico", &["tricolor"]),
+    ("longhair", &["persian"]),
+];
+
+/// Reduces an English word to a rough stem ("tabbies" → "tabby", "playing" → "play").
+pub fn stem(word: &str) -> String {
+    let word = word.to_lowercase();
+    if word.chars().count() <= 3 {
+        return word;
+    }
+    if let Some(base) = word.strip_suffix("ies") {
+        return format!("{}y", base);
+    }
+    for suffix in ["sses", "xes", "ches", "shes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = word.strip_suffix(suffix) {
+            if base.chars().count() >= 3 {
+                return base.to_string();
+            }
+        }
+    }
+    if word.ends_with('s') && !word.ends_with("ss") {
+        return word[..word.len() - 1].to_string();
+    }
+    word
+}
+
+fn stem_phrase(text: &str) -> String {
+    normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(stem)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Free-text matching with synonyms and stemming.
+pub struct TextSearch {
+    /// Stemmed term → stemmed alternatives, stored in both directions.
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+impl TextSearch {
+    /// Loads the built-in synonyms plus any from the JSON file named by
+    /// `CAT_SERVER_SYNONYMS_FILE` (an object of term → list of synonyms).
+    pub fn from_env() -> Self {
+        let mut groups: Vec<(String, Vec<String>)> = DEFAULT_SYNONYMS
+            .iter()
+            .map(|(term, alternatives)| (term.to_string(), alternatives.iter().map(|a| a.to_string()).collect()))
+            .collect();
+
+        if let Ok(path) = std::env::var("CAT_SERVER_SYNONYMS_FILE") {
+            let extra = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<HashMap<String, Vec<String>>>(&text).map_err(|e| e.to_string()));
+            match extra {
+                Ok(extra) => groups.extend(extra),
+                Err(e) => tracing::warn!("Ignoring synonyms file {}: {}", path, e),
+            }
+        }
+
+        let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+        for (term, alternatives) in groups {
+            let term = stem_phrase(&term);
+            for alternative in alternatives {
+                let alternative = stem_phrase(&alternative);
+                synonyms.entry(term.clone()).or_default().push(alternative.clone());
+                synonyms.entry(alternative).or_default().push(term.clone());
+            }
+        }
+        Self { synonyms }
+    }
+
+    /// True when every query word (or one of its synonyms) appears in `text`.
+    pub fn matches(&self, text: &str, query: &str) -> bool {
+        if contains(text, query, false) {
+            return true;
+        }
+
+        let haystack = format!(" {} ", stem_phrase(text));
+        let query = stem_phrase(query);
+        if query.is_empty() {
+            return false;
+        }
+        if self.phrase_matches(&haystack, &query) {
+            return true;
+        }
+        query.split(' ').all(|word| self.phrase_matches(&haystack, word))
+    }
+
+    fn phrase_matches(&self, haystack: &str, phrase: &str) -> bool {
+        let contains_phrase = |p: &str| haystack.contains(&format!(" {} ", p));
+        contains_phrase(phrase)
+            || self
+                .synonyms
+                .get(phrase)
+                .is_some_and(|alternatives| alternatives.iter().any(|a| contains_phrase(a)))
+    }
+}
+
+/// Default confidence below which phonetic candidates are dropped.
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.6;
+
+/// A simplified Metaphone-style key. Kana is spelled in romaji first, and
+/// l/r and b/v are merged since Japanese does not distinguish them.
+pub fn phonetic_key(text: &str) -> String {
+    let letters: Vec<char> = normalize_romaji(text).chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    let mut key = String::new();
+
+    let mut i = 0;
+    while i < letters.len() {
+        let c = letters[i];
+        let next = letters.get(i + 1).copied();
+        let (code, width) = match (c, next) {
+            ('p', Some('h')) => (Some('f'), 2),
+            ('s', Some('h')) | ('c', Some('h')) => (Some('x'), 2),
+            ('t', Some('s')) => (Some('s'), 2),
+            ('c', Some('k')) => (Some('k'), 2),
+            ('g', Some('h')) | ('w', Some('h')) => (Some(c), 2),
+            ('c', Some('e' | 'i' | 'y')) => (Some('s'), 1),
+            ('c' | 'q', _) => (Some('k'), 1),
+            ('z', _) => (Some('s'), 1),
+            ('v', _) => (Some('b'), 1),
+            ('l', _) => (Some('r'), 1),
+            ('x', _) => {
+                key.push('k');
+                (Some('s'), 1)
+            }
+            ('h', _) if i > 0 => (None, 1),
+            ('a' | 'e' | 'i' | 'o' | 'u' | 'y', _) if i > 0 => (None, 1),
+            _ => (Some(c), 1),
+        };
+        if let Some(code) = code {
+            if !key.ends_with(code) {
+                key.push(code);
+            }
+        }
+        i += width;
+    }
+    key
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// 1.0 for identical strings, falling toward 0.0 as the edit distance grows.
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+/// How likely `query` is a misheard or misspelled `candidate`, from 0.0 to 1.0.
+/// Sound-alike keys weigh more than the raw spelling.
+pub fn phonetic_confidence(candidate: &str, query: &str) -> f64 {
+    let sound = similarity(&phonetic_key(candidate), &phonetic_key(query));
+    let spelling = similarity(&normalize_romaji(candidate), &normalize_romaji(query));
+    0.6 * sound + 0.4 * spelling
+}
@@ -0,0 +1,239 @@
+use crate::incident::{Incident, Severity};
+use crate::config;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// Temperament pairs that tend to clash when housed together.
+const CLASHING_TEMPERAMENTS: &[(&str, &str)] = &[
+    ("energetic", "calm"),
+    ("dominant", "shy"),
+    ("dominant", "dominant"),
+    ("territorial", "territorial"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct CompanionMatch {
+    pub cat_id: u32,
+    pub cat_name: String,
+    pub companion_id: u32,
+    pub companion_name: String,
+    pub score: i32,
+    pub reasoning: Vec<String>,
+}
+
+/// The subset of a cat record that matters for pairing.
+pub struct Candidate<'a> {
+    pub id: u32,
+    pub name: &'a str,
+    pub age: u32,
+    pub is_indoor: bool,
+    pub temperament: &'a [String],
+    pub incidents: Vec<&'a Incident>,
+}
+
+fn clashes(a: &str, b: &str) -> bool {
+    CLASHING_TEMPERAMENTS.iter().any(|(x, y)| {
+        (x.eq_ignore_ascii_case(a) && y.eq_ignore_ascii_case(b))
+            || (x.eq_ignore_ascii_case(b) && y.eq_ignore_ascii_case(a))
+    })
+}
+
+fn is_aggressive(incident: &Incident) -> bool {
+    let kind = incident.kind.to_lowercase();
+    incident.severity == Severity::High || kind.contains("aggress") || kind.contains("fight")
+}
+
+/// Scores a pair on a 0-100 scale; every adjustment is explained in `reasoning`.
+pub fn score_pair(a: &Candidate, b: &Candidate) -> CompanionMatch {
+    let mut score = 50;
+    let mut reasoning = Vec::new();
+
+    let age_gap = a.age.abs_diff(b.age);
+    match age_gap {
+        0..=2 => {
+            score += 20;
+            reasoning.push(format!("Similar ages ({} year gap): +20", age_gap));
+        }
+        3..=5 => {
+            score += 5;
+            reasoning.push(format!("Moderate age gap ({} years): +5", age_gap));
+        }
+        _ => {
+            score -= 15;
+            reasoning.push(format!("Large age gap ({} years): -15", age_gap));
+        }
+    }
+
+    if a.is_indoor == b.is_indoor {
+        score += 15;
+        let lifestyle = if a.is_indoor { "indoor" } else { "outdoor" };
+        reasoning.push(format!("Both are {} cats: +15", lifestyle));
+    } else {
+        score -= 10;
+        reasoning.push("Different indoor/outdoor lifestyles: -10".to_string());
+    }
+
+    for tag in a.temperament {
+        if b.temperament.iter().any(|other| other.eq_ignore_ascii_case(tag)) {
+            score += 5;
+            reasoning.push(format!("Shared temperament \"{}\": +5", tag));
+        }
+        for other in b.temperament {
+            if clashes(tag, other) {
+                score -= 15;
+                reasoning.push(format!("Clashing temperaments \"{}\" / \"{}\": -15", tag, other));
+            }
+        }
+    }
+
+    for candidate in [a, b] {
+        let aggressive = candidate.incidents.iter().filter(|incident| is_aggressive(incident)).count();
+        if aggressive > 0 {
+            let penalty = (aggressive as i32 * 10).min(30);
+            score -= penalty;
+            reasoning.push(format!(
+                "{} has {} aggressive or high-severity incident(s): -{}",
+                candidate.name, aggressive, penalty
+            ));
+        }
+    }
+
+    CompanionMatch {
+        cat_id: a.id,
+        cat_name: a.name.to_string(),
+        companion_id: b.id,
+        companion_name: b.name.to_string(),
+        score: score.clamp(0, 100),
+        reasoning,
+    }
+}
+
+const SOCIABLE_TEMPERAMENTS: &[&str] = &["calm", "gentle", "playful"];
+const UNSOCIABLE_TEMPERAMENTS: &[&str] = &["dominant", "territorial", "shy"];
+const ACTIVE_TEMPERAMENTS: &[&str] = &["energetic", "playful", "curious"];
+
+/// Weights for the adopter fit score. Each factor contributes at most its weight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdopterWeights {
+    pub home_type: i32,
+    pub other_pets: i32,
+    pub activity_level: i32,
+}
+
+impl Default for AdopterWeights {
+    fn default() -> Self {
+        Self {
+            home_type: 40,
+            other_pets: 30,
+            activity_level: 30,
+        }
+    }
+}
+
+/// The largest weight a factor can be given.
+pub const MAX_WEIGHT: i32 = 100;
+
+impl AdopterWeights {
+    /// `CAT_SERVER_WEIGHT_HOME_TYPE`, `CAT_SERVER_WEIGHT_OTHER_PETS` and
+    /// `CAT_SERVER_WEIGHT_ACTIVITY_LEVEL`, else `[matchmaking]`, else the
+    /// defaults. Each weight must be between 0 and [`MAX_WEIGHT`], and at least
+    /// one above 0, or every adopter would score the same.
+    pub fn from_config() -> anyhow::Result<Self> {
+        let section = &config::file().matchmaking;
+        let defaults = Self::default();
+        let weight = |factor: &str, variable: &str, configured: Option<i32>, default: i32| -> anyhow::Result<i32> {
+            let weight = match std::env::var(variable) {
+                Ok(value) => value.trim().parse().with_context(|| format!("{} must be a whole number, got {:?}", variable, value))?,
+                Err(_) => configured.unwrap_or(default),
+            };
+            if !(0..=MAX_WEIGHT).contains(&weight) {
+                anyhow::bail!("The {} matchmaking weight must be between 0 and {}, got {}", factor, MAX_WEIGHT, weight);
+            }
+            Ok(weight)
+        };
+        let weights = Self {
+            home_type: weight("home_type", "CAT_SERVER_WEIGHT_HOME_TYPE", section.home_type, defaults.home_type)?,
+            other_pets: weight("other_pets", "CAT_SERVER_WEIGHT_OTHER_PETS", section.other_pets, defaults.other_pets)?,
+            activity_level: weight("activity_level", "CAT_SERVER_WEIGHT_ACTIVITY_LEVEL", section.activity_level, defaults.activity_level)?,
+        };
+        if weights.home_type + weights.other_pets + weights.activity_level == 0 {
+            anyhow::bail!("At least one matchmaking weight must be above 0");
+        }
+        Ok(weights)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdopterProfile {
+    /// `apartment`, `house` or `house_with_garden`.
+    pub home_type: String,
+    #[serde(default)]
+    pub other_pets: Vec<String>,
+    /// `low`, `medium` or `high`.
+    pub activity_level: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdopterMatch {
+    pub cat_id: u32,
+    pub cat_name: String,
+    pub score: i32,
+    pub breakdown: Vec<String>,
+}
+
+fn has_any(temperament: &[String], tags: &[&str]) -> bool {
+    temperament.iter().any(|t| tags.iter().any(|tag| t.eq_ignore_ascii_case(tag)))
+}
+
+/// Scores how well a cat fits an adopter; `breakdown` lists every contribution.
+pub fn score_adopter_fit(candidate: &Candidate, profile: &AdopterProfile, weights: &AdopterWeights) -> AdopterMatch {
+    let mut score = 0;
+    let mut breakdown = Vec::new();
+
+    let home = match (profile.home_type.to_lowercase().as_str(), candidate.is_indoor) {
+        ("apartment", true) => (weights.home_type, "indoor cat suits an apartment"),
+        ("apartment", false) => (-weights.home_type, "outdoor cat is confined in an apartment"),
+        ("house_with_garden", false) => (weights.home_type, "outdoor cat can use the garden"),
+        (_, _) => (weights.home_type / 2, "home type is workable"),
+    };
+    score += home.0;
+    breakdown.push(format!("Home type: {} ({:+})", home.1, home.0));
+
+    if profile.other_pets.is_empty() || profile.other_pets.iter().all(|pet| pet.eq_ignore_ascii_case("none")) {
+        score += weights.other_pets / 2;
+        breakdown.push(format!("Other pets: none in the home ({:+})", weights.other_pets / 2));
+    } else if has_any(candidate.temperament, UNSOCIABLE_TEMPERAMENTS) {
+        score -= weights.other_pets;
+        breakdown.push(format!(
+            "Other pets: temperament may clash with {} ({:+})",
+            profile.other_pets.join(", "),
+            -weights.other_pets
+        ));
+    } else if has_any(candidate.temperament, SOCIABLE_TEMPERAMENTS) {
+        score += weights.other_pets;
+        breakdown.push(format!(
+            "Other pets: sociable temperament suits {} ({:+})",
+            profile.other_pets.join(", "),
+            weights.other_pets
+        ));
+    } else {
+        breakdown.push("Other pets: no temperament signal (+0)".to_string());
+    }
+
+    let active = has_any(candidate.temperament, ACTIVE_TEMPERAMENTS) || candidate.age <= 3;
+    let activity = match (profile.activity_level.to_lowercase().as_str(), active) {
+        ("high", true) | ("low", false) => (weights.activity_level, "activity levels match"),
+        ("high", false) => (0, "cat may be too relaxed for a very active home"),
+        ("low", true) => (-weights.activity_level / 2, "cat may need more activity than offered"),
+        (_, _) => (weights.activity_level / 2, "moderate activity suits most cats"),
+    };
+    score += activity.0;
+    breakdown.push(format!("Activity level: {} ({:+})", activity.1, activity.0));
+
+    AdopterMatch {
+        cat_id: candidate.id,
+        cat_name: candidate.name.to_string(),
+        score,
+        breakdown,
+    }
+}
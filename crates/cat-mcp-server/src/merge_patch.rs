@@ -0,0 +1,64 @@
+//! JSON Merge Patch (RFC 7386).
+
+use serde_json::Value;
+
+/// Applies `patch` to `target`: objects are merged key by key, a null removes
+/// the key, and anything else replaces the target value outright.
+pub fn apply(target: &mut Value, patch: &Value) {
+    let Value::Object(changes) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(serde_json::Map::new());
+    }
+    if let Value::Object(fields) = target {
+        for (key, change) in changes {
+            if change.is_null() {
+                fields.remove(key);
+            } else {
+                apply(fields.entry(key.clone()).or_insert(Value::Null), change);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patched(mut target: Value, patch: Value) -> Value {
+        apply(&mut target, &patch);
+        target
+    }
+
+    #[test]
+    fn merges_objects_key_by_key() {
+        let target = json!({ "a": "b", "c": { "d": "e", "f": "g" } });
+        let patch = json!({ "a": "z", "c": { "f": null } });
+        assert_eq!(patched(target, patch), json!({ "a": "z", "c": { "d": "e" } }));
+    }
+
+    #[test]
+    fn null_removes_a_key_and_missing_keys_are_left_alone() {
+        assert_eq!(patched(json!({ "a": "b", "b": "c" }), json!({ "a": null })), json!({ "b": "c" }));
+        assert_eq!(patched(json!({ "a": "b" }), json!({ "missing": null })), json!({ "a": "b" }));
+    }
+
+    #[test]
+    fn anything_but_an_object_replaces_the_target() {
+        assert_eq!(patched(json!({ "a": ["b"] }), json!({ "a": "c" })), json!({ "a": "c" }));
+        assert_eq!(patched(json!({ "a": [{ "b": "c" }] }), json!({ "a": [1] })), json!({ "a": [1] }));
+        assert_eq!(patched(json!(["a", "b"]), json!(["c", "d"])), json!(["c", "d"]));
+        assert_eq!(patched(json!({ "a": "b" }), json!(["c"])), json!(["c"]));
+        assert_eq!(patched(json!({ "a": "foo" }), json!("bar")), json!("bar"));
+    }
+
+    #[test]
+    fn an_object_patch_turns_a_non_object_into_one() {
+        assert_eq!(patched(json!(["a"]), json!({ "a": "b" })), json!({ "a": "b" }));
+        assert_eq!(patched(json!({ "e": null }), json!({ "a": 1 })), json!({ "e": null, "a": 1 }));
+        assert_eq!(patched(json!({}), json!({ "a": { "bb": { "ccc": null } } })), json!({ "a": { "bb": {} } }));
+    }
+}
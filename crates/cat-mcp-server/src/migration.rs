@@ -0,0 +1,38 @@
+//! `--migrate`: loads the configured store, reports what the migrations in
+//! [`cat_mcp_core::migration`] would change, and exits without writing
+//! anything.
+
+pub use cat_mcp_core::migration::{CURRENT, migrate};
+use cat_mcp_core::store::Store;
+
+pub const FLAG: &str = "--migrate";
+
+/// `--migrate`: prints what loading the store would change, writing nothing.
+pub async fn dry_run(store: &Store) -> anyhow::Result<()> {
+    if store.is_fresh() {
+        println!("{} has not been written yet; there is nothing to migrate.", store.describe());
+        return Ok(());
+    }
+    let (mut data, version) = store.load_raw().await?;
+    println!("{} is in format version {}; the current version is {}.", store.describe(), version, CURRENT);
+    let steps = migrate(&mut data, version)?;
+    if steps.is_empty() {
+        println!("No migrations to apply.");
+    }
+    for step in &steps {
+        println!("\nVersion {}: {} ({} changes)", step.to, step.description, step.changes.len());
+        for change in &step.changes {
+            println!("  - {}", change);
+        }
+    }
+    match data.into_records() {
+        Ok((cats, incidents, archived)) => println!(
+            "\nAfterwards it loads cleanly: {} cats, {} archived cats, {} incidents. Start the server normally to apply.",
+            cats.len(),
+            archived.len(),
+            incidents.len()
+        ),
+        Err(e) => println!("\nAfterwards it still fails to load: {:#}", e),
+    }
+    Ok(())
+}
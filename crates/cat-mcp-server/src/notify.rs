@@ -0,0 +1,149 @@
+//! Resource change notifications, coalesced per connection.
+//!
+//! Each mutation, as read from the event bus, reports the resources it touched
+//! to every connection's [`Outbox`], and a task per connection sends them on at
+//! most once per [`WINDOW`]: one `resources/list_changed` however many cats came
+//! and went, and one `resources/updated` per URI the client subscribed to with
+//! `resources/subscribe`, however often it changed. A client that reads slowly
+//! only holds up its own task, and its outbox keeps merging in the meantime.
+//! Past [`MAX_UPDATED`] distinct URIs the individual updates are dropped for a
+//! single `list_changed`, so a bulk import costs each client a re-list rather
+//! than hundreds of messages.
+
+use crate::audit::Change;
+use crate::{ALL_CATS_URI, EXPORT_FORMATS, breed_uri, cat_uri, export_uri, matching, parse_breed_uri, timeline};
+use rmcp::RoleServer;
+use rmcp::model::ResourceUpdatedNotificationParam;
+use rmcp::service::Peer;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+/// How long to wait after a change for more to batch with it.
+pub const WINDOW: Duration = Duration::from_millis(250);
+/// The most resource URIs an outbox holds before falling back to `list_changed`.
+pub const MAX_UPDATED: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct Batch {
+    pub list_changed: bool,
+    pub updated: BTreeSet<String>,
+}
+
+impl Batch {
+    /// The resources affected by a call's changes: the record of every cat
+    /// changed and `cats://all`, the timeline of every cat changed (or whose
+    /// incidents were), the exports, and the resource list when a cat was
+    /// added, archived, restored or removed.
+    pub fn from_changes(changes: &[Change]) -> Self {
+        let listed = |state: &Value| !state.is_null() && state.get("deleted_at").is_none();
+        let mut batch = Self::default();
+        for change in changes {
+            let cat_id = match change.record.as_str() {
+                "cat" => Some(change.id),
+                _ => [&change.after, &change.before]
+                    .iter()
+                    .find_map(|state| u32::try_from(state.get("cat_id")?.as_u64()?).ok()),
+            };
+            if change.record == "cat" {
+                batch.list_changed |= listed(&change.before) != listed(&change.after);
+                batch.updated.insert(cat_uri(change.id));
+                batch.updated.insert(ALL_CATS_URI.to_string());
+                for state in [&change.before, &change.after] {
+                    if let Some(breed) = state.get("breed").and_then(Value::as_str) {
+                        batch.updated.insert(breed_uri(breed));
+                    }
+                }
+            }
+            if let Some(cat_id) = cat_id {
+                batch.updated.insert(timeline::uri(cat_id));
+            }
+        }
+        if !changes.is_empty() {
+            batch.updated.extend(EXPORT_FORMATS.iter().map(|(format, _)| export_uri(format)));
+        }
+        batch
+    }
+}
+
+/// The form subscriptions are matched in: breed URIs by their normalized
+/// breed, so a subscription to `cats://breed/persian` hears about Persian cats.
+fn canonical(uri: &str) -> String {
+    match parse_breed_uri(uri) {
+        Some(breed) => breed_uri(&matching::normalize(breed.trim())),
+        None => uri.to_string(),
+    }
+}
+
+/// Notifications waiting to be sent to one client.
+#[derive(Default)]
+pub struct Outbox {
+    pending: Mutex<Batch>,
+    ready: Notify,
+    /// The URIs the client subscribed to, by [`canonical`] form, each as the
+    /// client gave it.
+    subscriptions: Mutex<BTreeMap<String, String>>,
+}
+
+impl Outbox {
+    pub fn subscribe(&self, uri: &str) {
+        self.subscriptions.lock().expect("outbox poisoned").insert(canonical(uri), uri.to_string());
+    }
+
+    /// Whether the client was subscribed to `uri`.
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        self.subscriptions.lock().expect("outbox poisoned").remove(&canonical(uri)).is_some()
+    }
+
+    /// Queues `batch`'s list change, and its updates to subscribed URIs.
+    pub fn push(&self, batch: &Batch) {
+        let updated: Vec<String> = {
+            let subscriptions = self.subscriptions.lock().expect("outbox poisoned");
+            batch.updated.iter().filter_map(|uri| subscriptions.get(&canonical(uri)).cloned()).collect()
+        };
+        if !batch.list_changed && updated.is_empty() {
+            return;
+        }
+        {
+            let mut pending = self.pending.lock().expect("outbox poisoned");
+            pending.list_changed |= batch.list_changed;
+            pending.updated.extend(updated);
+            if pending.updated.len() > MAX_UPDATED {
+                pending.updated.clear();
+                pending.list_changed = true;
+            }
+        }
+        self.ready.notify_one();
+    }
+
+    /// Sends what is pushed to `peer` until `stop` fires or the client stops
+    /// accepting notifications.
+    pub async fn run(&self, peer: Peer<RoleServer>, stop: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = self.ready.notified() => {}
+                _ = stop.cancelled() => return,
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(WINDOW) => {}
+                _ = stop.cancelled() => return,
+            }
+            let batch = std::mem::take(&mut *self.pending.lock().expect("outbox poisoned"));
+            if batch.list_changed {
+                if let Err(e) = peer.notify_resource_list_changed().await {
+                    tracing::debug!("Could not send resources/list_changed: {:?}", e);
+                    return;
+                }
+            }
+            for uri in batch.updated {
+                if let Err(e) = peer.notify_resource_updated(ResourceUpdatedNotificationParam { uri }).await {
+                    tracing::debug!("Could not send resources/updated: {:?}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Keeping the server useful while its store is unreachable.
+//!
+//! With the JSON file, reads never touch the store, so they carry on from
+//! memory, with a note that the data may be out of date. With a database,
+//! reads and writes go to it, so both fail with a retryable error. Mutations
+//! are refused up front either way, since they could not be saved. Meanwhile a
+//! background task keeps trying the store with exponential backoff (writing the
+//! current state to the JSON file, or a no-op write to the database), and the
+//! outage ends with the first attempt that succeeds.
+
+use chrono::NaiveDateTime;
+use rmcp::{
+    ErrorData,
+    model::ErrorCode,
+};
+use std::time::Duration;
+use tokio::sync::{Notify, RwLock};
+
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct Outage {
+    pub since: NaiveDateTime,
+    pub error: String,
+    /// Reconnection attempts that have failed so far.
+    pub attempts: u32,
+}
+
+#[derive(Default)]
+pub struct Backend {
+    outage: RwLock<Option<Outage>>,
+    started: Notify,
+}
+
+impl Backend {
+    pub async fn outage(&self) -> Option<Outage> {
+        self.outage.read().await.clone()
+    }
+
+    /// Records a failed write, starting an outage if there is none yet, and
+    /// returns the outage.
+    pub async fn failed(&self, error: String) -> Outage {
+        let mut outage = self.outage.write().await;
+        match outage.as_mut() {
+            Some(outage) => {
+                outage.error = error;
+                outage.attempts += 1;
+                outage.clone()
+            }
+            None => {
+                tracing::warn!("💥 Store unreachable, refusing changes until it is back: {}", error);
+                let started = Outage {
+                    since: chrono::Local::now().naive_local(),
+                    error,
+                    attempts: 0,
+                };
+                *outage = Some(started.clone());
+                self.started.notify_one();
+                started
+            }
+        }
+    }
+
+    pub async fn recovered(&self) {
+        if let Some(outage) = self.outage.write().await.take() {
+            tracing::info!("💾 Store reachable again after {} attempts; accepting changes", outage.attempts + 1);
+        }
+    }
+
+    /// Waits until an outage starts.
+    pub async fn outage_started(&self) {
+        self.started.notified().await
+    }
+}
+
+/// How long to wait before reconnection attempt `attempt` (counting from 0).
+pub fn backoff(attempt: u32) -> Duration {
+    FIRST_RETRY.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY)
+}
+
+/// The error a mutating tool gets during an outage.
+pub fn refusal(outage: &Outage) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!(
+            "The database has been unreachable since {} ({}); changes are refused until it is back. Nothing was changed, so retry in a little while",
+            outage.since.format("%Y-%m-%d %H:%M:%S"),
+            outage.error
+        )
+        .into(),
+        data: Some(serde_json::json!({
+            "retryable": true,
+            "retry_after_secs": backoff(outage.attempts).as_secs(),
+        })),
+    }
+}
+
+/// The error a read gets when the database cannot be reached.
+pub fn unreadable(outage: &Outage) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INTERNAL_ERROR,
+        message: format!(
+            "The database has been unreachable since {} ({}), so it could not be read. Retry in a little while",
+            outage.since.format("%Y-%m-%d %H:%M:%S"),
+            outage.error
+        )
+        .into(),
+        data: Some(serde_json::json!({
+            "retryable": true,
+            "retry_after_secs": backoff(outage.attempts).as_secs(),
+        })),
+    }
+}
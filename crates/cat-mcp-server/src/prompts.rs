@@ -0,0 +1,89 @@
+//! Prompt templates for cat-care writing tasks, offered through `prompts/list`
+//! and `prompts/get`. Each is filled in with the live records of the cats it
+//! names and their incidents, so the model writes from current data rather
+//! than from what it remembers. The wording comes from the `prompt_*`
+//! response templates and can be overridden like any other.
+
+use rmcp::{
+    ErrorData,
+    model::{ErrorCode, JsonObject, Prompt, PromptArgument},
+};
+
+pub const SUMMARIZE_PROFILE: &str = "summarize_cat_profile";
+pub const ADOPTION_LISTING: &str = "draft_adoption_listing";
+pub const COMPARE_CATS: &str = "compare_cats";
+
+/// The prompts `CatServer::prompt` knows how to fill in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    SummarizeProfile,
+    AdoptionListing,
+    CompareCats,
+}
+
+impl Kind {
+    /// The prompt called `name`, if there is a way to fill it in. The startup
+    /// self-check holds every prompt in [`prompts`] to this.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            SUMMARIZE_PROFILE => Some(Kind::SummarizeProfile),
+            ADOPTION_LISTING => Some(Kind::AdoptionListing),
+            COMPARE_CATS => Some(Kind::CompareCats),
+            _ => None,
+        }
+    }
+}
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required: Some(required),
+    }
+}
+
+/// Every prompt the server offers.
+pub fn prompts() -> Vec<Prompt> {
+    vec![
+        Prompt::new(
+            SUMMARIZE_PROFILE,
+            Some("Summarize one cat's profile: personality, care needs and behavior history"),
+            Some(vec![argument("cat_id", "The cat's ID", true)]),
+        ),
+        Prompt::new(
+            ADOPTION_LISTING,
+            Some("Draft an adoption listing for a cat from its record"),
+            Some(vec![
+                argument("cat_id", "The cat's ID", true),
+                argument("tone", "The listing's tone, e.g. playful or formal (optional)", false),
+            ]),
+        ),
+        Prompt::new(
+            COMPARE_CATS,
+            Some("Compare two cats for someone deciding between them"),
+            Some(vec![
+                argument("first_cat_id", "One cat's ID", true),
+                argument("second_cat_id", "The other cat's ID", true),
+            ]),
+        ),
+    ]
+}
+
+/// A cat ID argument. Prompt arguments arrive as strings, but numbers are
+/// accepted too.
+pub fn cat_id(arguments: &JsonObject, field: &str) -> Result<u32, ErrorData> {
+    let value = arguments.get(field).ok_or_else(|| ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: format!("{} is required", field).into(),
+        data: None,
+    })?;
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|text| text.trim().parse().ok()))
+        .and_then(|id| u32::try_from(id).ok())
+        .ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("{} must be a cat ID, got {}", field, value).into(),
+            data: None,
+        })
+}
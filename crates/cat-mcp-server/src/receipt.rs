@@ -0,0 +1,133 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Tools that change data and therefore return a signed receipt.
+pub const MUTATING_TOOLS: &[&str] = &[
+    "add_cat",
+    "update_cat",
+    "patch_cat",
+    "delete_cat",
+    "restore_cat",
+    "undo_last_change",
+    "redo",
+    "batch_update",
+    "import_cats_csv",
+    "log_incident",
+    "intake_cat",
+    "publish",
+    "fix_orphaned_records",
+    "recompute_versions",
+    "normalize_text_fields",
+    "approve_change",
+];
+
+/// Tools that change only what the server keeps in memory: waitlists and
+/// drafts, which are lost when it restarts. Nothing is stored, so they return
+/// no receipt and are neither saved, undone nor audited.
+pub const IN_MEMORY_TOOLS: &[&str] = &["join_waitlist", "advance_waitlist", "save_draft", "discard_draft"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    pub tool: String,
+    /// `{"changes": [...]}`: each cat or incident the call changed, as stored
+    /// before and after it.
+    pub payload: serde_json::Value,
+    pub timestamp: String,
+    /// Hex-encoded HMAC-SHA256 over tool, timestamp and payload.
+    pub signature: String,
+}
+
+pub struct ReceiptSigner {
+    key: Vec<u8>,
+}
+
+impl ReceiptSigner {
+    /// Uses `CAT_SERVER_RECEIPT_KEY` when set. Otherwise a random key is
+    /// generated, and receipts only verify until the server restarts.
+    pub fn from_env() -> Self {
+        let key = match std::env::var("CAT_SERVER_RECEIPT_KEY") {
+            Ok(key) if !key.is_empty() => key.into_bytes(),
+            _ => {
+                tracing::warn!("CAT_SERVER_RECEIPT_KEY not set, receipts will not verify after a restart");
+                rand::random::<[u8; 32]>().to_vec()
+            }
+        };
+        Self { key }
+    }
+
+    fn mac(&self, tool: &str, timestamp: &str, payload: &serde_json::Value) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        // serde_json sorts object keys, so the payload encoding is canonical.
+        mac.update(tool.as_bytes());
+        mac.update(b"\n");
+        mac.update(timestamp.as_bytes());
+        mac.update(b"\n");
+        mac.update(payload.to_string().as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, tool: &str, payload: serde_json::Value) -> Receipt {
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        let signature = hex::encode(self.mac(tool, &timestamp, &payload).finalize().into_bytes());
+        Receipt {
+            tool: tool.to_string(),
+            payload,
+            timestamp,
+            signature,
+        }
+    }
+
+    pub fn verify(&self, receipt: &Receipt) -> bool {
+        let Ok(signature) = hex::decode(&receipt.signature) else {
+            return false;
+        };
+        self.mac(&receipt.tool, &receipt.timestamp, &receipt.payload)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(key: &str) -> ReceiptSigner {
+        ReceiptSigner { key: key.as_bytes().to_vec() }
+    }
+
+    fn receipt() -> Receipt {
+        signer("secret").sign("add_cat", serde_json::json!({ "changes": [{ "record": "cat", "id": 1, "before": null, "after": { "name": "Mochi" } }] }))
+    }
+
+    #[test]
+    fn a_receipt_verifies_with_the_key_that_signed_it() {
+        let receipt = receipt();
+        assert_eq!(receipt.signature.len(), 64);
+        assert!(signer("secret").verify(&receipt));
+        assert!(!signer("other").verify(&receipt));
+    }
+
+    #[test]
+    fn any_change_to_a_receipt_breaks_its_signature() {
+        let mut tool = receipt();
+        tool.tool = "delete_cat".to_string();
+        let mut payload = receipt();
+        payload.payload["changes"][0]["after"]["name"] = serde_json::json!("Tama");
+        let mut timestamp = receipt();
+        timestamp.timestamp = "2000-01-01T00:00:00+00:00".to_string();
+        let mut signature = receipt();
+        signature.signature = "not hex".to_string();
+        for receipt in [tool, payload, timestamp, signature] {
+            assert!(!signer("secret").verify(&receipt), "{:?} verified", receipt);
+        }
+    }
+
+    #[test]
+    fn a_receipt_still_verifies_after_a_round_trip_through_json() {
+        let receipt: Receipt = serde_json::from_str(&serde_json::to_string(&receipt()).expect("serializes")).expect("deserializes");
+        assert!(signer("secret").verify(&receipt));
+    }
+}
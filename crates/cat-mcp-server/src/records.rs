@@ -0,0 +1,266 @@
+//! The cats and incidents as the tool handlers see them: the repositories,
+//! with their errors turned into tool errors, and every write noted in the
+//! [`Journal`].
+//!
+//! With a database the repositories are the store's own, so every read and
+//! write goes to it a row at a time. Otherwise they are in memory, and the JSON
+//! file (if any) is written from them by `CatServer::save_now`.
+//!
+//! A write that cannot reach the database starts an outage, as a failed save
+//! does; see [`crate::outage`]. A cat update based on an older version than the
+//! stored one is refused, so two servers sharing a database cannot overwrite
+//! each other's changes.
+
+use crate::audit::Change;
+use crate::outage;
+use cat_mcp_core::Cat;
+use cat_mcp_core::incident::Incident;
+use cat_mcp_core::repository::{ArchivedCat, CatRepository, IncidentRepository, RepositoryError, RepositoryResult};
+use chrono::NaiveDateTime;
+use rmcp::{ErrorData, model::ErrorCode};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Every record written since [`Journal::start`], before and after, as
+/// `CatServer::set_record` takes them back: a live cat as itself, an archived
+/// one with its `deleted_at`, and a missing record as `null`.
+///
+/// Mutations run one at a time (see [`crate::audit::AuditLog::begin`]), so the
+/// journal of a mutating call holds only that call's writes.
+#[derive(Default)]
+pub struct Journal(Mutex<Vec<Change>>);
+
+impl Journal {
+    /// Forgets earlier writes, at the start of a mutating call.
+    pub fn start(&self) {
+        self.0.lock().expect("journal poisoned").clear();
+    }
+
+    /// Where the journal is now, for [`Journal::since`].
+    pub fn mark(&self) -> usize {
+        self.0.lock().expect("journal poisoned").len()
+    }
+
+    /// What changed since `mark`, one change per record.
+    pub fn since(&self, mark: usize) -> Vec<Change> {
+        coalesce(self.0.lock().expect("journal poisoned").get(mark..).unwrap_or_default())
+    }
+
+    /// What changed since [`Journal::start`], one change per record.
+    pub fn take(&self) -> Vec<Change> {
+        coalesce(&std::mem::take(&mut *self.0.lock().expect("journal poisoned")))
+    }
+
+    fn note(&self, record: &str, id: u32, before: Value, after: Value) {
+        self.0.lock().expect("journal poisoned").push(Change {
+            record: record.to_string(),
+            id,
+            before,
+            after,
+        });
+    }
+}
+
+/// Each record's state before its first write and after its last, leaving out
+/// records that ended up as they started, in record and ID order.
+fn coalesce(writes: &[Change]) -> Vec<Change> {
+    let mut records: BTreeMap<(&str, u32), (&Value, &Value)> = BTreeMap::new();
+    for write in writes {
+        records
+            .entry((write.record.as_str(), write.id))
+            .and_modify(|(_, after)| *after = &write.after)
+            .or_insert((&write.before, &write.after));
+    }
+    records
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|((record, id), (before, after))| Change {
+            record: record.to_string(),
+            id,
+            before: before.clone(),
+            after: after.clone(),
+        })
+        .collect()
+}
+
+fn json<T: Serialize>(record: Option<&T>) -> Value {
+    record.and_then(|record| serde_json::to_value(record).ok()).unwrap_or(Value::Null)
+}
+
+/// A repository result as a tool result. An unreachable backend starts (or
+/// continues) the outage.
+async fn checked<T>(backend: &outage::Backend, write: bool, result: RepositoryResult<T>) -> Result<T, ErrorData> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(RepositoryError::Conflict { id, current }) => Err(ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Cat {} has changed since you read it (now version {}); read it again and retry", id, current).into(),
+            data: None,
+        }),
+        Err(RepositoryError::Unavailable(reason)) => {
+            let outage = backend.failed(reason).await;
+            Err(if write { outage::refusal(&outage) } else { outage::unreadable(&outage) })
+        }
+    }
+}
+
+pub struct Cats {
+    repository: Arc<dyn CatRepository>,
+    journal: Arc<Journal>,
+    backend: Arc<outage::Backend>,
+}
+
+impl Cats {
+    pub fn new(repository: Arc<dyn CatRepository>, journal: Arc<Journal>, backend: Arc<outage::Backend>) -> Self {
+        Self { repository, journal, backend }
+    }
+
+    pub fn repository(&self) -> &Arc<dyn CatRepository> {
+        &self.repository
+    }
+
+    pub fn set_repository(&mut self, repository: Arc<dyn CatRepository>) {
+        self.repository = repository;
+    }
+
+    pub async fn list(&self) -> Result<Vec<Cat>, ErrorData> {
+        checked(&self.backend, false, self.repository.list().await).await
+    }
+
+    pub async fn get(&self, id: u32) -> Result<Option<Cat>, ErrorData> {
+        checked(&self.backend, false, self.repository.get(id).await).await
+    }
+
+    pub async fn search(&self, filter: &(dyn Fn(&Cat) -> bool + Send + Sync)) -> Result<Vec<Cat>, ErrorData> {
+        checked(&self.backend, false, self.repository.search(filter).await).await
+    }
+
+    pub async fn contains(&self, id: u32) -> Result<bool, ErrorData> {
+        checked(&self.backend, false, self.repository.contains(id).await).await
+    }
+
+    pub async fn archived(&self) -> Result<Vec<ArchivedCat>, ErrorData> {
+        checked(&self.backend, false, self.repository.archived().await).await
+    }
+
+    /// The cat as the journal has it: live, archived, or `null`.
+    pub async fn state(&self, id: u32) -> Result<Value, ErrorData> {
+        if let Some(cat) = self.get(id).await? {
+            return Ok(json(Some(&cat)));
+        }
+        Ok(json(self.archived().await?.iter().find(|entry| entry.cat.id == id)))
+    }
+
+    pub async fn insert(&self, cat: Cat) -> Result<Cat, ErrorData> {
+        let cat = checked(&self.backend, true, self.repository.insert(cat).await).await?;
+        self.journal.note("cat", cat.id, Value::Null, json(Some(&cat)));
+        Ok(cat)
+    }
+
+    /// Stores `cat` one version above, if it is still at `cat.version`;
+    /// returns the cat it replaced.
+    pub async fn update(&self, cat: Cat) -> Result<Option<Cat>, ErrorData> {
+        let mut stored = cat.clone();
+        let previous = checked(&self.backend, true, self.repository.update(cat).await).await?;
+        if let Some(previous) = &previous {
+            stored.version += 1;
+            self.journal.note("cat", stored.id, json(Some(previous)), json(Some(&stored)));
+        }
+        Ok(previous)
+    }
+
+    /// Stores `cat` as given; see [`CatRepository::replace`].
+    pub async fn replace(&self, cat: Cat) -> Result<(), ErrorData> {
+        let (id, after) = (cat.id, json(Some(&cat)));
+        let previous = checked(&self.backend, true, self.repository.replace(cat).await).await?;
+        self.journal.note("cat", id, json(previous.as_ref()), after);
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: u32) -> Result<Option<Cat>, ErrorData> {
+        let previous = checked(&self.backend, true, self.repository.delete(id).await).await?;
+        if let Some(previous) = &previous {
+            self.journal.note("cat", id, json(Some(previous)), Value::Null);
+        }
+        Ok(previous)
+    }
+
+    pub async fn archive(&self, id: u32, deleted_at: NaiveDateTime) -> Result<Option<ArchivedCat>, ErrorData> {
+        let archived = checked(&self.backend, true, self.repository.archive(id, deleted_at).await).await?;
+        if let Some(entry) = &archived {
+            self.journal.note("cat", id, json(Some(&entry.cat)), json(Some(entry)));
+        }
+        Ok(archived)
+    }
+
+    /// Moves an archived cat back, returning it.
+    pub async fn restore(&self, id: u32) -> Result<Option<Cat>, ErrorData> {
+        let restored = checked(&self.backend, true, self.repository.restore(id).await).await?;
+        Ok(restored.map(|entry| {
+            self.journal.note("cat", id, json(Some(&entry)), json(Some(&entry.cat)));
+            entry.cat
+        }))
+    }
+}
+
+pub struct Incidents {
+    repository: Arc<dyn IncidentRepository>,
+    journal: Arc<Journal>,
+    backend: Arc<outage::Backend>,
+}
+
+impl Incidents {
+    pub fn new(repository: Arc<dyn IncidentRepository>, journal: Arc<Journal>, backend: Arc<outage::Backend>) -> Self {
+        Self { repository, journal, backend }
+    }
+
+    pub fn repository(&self) -> &Arc<dyn IncidentRepository> {
+        &self.repository
+    }
+
+    pub fn set_repository(&mut self, repository: Arc<dyn IncidentRepository>) {
+        self.repository = repository;
+    }
+
+    /// Every incident, in ID order.
+    pub async fn list(&self) -> Result<Vec<Incident>, ErrorData> {
+        checked(&self.backend, false, self.repository.list().await).await
+    }
+
+    pub async fn get(&self, id: u32) -> Result<Option<Incident>, ErrorData> {
+        checked(&self.backend, false, self.repository.get(id).await).await
+    }
+
+    pub async fn for_cat(&self, cat_id: u32) -> Result<Vec<Incident>, ErrorData> {
+        checked(&self.backend, false, self.repository.for_cat(cat_id).await).await
+    }
+
+    /// The incident as the journal has it, or `null`.
+    pub async fn state(&self, id: u32) -> Result<Value, ErrorData> {
+        Ok(json(self.get(id).await?.as_ref()))
+    }
+
+    /// Stores `incident` under a new ID and returns it as stored.
+    pub async fn insert(&self, incident: Incident) -> Result<Incident, ErrorData> {
+        let incident = checked(&self.backend, true, self.repository.insert(incident).await).await?;
+        self.journal.note("incident", incident.id, Value::Null, json(Some(&incident)));
+        Ok(incident)
+    }
+
+    pub async fn put(&self, incident: Incident) -> Result<(), ErrorData> {
+        let (id, after) = (incident.id, json(Some(&incident)));
+        let previous = checked(&self.backend, true, self.repository.put(incident).await).await?;
+        self.journal.note("incident", id, json(previous.as_ref()), after);
+        Ok(())
+    }
+
+    pub async fn remove(&self, id: u32) -> Result<Option<Incident>, ErrorData> {
+        let previous = checked(&self.backend, true, self.repository.remove(id).await).await?;
+        if let Some(previous) = &previous {
+            self.journal.note("incident", id, json(Some(previous)), Value::Null);
+        }
+        Ok(previous)
+    }
+}
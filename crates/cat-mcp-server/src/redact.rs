@@ -0,0 +1,82 @@
+//! Masks sensitive data in outgoing tool results.
+
+use regex::Regex;
+use rmcp::model::{Content, RawContent};
+use serde::Deserialize;
+
+const MASK: &str = "[REDACTED]";
+
+/// Shape of the file named by `CAT_SERVER_REDACTION_FILE`.
+#[derive(Debug, Default, Deserialize)]
+struct RedactionConfig {
+    /// JSON field names whose values are always masked, e.g. `"contact"`.
+    #[serde(default)]
+    fields: Vec<String>,
+    /// Regexes masked wherever they appear, e.g. phone numbers pasted into notes.
+    #[serde(default)]
+    patterns: Vec<String>,
+}
+
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// No rules unless `CAT_SERVER_REDACTION_FILE` is set; a bad file or pattern is
+    /// logged and skipped rather than stopping the server.
+    pub fn from_env() -> Self {
+        let config = match std::env::var("CAT_SERVER_REDACTION_FILE") {
+            Ok(path) => std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<RedactionConfig>(&text).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Ignoring redaction file {}: {}", path, e);
+                    RedactionConfig::default()
+                }),
+            Err(_) => RedactionConfig::default(),
+        };
+
+        let mut rules = Vec::new();
+        for field in &config.fields {
+            // Tool results embed pretty-printed JSON, so a field is `"name": value`.
+            let pattern = format!(r#""{}"\s*:\s*(?:"(?:[^"\\]|\\.)*"|[^,\}}\]\s]+)"#, regex::escape(field));
+            let replacement = format!(r#""{}": "{}""#, field, MASK);
+            rules.push((Regex::new(&pattern).expect("escaped field pattern is valid"), replacement));
+        }
+        for pattern in &config.patterns {
+            match Regex::new(pattern) {
+                Ok(regex) => rules.push((regex, MASK.to_string())),
+                Err(e) => tracing::warn!("Ignoring redaction pattern {}: {}", pattern, e),
+            }
+        }
+        Self { rules }
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        self.rules.iter().fold(text.to_string(), |text, (regex, replacement)| {
+            regex.replace_all(&text, regex::NoExpand(replacement)).into_owned()
+        })
+    }
+
+    /// Applies every rule to `value` as it would appear in a result. Should a
+    /// pattern cut through the JSON itself, the whole value is masked.
+    pub fn apply_value(&self, value: serde_json::Value) -> serde_json::Value {
+        if self.rules.is_empty() {
+            return value;
+        }
+        let text = serde_json::to_string_pretty(&value).unwrap_or_default();
+        serde_json::from_str(&self.redact(&text)).unwrap_or_else(|_| serde_json::Value::String(MASK.to_string()))
+    }
+
+    /// Applies every rule to the text parts of a tool result.
+    pub fn apply(&self, content: &mut [Content]) {
+        if self.rules.is_empty() {
+            return;
+        }
+        for item in content {
+            if let RawContent::Text(text) = &mut item.raw {
+                text.text = self.redact(&text.text);
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Admin repairs for inconsistencies that bugs or bad imports leave behind,
+//! so they can be fixed without editing the data file by hand.
+//!
+//! Each repair only works out what it would change, one [`Fix`] per record;
+//! the `fix_orphaned_records`, `recompute_versions` and `normalize_text_fields`
+//! tools apply the fixes unless asked for a dry run. Cat and incident fixes go
+//! through the audit log like any other change.
+
+use crate::audit;
+use crate::draft::{Draft, RecordType};
+use crate::waitlist::WaitlistEntry;
+use cat_mcp_core::Cat;
+use cat_mcp_core::incident::Incident;
+use cat_mcp_core::repository::ArchivedCat;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Serialize)]
+pub struct Fix {
+    /// `cat`, `incident`, `waitlist` or `draft`.
+    pub record: &'static str,
+    /// The record's ID; for a waitlist, the cat it is for.
+    pub id: u32,
+    pub problems: Vec<String>,
+    /// The record as it will be stored; `null` when it is removed.
+    pub after: Value,
+}
+
+impl Fix {
+    fn removed(record: &'static str, id: u32, problem: String) -> Self {
+        Self { record, id, problems: vec![problem], after: Value::Null }
+    }
+}
+
+/// Incidents and waitlists for cats that exist neither live nor archived, and
+/// drafts editing a cat or incident that is gone.
+pub fn orphans(
+    cats: &BTreeSet<u32>,
+    incidents: &[Incident],
+    waitlists: &HashMap<u32, VecDeque<WaitlistEntry>>,
+    drafts: &HashMap<u32, Draft>,
+) -> Vec<Fix> {
+    let incident_ids: BTreeSet<u32> = incidents.iter().map(|incident| incident.id).collect();
+    let mut fixes: Vec<Fix> = incidents
+        .iter()
+        .filter(|incident| !cats.contains(&incident.cat_id))
+        .map(|incident| Fix::removed("incident", incident.id, format!("belongs to cat {}, which does not exist", incident.cat_id)))
+        .collect();
+
+    let mut waitlisted: Vec<(&u32, &VecDeque<WaitlistEntry>)> = waitlists.iter().filter(|(id, _)| !cats.contains(id)).collect();
+    waitlisted.sort_by_key(|(id, _)| **id);
+    fixes.extend(waitlisted.into_iter().map(|(id, entries)| {
+        Fix::removed("waitlist", *id, format!("{} adopter(s) waiting for cat {}, which does not exist", entries.len(), id))
+    }));
+
+    let mut drafts: Vec<&Draft> = drafts.values().collect();
+    drafts.sort_by_key(|draft| draft.id);
+    fixes.extend(drafts.into_iter().filter_map(|draft| {
+        let record_id = draft.record_id?;
+        let (kind, known) = match draft.record_type {
+            RecordType::Cat => ("cat", cats),
+            RecordType::Incident => ("incident", &incident_ids),
+        };
+        (!known.contains(&record_id)).then(|| Fix::removed("draft", draft.id, format!("edits {} {}, which does not exist", kind, record_id)))
+    }));
+    fixes
+}
+
+/// The highest version each cat has had according to the audit log.
+pub fn highest_versions(entries: &[audit::Entry]) -> BTreeMap<u32, u64> {
+    let mut highest = BTreeMap::new();
+    for change in entries.iter().flat_map(|entry| &entry.changes).filter(|change| change.record == "cat") {
+        for state in [&change.before, &change.after] {
+            if let Some(version) = state.get("version").and_then(Value::as_u64) {
+                let seen = highest.entry(change.id).or_insert(0);
+                *seen = version.max(*seen);
+            }
+        }
+    }
+    highest
+}
+
+/// Cats whose version was never set, or is lower than one they have had
+/// before (a restore or bad import put an old copy back). Either would let a
+/// client holding a stale `expected_version` overwrite newer data, so each gets
+/// a version above any it has had.
+pub fn versions(cats: &[Cat], archived: &[ArchivedCat], highest: &BTreeMap<u32, u64>) -> Vec<Fix> {
+    let fix = |cat: &Cat| -> Option<(u64, String)> {
+        let seen = highest.get(&cat.id).copied().unwrap_or(0);
+        if cat.version == 0 {
+            Some((seen + 1, format!("version was never set; set to {}", seen + 1)))
+        } else if cat.version < seen {
+            Some((seen + 1, format!("version {} is lower than version {} it had before; set to {}", cat.version, seen, seen + 1)))
+        } else {
+            None
+        }
+    };
+    let live = cats.iter().filter_map(|cat| {
+        let (version, problem) = fix(cat)?;
+        let cat = Cat { version, ..cat.clone() };
+        Some(Fix { record: "cat", id: cat.id, problems: vec![problem], after: serde_json::to_value(&cat).unwrap_or_default() })
+    });
+    let deleted = archived.iter().filter_map(|entry| {
+        let (version, problem) = fix(&entry.cat)?;
+        let entry = ArchivedCat { cat: Cat { version, ..entry.cat.clone() }, deleted_at: entry.deleted_at };
+        Some(Fix { record: "cat", id: entry.cat.id, problems: vec![problem], after: serde_json::to_value(&entry).unwrap_or_default() })
+    });
+    let mut fixes: Vec<Fix> = live.chain(deleted).collect();
+    fixes.sort_by_key(|fix| fix.id);
+    fixes
+}
+
+/// NFC, trimmed, with each run of whitespace made a single space.
+fn normalize_line(text: &str) -> String {
+    text.nfc().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// NFC, with each line's trailing whitespace and the blank lines around the
+/// text removed; line breaks inside are kept.
+fn normalize_block(text: &str) -> String {
+    let nfc: String = text.nfc().collect();
+    let lines: Vec<&str> = nfc.lines().map(str::trim_end).collect();
+    lines.join("\n").trim().to_string()
+}
+
+fn normalize_field(problems: &mut Vec<String>, field: &str, value: &mut String, normalize: fn(&str) -> String) {
+    let normalized = normalize(value);
+    if normalized != *value {
+        problems.push(format!("{}: {:?} → {:?}", field, value, normalized));
+        *value = normalized;
+    }
+}
+
+/// Normalizes each item, dropping the ones left empty and repeats.
+fn normalize_list(problems: &mut Vec<String>, field: &str, items: &mut Vec<String>) {
+    let mut normalized: Vec<String> = Vec::new();
+    for item in items.iter().map(|item| normalize_line(item)).filter(|item| !item.is_empty()) {
+        if !normalized.contains(&item) {
+            normalized.push(item);
+        }
+    }
+    if normalized != *items {
+        problems.push(format!("{}: {:?} → {:?}", field, items, normalized));
+        *items = normalized;
+    }
+}
+
+/// The problems found in `cat`'s text, which is normalized in place.
+fn normalize_cat(cat: &mut Cat) -> Vec<String> {
+    let mut problems = Vec::new();
+    normalize_field(&mut problems, "name", &mut cat.name, normalize_line);
+    normalize_field(&mut problems, "breed", &mut cat.breed, normalize_line);
+    normalize_field(&mut problems, "color", &mut cat.color, normalize_line);
+    normalize_field(&mut problems, "favorite_toy", &mut cat.favorite_toy, normalize_line);
+    normalize_list(&mut problems, "name_variants", &mut cat.name_variants);
+    normalize_list(&mut problems, "allergies", &mut cat.allergies);
+    normalize_list(&mut problems, "prescribed_diets", &mut cat.prescribed_diets);
+    normalize_list(&mut problems, "temperament", &mut cat.temperament);
+    normalize_list(&mut problems, "photos", &mut cat.photos);
+    if !problems.is_empty() {
+        cat.version += 1;
+    }
+    problems
+}
+
+/// Text with stray whitespace, mixed Unicode forms (e.g. a decomposed "ポ"),
+/// or empty and repeated list items. Normalized cats go up a version, like any
+/// other write.
+pub fn text(cats: &[Cat], archived: &[ArchivedCat], incidents: &[Incident]) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    for cat in cats {
+        let mut cat = cat.clone();
+        let problems = normalize_cat(&mut cat);
+        if !problems.is_empty() {
+            fixes.push(Fix { record: "cat", id: cat.id, problems, after: serde_json::to_value(&cat).unwrap_or_default() });
+        }
+    }
+    for entry in archived {
+        let mut entry = entry.clone();
+        let problems = normalize_cat(&mut entry.cat);
+        if !problems.is_empty() {
+            fixes.push(Fix { record: "cat", id: entry.cat.id, problems, after: serde_json::to_value(&entry).unwrap_or_default() });
+        }
+    }
+    fixes.sort_by_key(|fix| fix.id);
+    for incident in incidents {
+        let mut incident = incident.clone();
+        let mut problems = Vec::new();
+        normalize_field(&mut problems, "type", &mut incident.kind, normalize_line);
+        normalize_field(&mut problems, "description", &mut incident.description, normalize_block);
+        if !problems.is_empty() {
+            fixes.push(Fix { record: "incident", id: incident.id, problems, after: serde_json::to_value(&incident).unwrap_or_default() });
+        }
+    }
+    fixes
+}
@@ -0,0 +1,46 @@
+//! JSON for tool results. Values are serialized straight into the buffer that
+//! becomes the result text, compact unless `CAT_SERVER_PRETTY_JSON=true`. Each
+//! buffer starts at the size the last value of the same type needed, so a
+//! large listing is written without the buffer growing again and again.
+
+use rmcp::{
+    ErrorData,
+    model::ErrorCode,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub struct JsonWriter {
+    pretty: bool,
+    /// Bytes the last value of each type took, by type name.
+    sizes: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl JsonWriter {
+    pub fn from_env() -> Self {
+        Self {
+            pretty: std::env::var("CAT_SERVER_PRETTY_JSON").is_ok_and(|value| matches!(value.to_lowercase().as_str(), "true" | "1" | "on")),
+            sizes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn write<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, ErrorData> {
+        let kind = std::any::type_name::<T>();
+        let capacity = self.sizes.lock().expect("size hints poisoned").get(kind).copied().unwrap_or(0);
+        let mut buffer = Vec::with_capacity(capacity);
+        let written = match self.pretty {
+            true => serde_json::to_writer_pretty(&mut buffer, value),
+            false => serde_json::to_writer(&mut buffer, value),
+        };
+        let error = |message: String| ErrorData {
+            code: ErrorCode::INTERNAL_ERROR,
+            message: format!("Serialization error: {}", message).into(),
+            data: None,
+        };
+        written.map_err(|e| error(e.to_string()))?;
+        self.sizes.lock().expect("size hints poisoned").insert(kind, buffer.len());
+        // serde_json only writes UTF-8, so this takes the buffer without copying it.
+        String::from_utf8(buffer).map_err(|e| error(e.to_string()))
+    }
+}
@@ -0,0 +1,124 @@
+//! A REST API served next to the HTTP transport, so scripts and dashboards
+//! can use the same cats as agents without speaking MCP:
+//!
+//! - `GET /cats`: every cat;
+//! - `GET /cats/{id}`: one cat, or 404;
+//! - `POST /cats`: adds the cat in the JSON body, which takes `add_cat`'s
+//!   arguments. 201 with the stored cat, 202 if it waits for review, or 200
+//!   with `add_cat`'s messages if it was not added (e.g. a likely duplicate).
+//!
+//! Each endpoint needs its tool (`list_all_cats`, `get_cat_by_id`, `add_cat`)
+//! to be enabled, so a server without `add_cat` is read-only here too, and
+//! nothing is served in maintenance mode. A POST runs as an `add_cat` call
+//! over `http`, with the same validation, review queue, audit log and
+//! receipts; `Authorization: Bearer <admin token>` stands in for
+//! `admin_token`. Everything is masked like tool results.
+
+use crate::CatServer;
+use crate::connection::Connection;
+use crate::web::{bearer_token, failure};
+use axum::Router;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Json, Response};
+use axum::routing::get;
+use rmcp::ErrorData;
+use rmcp::model::{CallToolRequestParam, ErrorCode, JsonObject};
+use serde_json::json;
+use std::sync::Arc;
+
+/// The client name POSTs are audited under.
+const CLIENT: &str = "REST API";
+
+pub fn router(server: Arc<CatServer>) -> Router {
+    Router::new()
+        .route("/cats", get(list).post(add))
+        .route("/cats/{id}", get(one))
+        .with_state(server)
+}
+
+/// The status a failed tool call is answered with.
+fn status(error: &ErrorData) -> StatusCode {
+    if error.code == ErrorCode::INVALID_PARAMS {
+        StatusCode::BAD_REQUEST
+    } else if error.code == ErrorCode::INVALID_REQUEST {
+        StatusCode::FORBIDDEN
+    } else {
+        // Maintenance mode, an outage or the store failing.
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Refuses a request to an endpoint whose tool is disabled, or to any
+/// endpoint in maintenance mode.
+async fn check_open(server: &CatServer, tool: &str) -> Result<(), Response> {
+    server.check_enabled(tool).map_err(|e| failure(StatusCode::FORBIDDEN, e))?;
+    if let Some(message) = server.maintenance.read().await.clone() {
+        return Err(failure(StatusCode::SERVICE_UNAVAILABLE, ErrorData {
+            code: ErrorCode::INTERNAL_ERROR,
+            message: message.into(),
+            data: None,
+        }));
+    }
+    Ok(())
+}
+
+async fn list(State(server): State<Arc<CatServer>>) -> Response {
+    if let Err(refused) = check_open(&server, "list_all_cats").await {
+        return refused;
+    }
+    match server.cats.list().await {
+        Ok(cats) => Json(server.redactor.apply_value(json!(cats))).into_response(),
+        Err(e) => failure(status(&e), e),
+    }
+}
+
+async fn one(State(server): State<Arc<CatServer>>, Path(id): Path<u32>) -> Response {
+    if let Err(refused) = check_open(&server, "get_cat_by_id").await {
+        return refused;
+    }
+    match server.cats.get(id).await {
+        Ok(Some(cat)) => Json(server.redactor.apply_value(json!(cat))).into_response(),
+        Ok(None) => failure(StatusCode::NOT_FOUND, ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: format!("Cat with ID {} not found", id).into(),
+            data: None,
+        }),
+        Err(e) => failure(status(&e), e),
+    }
+}
+
+async fn add(State(server): State<Arc<CatServer>>, headers: HeaderMap, Json(mut arguments): Json<JsonObject>) -> Response {
+    if let Err(refused) = check_open(&server, "add_cat").await {
+        return refused;
+    }
+    let token = bearer_token(&headers);
+    let reviewed = server.review.holds("add_cat") && server.admin.check("add_cat", "http", token.as_ref()).is_err();
+    if let Some(token) = token {
+        arguments.insert(crate::admin::ARGUMENT.to_string(), token);
+    }
+    let request = CallToolRequestParam {
+        name: "add_cat".into(),
+        arguments: Some(arguments),
+    };
+    let connection = Connection::new(server.clone(), "http");
+    let (result, changes) = match connection.call(request, None, Some(CLIENT.to_string())).await {
+        Ok(called) => called,
+        Err(e) => return failure(status(&e), e),
+    };
+    match changes.into_iter().find(|change| change.record == "cat" && !change.after.is_null()) {
+        Some(change) => (
+            StatusCode::CREATED,
+            [(header::LOCATION, format!("/cats/{}", change.id))],
+            Json(server.redactor.apply_value(change.after)),
+        )
+            .into_response(),
+        // Nothing was stored: the cat waits for review, or `add_cat` answered
+        // without adding it (a likely duplicate, or a repeated idempotency key).
+        None => {
+            let messages: Vec<String> = result.content.iter().filter_map(|content| content.as_text()).map(|text| text.text.clone()).collect();
+            let answered = if reviewed { StatusCode::ACCEPTED } else { StatusCode::OK };
+            (answered, Json(json!({ "messages": messages }))).into_response()
+        }
+    }
+}
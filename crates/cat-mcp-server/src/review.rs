@@ -0,0 +1,163 @@
+//! The review queue for changes made by agents. With review on
+//! (`CAT_SERVER_REVIEW_CHANGES=true`, else `[review] enabled`), a call that
+//! would change records from a session without admin access (see
+//! [`crate::admin`]) is not run: it is checked against the tool's schema and
+//! waits here. An admin lists the queue with `list_pending_changes`, runs a
+//! change with `approve_change`, which goes through like the admin's own call
+//! (audited, undoable and with a receipt), or drops it with `reject_change`.
+//!
+//! The queue is kept in memory, so pending changes are lost on restart.
+
+use crate::{admin, config, receipt};
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use rmcp::{
+    ErrorData,
+    model::{CallToolRequestParam, ErrorCode, JsonObject},
+};
+use serde::Serialize;
+use std::sync::Mutex;
+
+/// The admin tools that work the queue.
+pub const TOOLS: &[&str] = &["list_pending_changes", "approve_change", "reject_change"];
+
+/// Changes that are never queued. Undo only reaches the caller's own changes,
+/// and a reviewed session's changes are made by whoever approves them, so it
+/// has none to undo.
+const NOT_QUEUED: &[&str] = &["undo_last_change", "redo"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingChange {
+    pub id: u32,
+    pub tool: String,
+    pub arguments: Option<JsonObject>,
+    /// The session that asked for it.
+    pub session: u64,
+    pub client: Option<String>,
+    pub queued_at: NaiveDateTime,
+    /// An admin has approved it and it is being applied.
+    #[serde(skip)]
+    claimed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u32,
+    pending: Vec<PendingChange>,
+}
+
+#[derive(Default)]
+pub struct Queue {
+    enabled: bool,
+    state: Mutex<State>,
+}
+
+fn not_pending(id: u32) -> ErrorData {
+    ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: format!("No pending change {}", id).into(),
+        data: None,
+    }
+}
+
+impl Queue {
+    pub fn from_config() -> anyhow::Result<Self> {
+        let enabled = match std::env::var("CAT_SERVER_REVIEW_CHANGES") {
+            Ok(value) => value.trim().parse().with_context(|| format!("CAT_SERVER_REVIEW_CHANGES must be true or false, got {:?}", value))?,
+            Err(_) => config::file().review.enabled.unwrap_or(false),
+        };
+        Ok(Self {
+            enabled,
+            ..Self::default()
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether a call to `tool` from a session without admin access waits for
+    /// review instead of running.
+    pub fn holds(&self, tool: &str) -> bool {
+        self.enabled && receipt::MUTATING_TOOLS.contains(&tool) && !admin::TOOLS.contains(&tool) && !NOT_QUEUED.contains(&tool)
+    }
+
+    pub fn submit(&self, request: &CallToolRequestParam, session: u64, client: Option<String>) -> PendingChange {
+        let mut state = self.state.lock().expect("review queue poisoned");
+        state.next_id += 1;
+        let change = PendingChange {
+            id: state.next_id,
+            tool: request.name.to_string(),
+            arguments: request.arguments.clone(),
+            session,
+            client,
+            queued_at: chrono::Local::now().naive_local(),
+            claimed: false,
+        };
+        state.pending.push(change.clone());
+        change
+    }
+
+    /// Everything waiting, oldest first, without the ones being applied.
+    pub fn list(&self) -> Vec<PendingChange> {
+        let state = self.state.lock().expect("review queue poisoned");
+        state.pending.iter().filter(|change| !change.claimed).cloned().collect()
+    }
+
+    /// Marks change `id` as being applied and returns the call to run for it.
+    /// The change leaves the queue when [`Claim::applied`] is called, and is
+    /// back in it if the claim is dropped first, when the call fails.
+    pub fn claim(&self, id: u32) -> Result<(Claim<'_>, CallToolRequestParam), ErrorData> {
+        let mut state = self.state.lock().expect("review queue poisoned");
+        let change = state.pending.iter_mut().find(|change| change.id == id && !change.claimed).ok_or_else(|| not_pending(id))?;
+        change.claimed = true;
+        let request = CallToolRequestParam {
+            name: change.tool.clone().into(),
+            arguments: change.arguments.clone(),
+        };
+        Ok((Claim { queue: self, id, applied: false }, request))
+    }
+
+    pub fn reject(&self, id: u32) -> Result<PendingChange, ErrorData> {
+        let mut state = self.state.lock().expect("review queue poisoned");
+        let index = state.pending.iter().position(|change| change.id == id && !change.claimed).ok_or_else(|| not_pending(id))?;
+        Ok(state.pending.remove(index))
+    }
+}
+
+/// A pending change an admin approved, while its call runs.
+pub struct Claim<'a> {
+    queue: &'a Queue,
+    id: u32,
+    applied: bool,
+}
+
+impl Claim<'_> {
+    pub fn applied(mut self) {
+        self.applied = true;
+    }
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().expect("review queue poisoned");
+        if self.applied {
+            state.pending.retain(|change| change.id != self.id);
+        } else if let Some(change) = state.pending.iter_mut().find(|change| change.id == self.id) {
+            change.claimed = false;
+        }
+    }
+}
+
+/// The `id` argument of `approve_change` and `reject_change`.
+pub fn id_argument(arguments: Option<&JsonObject>) -> Result<u32, ErrorData> {
+    arguments
+        .and_then(|arguments| arguments.get("id"))
+        .and_then(|id| id.as_u64())
+        .and_then(|id| u32::try_from(id).ok())
+        .ok_or_else(|| ErrorData {
+            code: ErrorCode::INVALID_PARAMS,
+            message: "id must be the number of a pending change".into(),
+            data: None,
+        })
+}
@@ -0,0 +1,28 @@
+//! `--seed-rng <n>`: one seed for the random choices the server makes, so a
+//! demo or test run repeats exactly. Chaos faults and
+//! `generate-benchmark-dataset` draw from it unless given their own
+//! `--chaos-seed` or `--seed`; matchmaking and search already rank ties in a
+//! fixed order. Receipt signing keys stay random, since a known key would let
+//! anyone forge receipts.
+
+use crate::store::flag_value;
+use anyhow::Context;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+pub const FLAG: &str = "--seed-rng";
+
+/// The seed given with `--seed-rng`, if any.
+pub fn from_args() -> anyhow::Result<Option<u64>> {
+    flag_value(FLAG)
+        .map(|seed| seed.parse().with_context(|| format!("{} {} is not a number", FLAG, seed)))
+        .transpose()
+}
+
+/// A generator started from `seed`, or from entropy without one.
+pub fn seeded(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
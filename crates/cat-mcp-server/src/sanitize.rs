@@ -0,0 +1,82 @@
+//! Cleans free text on write and spots stored text that reads like instructions.
+//!
+//! Notes and descriptions end up in LLM contexts, so text a user pasted in should
+//! never be mistaken for something the client was told to do.
+
+use crate::matching;
+use serde_json::Value;
+
+const DEFAULT_MAX_TEXT_LEN: usize = 2000;
+
+/// Lowercase phrases typical of prompt-injection attempts.
+const SUSPICIOUS_PHRASES: &[&str] = &[
+    "ignore previous instructions",
+    "ignore all previous",
+    "ignore the above",
+    "disregard previous",
+    "disregard all",
+    "forget your instructions",
+    "new instructions:",
+    "system prompt",
+    "you are now",
+    "<system>",
+    "</system>",
+    "[inst]",
+    "以前の指示を無視",
+    "指示を無視",
+];
+
+/// (tool, argument) pairs holding bulk data such as a whole CSV file. They are
+/// not capped as one string; the tool cleans each field after parsing instead.
+pub const BULK_ARGUMENTS: &[(&str, &str)] = &[("import_cats_csv", "csv")];
+
+pub struct Sanitizer {
+    max_len: usize,
+    flag_suspicious: bool,
+}
+
+impl Sanitizer {
+    /// `CAT_SERVER_MAX_TEXT_LEN` bounds each string (default 2000 characters);
+    /// `CAT_SERVER_FLAG_SUSPICIOUS_TEXT=false` turns off the output warning.
+    pub fn from_env() -> Self {
+        let max_len = std::env::var("CAT_SERVER_MAX_TEXT_LEN")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_TEXT_LEN);
+        let flag_suspicious = std::env::var("CAT_SERVER_FLAG_SUSPICIOUS_TEXT")
+            .map(|value| !matches!(value.to_lowercase().as_str(), "false" | "0" | "off"))
+            .unwrap_or(true);
+        Self { max_len, flag_suspicious }
+    }
+
+    /// Strips control characters (keeping newlines and tabs) and truncates to the limit.
+    pub fn clean(&self, text: &str) -> String {
+        text.chars()
+            .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+            .take(self.max_len)
+            .collect()
+    }
+
+    /// Cleans every string in `value`, however deeply nested.
+    pub fn clean_value(&self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.clean(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.clean_value(item)),
+            Value::Object(fields) => fields.values_mut().for_each(|field| self.clean_value(field)),
+            _ => {}
+        }
+    }
+
+    /// The suspicious phrases found in `text`, if flagging is enabled.
+    pub fn suspicious_phrases(&self, text: &str) -> Vec<&'static str> {
+        if !self.flag_suspicious {
+            return Vec::new();
+        }
+        let normalized = matching::normalize(text);
+        SUSPICIOUS_PHRASES
+            .iter()
+            .filter(|phrase| normalized.contains(&matching::normalize(phrase)))
+            .copied()
+            .collect()
+    }
+}
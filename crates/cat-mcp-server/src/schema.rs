@@ -0,0 +1,314 @@
+//! Checks tool arguments against the advertised input schema, and counts the
+//! failures so maintainers can see which schemas clients get wrong most often.
+//! Also maps the field names models tend to guess (`cat_id` for `id`, ...) onto
+//! the real ones.
+//!
+//! How forgiving the check is depends on `CAT_SERVER_ARGUMENT_MODE`:
+//! - `standard` (default): required fields and JSON types are checked, unknown
+//!   fields are ignored.
+//! - `strict`: unknown fields are rejected too.
+//! - `lenient`: unknown fields are dropped and values are converted where the
+//!   meaning is clear (`"3"` → `3`, `"true"` → `true`, `3` → `"3"`), with a note
+//!   in the result listing what was changed.
+
+use rmcp::model::Tool;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Field → other names accepted for it.
+const DEFAULT_FIELD_ALIASES: &[(&str, &[&str])] = &[
+    ("id", &["cat_id"]),
+    ("is_indoor", &["indoor"]),
+    ("favorite_toy", &["toy"]),
+    ("limit", &["max_results"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Standard,
+    Strict,
+    Lenient,
+}
+
+impl Mode {
+    pub fn from_env() -> Self {
+        match std::env::var("CAT_SERVER_ARGUMENT_MODE").as_deref() {
+            Err(_) | Ok("standard") => Mode::Standard,
+            Ok("strict") => Mode::Strict,
+            Ok("lenient") => Mode::Lenient,
+            Ok(other) => {
+                tracing::warn!("Ignoring CAT_SERVER_ARGUMENT_MODE {}: expected standard, strict or lenient", other);
+                Mode::Standard
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    pub field: String,
+    pub expected: String,
+    /// JSON type that was sent, or "missing".
+    pub received: String,
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+/// Required fields that are missing, and fields whose JSON type differs from the
+/// schema. A null counts as not given. In strict mode, fields the schema does
+/// not list are reported as well.
+pub fn check(schema: &Map<String, Value>, arguments: Option<&Map<String, Value>>, mode: Mode) -> Vec<Mismatch> {
+    let empty = Map::new();
+    let arguments = arguments.unwrap_or(&empty);
+    let properties = schema.get("properties").and_then(|v| v.as_object()).unwrap_or(&empty);
+    let expected_type = |field: &str| {
+        properties
+            .get(field)
+            .and_then(|property| property.get("type"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("value")
+            .to_string()
+    };
+
+    let mut mismatches = Vec::new();
+    let required = schema.get("required").and_then(|v| v.as_array()).into_iter().flatten();
+    for field in required.filter_map(|v| v.as_str()) {
+        if arguments.get(field).is_none_or(|v| v.is_null()) {
+            mismatches.push(Mismatch {
+                field: field.to_string(),
+                expected: expected_type(field),
+                received: "missing".to_string(),
+            });
+        }
+    }
+    for (field, value) in arguments.iter().filter(|(_, v)| !v.is_null()) {
+        if mode == Mode::Strict && !properties.contains_key(field) {
+            mismatches.push(Mismatch {
+                field: field.clone(),
+                expected: "no such field".to_string(),
+                received: json_type(value).to_string(),
+            });
+            continue;
+        }
+        let expected = expected_type(field);
+        if !type_matches(&expected, value) {
+            mismatches.push(Mismatch {
+                field: field.clone(),
+                expected,
+                received: json_type(value).to_string(),
+            });
+        }
+    }
+    mismatches
+}
+
+/// `value` converted to `expected`, when the conversion loses nothing.
+fn convert(expected: &str, value: &Value) -> Option<Value> {
+    match (expected, value) {
+        ("number" | "integer", Value::String(text)) => {
+            let number: Value = serde_json::from_str(text.trim()).ok()?;
+            number.is_number().then_some(number).filter(|n| expected == "number" || n.is_i64() || n.is_u64())
+        }
+        ("integer", Value::Number(number)) => {
+            let whole = number.as_f64().filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64)?;
+            Some(Value::from(whole as u64))
+        }
+        ("boolean", Value::String(text)) => match text.trim().to_lowercase().as_str() {
+            "true" | "yes" => Some(Value::Bool(true)),
+            "false" | "no" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ("string", Value::Number(_) | Value::Bool(_)) => Some(Value::String(value.to_string())),
+        ("array", Value::String(_) | Value::Number(_) | Value::Bool(_)) => Some(Value::Array(vec![value.clone()])),
+        _ => None,
+    }
+}
+
+/// Lenient mode: drops fields the schema does not list and converts values to
+/// the declared type where possible. Returns a note for each change; anything
+/// it cannot fix is left for [`check`] to report.
+pub fn coerce(schema: &Map<String, Value>, arguments: &mut Map<String, Value>) -> Vec<String> {
+    let empty = Map::new();
+    let properties = schema.get("properties").and_then(|v| v.as_object()).unwrap_or(&empty);
+    let mut notes = Vec::new();
+    arguments.retain(|field, _| {
+        let known = properties.contains_key(field);
+        if !known {
+            notes.push(format!("ignored unknown field \"{}\"", field));
+        }
+        known
+    });
+    for (field, value) in arguments.iter_mut().filter(|(_, v)| !v.is_null()) {
+        let Some(expected) = properties.get(field).and_then(|p| p.get("type")).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if type_matches(expected, value) {
+            continue;
+        }
+        if let Some(converted) = convert(expected, value) {
+            notes.push(format!("converted \"{}\" from {} to {}", field, json_type(value), expected));
+            *value = converted;
+        }
+    }
+    notes
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureCount {
+    pub tool: String,
+    pub field: String,
+    pub received: String,
+    pub count: usize,
+}
+
+/// Validation failures aggregated by tool, field and received type.
+#[derive(Default)]
+pub struct FailureStats {
+    counts: RwLock<HashMap<(String, String, String), usize>>,
+}
+
+impl FailureStats {
+    pub async fn record(&self, tool: &str, mismatches: &[Mismatch]) {
+        let mut counts = self.counts.write().await;
+        for mismatch in mismatches {
+            let key = (tool.to_string(), mismatch.field.clone(), mismatch.received.clone());
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    /// Most frequent failures first.
+    pub async fn report(&self) -> Vec<FailureCount> {
+        let mut report: Vec<FailureCount> = self
+            .counts
+            .read()
+            .await
+            .iter()
+            .map(|((tool, field, received), count)| FailureCount {
+                tool: tool.clone(),
+                field: field.clone(),
+                received: received.clone(),
+                count: *count,
+            })
+            .collect();
+        report.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tool.cmp(&b.tool)).then_with(|| a.field.cmp(&b.field)));
+        report
+    }
+}
+
+/// Alternative argument names, applied to every tool whose schema has the field.
+/// An alias is skipped for a tool that has a real field of that name.
+pub struct FieldAliases {
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl FieldAliases {
+    /// The built-in aliases plus any from the JSON file named by
+    /// `CAT_SERVER_FIELD_ALIASES_FILE` (an object of field → list of aliases).
+    pub fn from_env() -> Self {
+        let mut aliases: BTreeMap<String, Vec<String>> = DEFAULT_FIELD_ALIASES
+            .iter()
+            .map(|(field, names)| (field.to_string(), names.iter().map(|name| name.to_string()).collect()))
+            .collect();
+
+        if let Ok(path) = std::env::var("CAT_SERVER_FIELD_ALIASES_FILE") {
+            let extra = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str::<HashMap<String, Vec<String>>>(&text).map_err(|e| e.to_string()));
+            match extra {
+                Ok(extra) => {
+                    for (field, names) in extra {
+                        let known = aliases.entry(field).or_default();
+                        for name in names {
+                            if !known.contains(&name) {
+                                known.push(name);
+                            }
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Ignoring field aliases file {}: {}", path, e),
+            }
+        }
+        Self { aliases }
+    }
+
+    /// Aliases that apply to `field` in a schema with these properties.
+    fn for_field<'a>(&'a self, field: &str, properties: &'a Map<String, Value>) -> impl Iterator<Item = &'a String> {
+        self.aliases
+            .get(field)
+            .into_iter()
+            .flatten()
+            .filter(move |alias| !properties.contains_key(alias.as_str()))
+    }
+
+    /// Adds "Also accepted as ..." to the description of each aliased field.
+    pub fn describe(&self, tools: Vec<Tool>) -> Vec<Tool> {
+        tools
+            .into_iter()
+            .map(|mut tool| {
+                let Some(properties) = tool.input_schema.get("properties").and_then(|v| v.as_object()) else {
+                    return tool;
+                };
+                let mut described = properties.clone();
+                for (field, property) in described.iter_mut() {
+                    let names: Vec<String> = self.for_field(field, properties).map(|alias| format!("`{}`", alias)).collect();
+                    let Some(property) = property.as_object_mut().filter(|_| !names.is_empty()) else {
+                        continue;
+                    };
+                    let note = format!("Also accepted as {}.", names.join(", "));
+                    let description = match property.get("description").and_then(|v| v.as_str()) {
+                        Some(description) if description.ends_with('.') => format!("{} {}", description, note),
+                        Some(description) => format!("{}. {}", description, note),
+                        None => note,
+                    };
+                    property.insert("description".to_string(), Value::String(description));
+                }
+                let mut schema = (*tool.input_schema).clone();
+                schema.insert("properties".to_string(), Value::Object(described));
+                tool.input_schema = Arc::new(schema);
+                tool
+            })
+            .collect()
+    }
+
+    /// Renames aliased arguments to their field names. An alias sent alongside
+    /// the real field is left alone.
+    pub fn resolve(&self, schema: &Map<String, Value>, arguments: &mut Map<String, Value>) {
+        let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) else {
+            return;
+        };
+        for field in properties.keys() {
+            if arguments.contains_key(field) {
+                continue;
+            }
+            let alias = self.for_field(field, properties).find(|alias| arguments.contains_key(alias.as_str()));
+            if let Some(value) = alias.cloned().and_then(|alias| arguments.remove(&alias)) {
+                arguments.insert(field.clone(), value);
+            }
+        }
+    }
+}
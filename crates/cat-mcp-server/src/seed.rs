@@ -0,0 +1,89 @@
+//! The cats a new server starts with: those in the file given with
+//! `--seed <path>` (or `CATS_SEED_FILE`), the sample cats with
+//! `--init-sample-data`, else what the configuration's `[seed]` section asks
+//! for, or none.
+//!
+//! A seed file is JSON or, if its name ends in `.toml`, TOML. JSON may be a
+//! list of cats or `{"cats": [...]}`; TOML is a `[[cats]]` array. Cats take the
+//! same fields `add_cat` does plus an `id`. A relative path is inside the data
+//! directory. With a persistent store, the seed is only used to fill it on
+//! first run.
+//!
+//! The samples are `samples/cats.toml`, built into the binary so a demo needs
+//! no files, but kept out of the code and out of any store that did not ask for
+//! them.
+
+use crate::config;
+use crate::store::flag_value;
+use crate::validation;
+use anyhow::Context;
+use cat_mcp_core::Cat;
+use serde::Deserialize;
+use std::collections::HashSet;
+
+pub const SAMPLES_FLAG: &str = "--init-sample-data";
+const SAMPLES: &str = include_str!("../samples/cats.toml");
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SeedFile {
+    List(Vec<Cat>),
+    Table { cats: Vec<Cat> },
+}
+
+/// The cats from the seed file or the samples, if either is asked for.
+pub fn from_args() -> anyhow::Result<Vec<Cat>> {
+    let mut seed = flag_value("--seed").or_else(|| std::env::var("CATS_SEED_FILE").ok());
+    let mut samples = std::env::args().skip(1).any(|arg| arg == SAMPLES_FLAG);
+    if seed.is_none() && !samples {
+        let configured = &config::file().seed;
+        seed = configured.file.clone();
+        samples = configured.samples.unwrap_or(false);
+    }
+    match (seed, samples) {
+        (Some(_), true) => anyhow::bail!("Use either --seed or {}, not both", SAMPLES_FLAG),
+        (Some(path), false) => {
+            let path = config::resolve(path).display().to_string();
+            let text = std::fs::read_to_string(&path).with_context(|| format!("Could not read seed file {}", path))?;
+            let cats = parse(&text, &path)?;
+            tracing::info!("🌱 Seeded {} cats from {}", cats.len(), path);
+            Ok(cats)
+        }
+        (None, true) => {
+            let cats = samples()?;
+            tracing::info!("🌱 Seeded the {} sample cats", cats.len());
+            Ok(cats)
+        }
+        (None, false) => Ok(Vec::new()),
+    }
+}
+
+/// The sample cats `--init-sample-data` starts with.
+pub fn samples() -> anyhow::Result<Vec<Cat>> {
+    parse(SAMPLES, "samples/cats.toml")
+}
+
+/// The cats in a seed file's `text`, checked like `add_cat` checks them.
+fn parse(text: &str, path: &str) -> anyhow::Result<Vec<Cat>> {
+    let file: SeedFile = if path.ends_with(".toml") {
+        toml::from_str(text).with_context(|| format!("Seed file {} is not valid TOML", path))?
+    } else {
+        serde_json::from_str(text).with_context(|| format!("Seed file {} is not valid JSON", path))?
+    };
+    let mut cats = match file {
+        SeedFile::List(cats) | SeedFile::Table { cats } => cats,
+    };
+    let mut ids = HashSet::new();
+    for cat in &mut cats {
+        if !ids.insert(cat.id) {
+            anyhow::bail!("Seed file {} has more than one cat with ID {}", path, cat.id);
+        }
+        let violations = validation::check_cat(cat);
+        if !violations.is_empty() {
+            let details: Vec<String> = violations.iter().map(|v| format!("{} {}", v.field, v.problem)).collect();
+            anyhow::bail!("Cat {} in seed file {} is invalid: {}", cat.id, path, details.join("; "));
+        }
+        cat.version = cat.version.max(1);
+    }
+    Ok(cats)
+}
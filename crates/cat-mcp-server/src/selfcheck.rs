@@ -0,0 +1,105 @@
+//! Startup check that what the server advertises matches what it implements.
+
+use crate::{admin, cache, deprecation, receipt, review};
+use rmcp::model::{Prompt, ServerCapabilities, Tool};
+use std::collections::HashSet;
+
+/// What the server can actually serve, taken from the tables its dispatch uses.
+pub struct Handlers<'a> {
+    /// Tools with a handler.
+    pub tools: &'a [&'a str],
+    /// Whether a prompt name has a way to be filled in.
+    pub prompt: &'a dyn Fn(&str) -> bool,
+    /// Whether `read_resource` serves a URI.
+    pub resource: &'a dyn Fn(&str) -> bool,
+}
+
+/// `template` with each `{placeholder}` filled in with `1`, a value every
+/// template in the server accepts.
+fn expand(template: &str) -> String {
+    let mut uri = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        uri.push_str(&rest[..open]);
+        uri.push('1');
+        rest = rest[open..].find('}').map_or("", |close| &rest[open + close + 1..]);
+    }
+    uri.push_str(rest);
+    uri
+}
+
+/// Lists every mismatch found; an empty list means the server is consistent.
+/// `resource_templates` are the URI templates `list_resource_templates` offers.
+pub fn check(capabilities: &ServerCapabilities, advertised: &[Tool], handlers: &Handlers, prompts: &[Prompt], resource_templates: &[&str]) -> Vec<String> {
+    let handled = handlers.tools;
+    let mut problems = Vec::new();
+
+    let mut names = HashSet::new();
+    for tool in advertised {
+        if !names.insert(tool.name.as_ref()) {
+            problems.push(format!("tool \"{}\" is advertised more than once", tool.name));
+        }
+        let implementation = deprecation::resolve(&tool.name).unwrap_or(tool.name.as_ref());
+        if !handled.contains(&implementation) {
+            problems.push(format!("tool \"{}\" is advertised but has no handler", tool.name));
+        }
+    }
+    for name in handled {
+        if !names.contains(name) {
+            problems.push(format!("tool \"{}\" has a handler but is not advertised", name));
+        }
+    }
+
+    for (alias, target) in deprecation::ALIASES {
+        if !handled.contains(target) {
+            problems.push(format!("alias \"{}\" forwards to \"{}\", which has no handler", alias, target));
+        }
+    }
+
+    match (capabilities.tools.is_some(), advertised.is_empty()) {
+        (true, true) => problems.push("the tools capability is advertised but no tools are defined".to_string()),
+        (false, false) => problems.push("tools are defined but the tools capability is not advertised".to_string()),
+        _ => {}
+    }
+    match (capabilities.prompts.is_some(), prompts.is_empty()) {
+        (true, true) => problems.push("the prompts capability is advertised but no prompts are defined".to_string()),
+        (false, false) => problems.push("prompts are defined but the prompts capability is not advertised".to_string()),
+        _ => {}
+    }
+    let mut prompt_names = HashSet::new();
+    for prompt in prompts {
+        if !prompt_names.insert(prompt.name.as_str()) {
+            problems.push(format!("prompt \"{}\" is defined more than once", prompt.name));
+        }
+        if !(handlers.prompt)(&prompt.name) {
+            problems.push(format!("prompt \"{}\" is advertised but has no handler", prompt.name));
+        }
+    }
+    match (capabilities.resources.is_some(), resource_templates.is_empty()) {
+        (true, true) => problems.push("the resources capability is advertised but no resources are implemented".to_string()),
+        (false, false) => problems.push("resources are implemented but the resources capability is not advertised".to_string()),
+        _ => {}
+    }
+    for template in resource_templates {
+        let example = expand(template);
+        if !(handlers.resource)(&example) {
+            problems.push(format!("resource template \"{}\" is advertised but {} is not served", template, example));
+        }
+    }
+
+    for (list, list_name) in [
+        (receipt::MUTATING_TOOLS, "receipt::MUTATING_TOOLS"),
+        (receipt::IN_MEMORY_TOOLS, "receipt::IN_MEMORY_TOOLS"),
+        (admin::TOOLS, "admin::TOOLS"),
+        (cache::TOOLS, "cache::TOOLS"),
+        (review::TOOLS, "review::TOOLS"),
+    ] {
+        for name in list {
+            if !names.contains(name) {
+                problems.push(format!("{} lists unknown tool \"{}\"", list_name, name));
+            }
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,105 @@
+use chrono::NaiveDateTime;
+use rmcp::model::CallToolRequestParam;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::arguments;
+use crate::receipt::MUTATING_TOOLS;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallRecord {
+    pub tool: String,
+    pub at: NaiveDateTime,
+    pub mutating: bool,
+    pub succeeded: bool,
+    pub cat_ids: Vec<u32>,
+}
+
+/// Tool calls made during this session (one client connection's lifetime).
+pub struct SessionLog {
+    started_at: NaiveDateTime,
+    calls: Vec<CallRecord>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub started_at: NaiveDateTime,
+    pub total_calls: usize,
+    pub reads: usize,
+    pub changes: usize,
+    pub failed: usize,
+    pub calls_by_tool: BTreeMap<String, usize>,
+    pub cats_read: BTreeSet<u32>,
+    pub cats_changed: BTreeSet<u32>,
+}
+
+/// Cat IDs named in a call's arguments.
+fn cat_ids_in(request: &CallToolRequestParam) -> Vec<u32> {
+    let Some(args) = request.arguments.as_ref() else {
+        return Vec::new();
+    };
+
+    let mut ids = Vec::new();
+    for key in ["id", "cat_id"] {
+        if let Ok(Some(id)) = arguments::id(Some(args), key) {
+            ids.push(id);
+        }
+    }
+    if let Ok(Some(list)) = arguments::ids(Some(args), "cat_ids") {
+        ids.extend(list);
+    }
+    if args.get("record_type").and_then(|v| v.as_str()) == Some("cat") {
+        if let Ok(Some(id)) = arguments::id(Some(args), "record_id") {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+impl SessionLog {
+    pub fn new() -> Self {
+        Self {
+            started_at: chrono::Local::now().naive_local(),
+            calls: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, request: &CallToolRequestParam, succeeded: bool) {
+        self.calls.push(CallRecord {
+            tool: request.name.to_string(),
+            at: chrono::Local::now().naive_local(),
+            mutating: MUTATING_TOOLS.contains(&request.name.as_ref()),
+            succeeded,
+            cat_ids: cat_ids_in(request),
+        });
+    }
+
+    pub fn summarize(&self) -> SessionSummary {
+        let mut summary = SessionSummary {
+            started_at: self.started_at,
+            total_calls: self.calls.len(),
+            reads: 0,
+            changes: 0,
+            failed: 0,
+            calls_by_tool: BTreeMap::new(),
+            cats_read: BTreeSet::new(),
+            cats_changed: BTreeSet::new(),
+        };
+
+        for call in &self.calls {
+            *summary.calls_by_tool.entry(call.tool.clone()).or_insert(0) += 1;
+            if !call.succeeded {
+                summary.failed += 1;
+                continue;
+            }
+            if call.mutating {
+                summary.changes += 1;
+                summary.cats_changed.extend(&call.cat_ids);
+            } else {
+                summary.reads += 1;
+                summary.cats_read.extend(&call.cat_ids);
+            }
+        }
+        summary
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 10;
+
+/// Tracks in-flight tool calls so shutdown can let them finish.
+#[derive(Default)]
+pub struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+/// Marks one tool call as in flight until dropped.
+pub struct InFlightGuard<'a>(&'a DrainState);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl DrainState {
+    /// Registers a new call, or returns `None` once draining has started.
+    pub fn try_begin(&self) -> Option<InFlightGuard<'_>> {
+        // Count first so a drain that starts concurrently cannot miss this call.
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard(self);
+        if self.draining.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Waits for in-flight calls to finish; returns `false` if the deadline passed first.
+    pub async fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.in_flight() > 0 {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        true
+    }
+}
+
+/// Read from `CAT_SERVER_DRAIN_TIMEOUT_SECS`, defaulting to 10 seconds.
+pub fn drain_timeout() -> Duration {
+    let secs = std::env::var("CAT_SERVER_DRAIN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Resolves on Ctrl+C, or SIGTERM on Unix.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,89 @@
+//! "Cats like this one" by a weighted feature distance.
+//!
+//! Each feature gives a similarity from 0 to 1: breed and color by the share of
+//! words they have in common, temperament by the share of shared tags, and age
+//! by closeness (10 or more years apart counts as nothing in common). A pair's
+//! score is the weighted average, as a percentage.
+
+use crate::matching;
+use cat_mcp_core::Cat;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+const AGE_SPAN: f64 = 10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimilarityWeights {
+    pub breed: u32,
+    pub age: u32,
+    pub color: u32,
+    pub temperament: u32,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        Self {
+            breed: 30,
+            age: 20,
+            color: 25,
+            temperament: 25,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarCat {
+    pub cat_id: u32,
+    pub cat_name: String,
+    /// 0-100.
+    pub score: u32,
+    pub breakdown: Vec<String>,
+}
+
+fn words(text: &str) -> BTreeSet<String> {
+    matching::normalize(text)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && *word != "and")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Shared items over all items; two empty sets are not evidence of anything.
+fn overlap(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+pub fn compare(target: &Cat, other: &Cat, weights: &SimilarityWeights) -> SimilarCat {
+    let tags = |cat: &Cat| cat.temperament.iter().map(|tag| matching::normalize(tag)).collect::<BTreeSet<_>>();
+    let age_gap = target.age.abs_diff(other.age) as f64;
+    let shared: Vec<String> = tags(target).intersection(&tags(other)).cloned().collect();
+    let shared = if shared.is_empty() { "no shared tags".to_string() } else { format!("shares {}", shared.join(", ")) };
+    let features = [
+        ("Breed", weights.breed, overlap(&words(&target.breed), &words(&other.breed)), format!("{} vs {}", target.breed, other.breed)),
+        ("Age", weights.age, (1.0 - age_gap / AGE_SPAN).max(0.0), format!("{} years apart", age_gap)),
+        ("Color", weights.color, overlap(&words(&target.color), &words(&other.color)), format!("{} vs {}", target.color, other.color)),
+        ("Temperament", weights.temperament, overlap(&tags(target), &tags(other)), shared),
+    ];
+
+    let total_weight: u32 = features.iter().map(|(_, weight, _, _)| weight).sum();
+    let mut points = 0.0;
+    let mut breakdown = Vec::new();
+    for (name, weight, similarity, reason) in &features {
+        let earned = similarity * *weight as f64;
+        points += earned;
+        breakdown.push(format!("{}: {} ({:.0} of {})", name, reason, earned, weight));
+    }
+    let score = if total_weight == 0 { 0.0 } else { 100.0 * points / total_weight as f64 };
+
+    SimilarCat {
+        cat_id: other.id,
+        cat_name: other.name.clone(),
+        score: score.round() as u32,
+        breakdown,
+    }
+}
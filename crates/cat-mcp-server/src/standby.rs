@@ -0,0 +1,182 @@
+//! Warm standby for a team deployment. Started with `--standby-of <url>` (or
+//! `CAT_SERVER_STANDBY_OF`), a server follows the primary serving HTTP at
+//! `url`: every [`POLL_INTERVAL`] it reads the entries the primary's audit log
+//! gained from `GET /api/journal?after=<n>` and applies their changes to its
+//! own store, audit log and subscribers. Meanwhile it answers reads but
+//! refuses changes, which belong on the primary. The admin tool `promote`
+//! catches up one last time, if the primary still answers, and makes it a
+//! server of its own.
+//!
+//! The journal needs admin access on the primary, as `get_audit_log` does; the
+//! standby sends its own admin token, so the pair should share one. A standby
+//! starts from a copy of the primary's store, since it only learns about the
+//! records the journal changes, and the primary should keep its audit log in
+//! a file (`CAT_SERVER_AUDIT_LOG`) so the journal survives its restarts.
+
+use crate::CatServer;
+use crate::audit::Entry;
+#[cfg(feature = "http")]
+use anyhow::Context;
+use rmcp::{ErrorData, model::ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+pub const FLAG: &str = "--standby-of";
+/// How often the standby asks the primary for new entries.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The most entries the primary sends at once.
+pub const PAGE_SIZE: usize = 500;
+
+/// The answer to `GET /api/journal`.
+#[derive(Serialize, Deserialize)]
+pub struct Page {
+    /// How many entries the primary's audit log holds in all.
+    pub length: usize,
+    pub entries: Vec<Entry>,
+}
+
+pub struct Standby {
+    primary: String,
+    token: Option<String>,
+    /// How many of the primary's entries are applied here; held while
+    /// applying more, so a poll and `promote` never apply the same ones twice.
+    applied: Mutex<usize>,
+    /// Cancelled by `promote`.
+    promoted: CancellationToken,
+    #[cfg(feature = "http")]
+    client: reqwest::Client,
+}
+
+impl Standby {
+    /// The standby configuration from `--standby-of` or
+    /// `CAT_SERVER_STANDBY_OF`, if this server is a standby.
+    pub fn from_args(token: Option<&str>) -> anyhow::Result<Option<Self>> {
+        let Some(primary) = crate::store::flag_value(FLAG).or_else(|| std::env::var("CAT_SERVER_STANDBY_OF").ok()) else {
+            return Ok(None);
+        };
+        if !cfg!(feature = "http") {
+            anyhow::bail!("{} needs the http feature, which this build leaves out", FLAG);
+        }
+        if !primary.starts_with("http://") {
+            anyhow::bail!("{} takes the primary's HTTP address, such as http://10.0.0.5:8000, got {}", FLAG, primary);
+        }
+        Ok(Some(Self {
+            primary: primary.trim_end_matches('/').to_string(),
+            token: token.map(str::to_string),
+            applied: Mutex::new(0),
+            promoted: CancellationToken::new(),
+            #[cfg(feature = "http")]
+            client: reqwest::Client::new(),
+        }))
+    }
+
+    pub fn primary(&self) -> &str {
+        &self.primary
+    }
+
+    /// Whether this server still follows the primary, rather than having been
+    /// promoted.
+    pub fn following(&self) -> bool {
+        !self.promoted.is_cancelled()
+    }
+
+    /// The error a change is refused with while following.
+    pub fn refusal(&self) -> ErrorData {
+        ErrorData {
+            code: ErrorCode::INVALID_REQUEST,
+            message: format!("This server is a standby of {}; make changes there, or promote this server with promote", self.primary).into(),
+            data: None,
+        }
+    }
+
+    /// Applies whatever the primary's journal gained since the last time,
+    /// returning how many entries that was; none once promoted.
+    pub async fn catch_up(&self, server: &CatServer) -> anyhow::Result<usize> {
+        let mut applied = self.applied.lock().await;
+        if !self.following() {
+            return Ok(0);
+        }
+        self.apply_new(server, &mut applied).await
+    }
+
+    /// Stops following: one last catch-up, then changes are taken here.
+    /// Returns how many entries the catch-up applied, or why it could not.
+    pub async fn promote(&self, server: &CatServer) -> Result<usize, String> {
+        let mut applied = self.applied.lock().await;
+        let caught_up = self.apply_new(server, &mut applied).await.map_err(|e| format!("{:#}", e));
+        self.promoted.cancel();
+        caught_up
+    }
+
+    async fn apply_new(&self, server: &CatServer, applied: &mut usize) -> anyhow::Result<usize> {
+        let mut count = 0;
+        loop {
+            let page = self.fetch(*applied).await?;
+            if page.length < *applied {
+                tracing::warn!(
+                    "The journal of {} has {} entries but {} were applied; it restarted without an audit log file, so it is applied again from the start",
+                    self.primary,
+                    page.length,
+                    *applied
+                );
+                *applied = 0;
+                continue;
+            }
+            if page.entries.is_empty() {
+                return Ok(count);
+            }
+            for entry in page.entries {
+                server.apply_primary_change(entry).await.map_err(|e| anyhow::anyhow!(e.message))?;
+                *applied += 1;
+                count += 1;
+            }
+        }
+    }
+
+    /// Follows the primary until promoted or `shutdown`. A primary that does
+    /// not answer is retried at the next poll.
+    pub async fn follow(server: Arc<CatServer>, shutdown: CancellationToken) {
+        let Some(standby) = server.standby.as_ref() else {
+            return;
+        };
+        let mut unreachable = false;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = standby.promoted.cancelled() => return,
+                _ = shutdown.cancelled() => return,
+            }
+            match standby.catch_up(&server).await {
+                Ok(_) if unreachable => {
+                    tracing::info!("🔁 Following {} again", standby.primary);
+                    unreachable = false;
+                }
+                Ok(_) => {}
+                Err(e) if !unreachable => {
+                    tracing::warn!("Could not follow {}: {:#}", standby.primary, e);
+                    unreachable = true;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    #[cfg(feature = "http")]
+    async fn fetch(&self, after: usize) -> anyhow::Result<Page> {
+        let mut request = self.client.get(format!("{}/api/journal?after={}", self.primary, after));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.with_context(|| format!("{} did not answer", self.primary))?;
+        let response = response.error_for_status().with_context(|| format!("{} refused to send its journal", self.primary))?;
+        response.json().await.with_context(|| format!("{} sent a journal this server cannot read", self.primary))
+    }
+
+    #[cfg(not(feature = "http"))]
+    async fn fetch(&self, _after: usize) -> anyhow::Result<Page> {
+        anyhow::bail!("{} needs the http feature, which this build leaves out", FLAG)
+    }
+}
@@ -0,0 +1,105 @@
+//! Choosing the store: SQLite with `--db path.sqlite` (or `--persist` for
+//! `cats.sqlite`), a JSON file with `--data-file path.json` (or
+//! `CAT_SERVER_DATA_FILE`), or PostgreSQL via `DATABASE_URL` when built with the
+//! `postgres` feature. The SQLite and PostgreSQL backends can also be switched
+//! off at runtime; see [`crate::features`]. Relative file paths are inside the
+//! data directory; see [`crate::config`]. The backends themselves are in
+//! [`cat_mcp_core::store`].
+//!
+//! With SQLite or PostgreSQL, tools read and write the database a row at a time through its
+//! repositories (see [`crate::records`]), so what they see is what is stored.
+//! With the JSON file, tools work on in-memory collections, which are written
+//! back in full after every successful mutation, or by [`crate::autosave`]
+//! every few seconds.
+//!
+//! Without any of these, the `[storage]` section of the configuration file
+//! picks the store (`backend = "sqlite"`, `"json"`, `"postgres"` or
+//! `"memory"`, with `path` or `url`).
+
+use crate::config::{self, Backend};
+use crate::features;
+use anyhow::Context;
+#[cfg(feature = "postgres")]
+use cat_mcp_core::store::PostgresStore;
+#[cfg(feature = "sqlite")]
+use cat_mcp_core::store::SqliteStore;
+pub use cat_mcp_core::store::{JsonFileStore, Store};
+
+/// The value of `<flag> <value>` or `<flag>=<value>` on the command line.
+pub(crate) fn flag_value(flag: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// The store chosen on the command line or environment, if any.
+pub async fn from_args() -> anyhow::Result<Option<Store>> {
+    let mut chosen = Vec::new();
+    if let Some(path) = flag_value("--db") {
+        chosen.push(sqlite(&path)?);
+    }
+    if std::env::args().skip(1).any(|arg| arg == "--persist") {
+        chosen.push(sqlite("cats.sqlite")?);
+    }
+    if let Some(path) = flag_value("--data-file").or_else(|| std::env::var("CAT_SERVER_DATA_FILE").ok()) {
+        chosen.push(Store::JsonFile(JsonFileStore::open(config::resolve(path))));
+    }
+    // DATABASE_URL is often set for other programs, so without PostgreSQL it is
+    // ignored rather than refused.
+    let database_url = std::env::var("DATABASE_URL").ok();
+    let postgres = features::Switches::from_config().enabled("postgres");
+    if database_url.is_some() && !postgres {
+        tracing::warn!("DATABASE_URL is set, but the postgres feature is not built in or is disabled; ignoring it");
+    }
+    #[cfg(feature = "postgres")]
+    if let Some(url) = database_url.filter(|_| postgres) {
+        chosen.push(Store::Postgres(PostgresStore::connect(&url).await?));
+    }
+    if chosen.len() > 1 {
+        anyhow::bail!("Only one of --db, --persist, --data-file and DATABASE_URL can be used at a time");
+    }
+    match chosen.pop() {
+        Some(store) => Ok(Some(store)),
+        None => from_config().await,
+    }
+}
+
+/// The store in the configuration file's `[storage]` section, used when
+/// none is chosen on the command line or environment.
+async fn from_config() -> anyhow::Result<Option<Store>> {
+    let storage = &config::file().storage;
+    Ok(match storage.backend {
+        None | Some(Backend::Memory) => None,
+        Some(Backend::Sqlite) => Some(sqlite(storage.path.as_deref().unwrap_or("cats.sqlite"))?),
+        Some(Backend::Json) => {
+            let path = storage.path.as_deref().context("[storage] path is required for json")?;
+            Some(Store::JsonFile(JsonFileStore::open(config::resolve(path))))
+        }
+        #[cfg(feature = "postgres")]
+        Some(Backend::Postgres) => {
+            features::Switches::from_config().require("postgres", "[storage] backend = \"postgres\"")?;
+            let url = storage.url.as_deref().context("[storage] url is required for postgres")?;
+            Some(Store::Postgres(PostgresStore::connect(url).await?))
+        }
+        #[cfg(not(feature = "postgres"))]
+        Some(Backend::Postgres) => anyhow::bail!("[storage] backend = \"postgres\" needs a build with the postgres feature"),
+    })
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite(path: &str) -> anyhow::Result<Store> {
+    features::Switches::from_config().require("sqlite", "SQLite storage")?;
+    Ok(Store::Sqlite(SqliteStore::open(&config::resolve(path))?))
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite(_path: &str) -> anyhow::Result<Store> {
+    anyhow::bail!("SQLite storage needs a build with the sqlite feature")
+}
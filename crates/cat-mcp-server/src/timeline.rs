@@ -0,0 +1,178 @@
+//! One chronological story per cat, served as the `cat://{id}/timeline` resource.
+//!
+//! Incidents and waitlist entries carry their own timestamps. Everything else
+//! (creation, field changes, the intake medical check) is recorded here as it
+//! happens. That history is kept in memory only, so cats that predate this
+//! server process start their timeline with the first recorded change.
+
+use crate::intake::MedicalCheck;
+use crate::waitlist::WaitlistEntry;
+use cat_mcp_core::Cat;
+use cat_mcp_core::incident::Incident;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+pub const URI_TEMPLATE: &str = "cat://{id}/timeline";
+
+pub fn uri(cat_id: u32) -> String {
+    format!("cat://{}/timeline", cat_id)
+}
+
+/// The cat ID in a timeline URI.
+pub fn parse_uri(uri: &str) -> Option<u32> {
+    uri.strip_prefix("cat://")?.strip_suffix("/timeline")?.parse().ok()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub at: NaiveDateTime,
+    /// `created`, `updated`, `deleted`, `restored`, `medical_check`, `incident`
+    /// or `waitlist`.
+    pub kind: &'static str,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+fn now() -> NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
+#[derive(Default)]
+pub struct History {
+    events: RwLock<HashMap<u32, Vec<Event>>>,
+}
+
+impl History {
+    async fn push(&self, cat_id: u32, event: Event) {
+        self.events.write().await.entry(cat_id).or_default().push(event);
+    }
+
+    /// `how` finishes "Added ...", e.g. "with add_cat".
+    pub async fn created(&self, cat: &Cat, how: &str) {
+        self.push(cat.id, Event {
+            at: now(),
+            kind: "created",
+            summary: format!("Added {}", how),
+            details: serde_json::to_value(cat).unwrap_or_default(),
+        })
+        .await;
+    }
+
+    /// Records the fields that differ between `before` and `after`, if any.
+    pub async fn changed(&self, before: &Cat, after: &Cat) {
+        let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(before), serde_json::to_value(after)) else {
+            return;
+        };
+        let changes: serde_json::Map<String, Value> = new
+            .iter()
+            .filter(|(field, value)| *field != "version" && old.get(*field) != Some(*value))
+            .map(|(field, value)| (field.clone(), serde_json::json!({ "from": old.get(field), "to": value })))
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+        let fields: Vec<&str> = changes.keys().map(String::as_str).collect();
+        let summary = format!("Changed {}", fields.join(", "));
+        self.push(after.id, Event {
+            at: now(),
+            kind: "updated",
+            summary,
+            details: Value::Object(changes),
+        })
+        .await;
+    }
+
+    pub async fn archived(&self, cat_id: u32) {
+        self.push(cat_id, Event {
+            at: now(),
+            kind: "deleted",
+            summary: "Deleted (archived)".to_string(),
+            details: Value::Null,
+        })
+        .await;
+    }
+
+    pub async fn restored(&self, cat_id: u32) {
+        self.push(cat_id, Event {
+            at: now(),
+            kind: "restored",
+            summary: "Restored from the archive".to_string(),
+            details: Value::Null,
+        })
+        .await;
+    }
+
+    pub async fn medical_check(&self, cat_id: u32, check: &MedicalCheck) {
+        let summary = if check.notes.is_empty() {
+            "Intake medical check".to_string()
+        } else {
+            format!("Intake medical check: {}", check.notes)
+        };
+        self.push(cat_id, Event {
+            at: now(),
+            kind: "medical_check",
+            summary,
+            details: serde_json::to_value(check).unwrap_or_default(),
+        })
+        .await;
+    }
+
+    pub async fn for_cat(&self, cat_id: u32) -> Vec<Event> {
+        self.events.read().await.get(&cat_id).cloned().unwrap_or_default()
+    }
+
+    /// How far each cat's history goes now, to return to with [`History::rollback`].
+    pub async fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.events.read().await.iter().map(|(id, events)| (*id, events.len())).collect())
+    }
+
+    /// Forgets everything recorded since `checkpoint`, for changes that were
+    /// rolled back.
+    pub async fn rollback(&self, checkpoint: &Checkpoint) {
+        let mut events = self.events.write().await;
+        events.retain(|id, _| checkpoint.0.contains_key(id));
+        for (id, events) in events.iter_mut() {
+            events.truncate(checkpoint.0[id]);
+        }
+    }
+}
+
+pub struct Checkpoint(HashMap<u32, usize>);
+
+/// Recorded history plus incidents and waitlist entries, oldest first.
+pub fn build(history: Vec<Event>, incidents: &[&Incident], waitlist: &[WaitlistEntry]) -> Vec<Event> {
+    let mut events = history;
+    events.extend(incidents.iter().map(|incident| {
+        let details = serde_json::to_value(incident).unwrap_or_default();
+        Event {
+            at: incident.datetime,
+            kind: "incident",
+            summary: format!("{} ({} severity): {}", incident.kind, details["severity"].as_str().unwrap_or_default(), incident.description),
+            details,
+        }
+    }));
+    // Contact details stay out: resources are not redacted like tool results.
+    events.extend(waitlist.iter().map(|entry| Event {
+        at: entry.joined_at,
+        kind: "waitlist",
+        summary: format!("{} joined the adoption waitlist", entry.adopter_name),
+        details: Value::Null,
+    }));
+    events.sort_by_key(|event| event.at);
+    events
+}
+
+pub fn markdown(cat: &Cat, events: &[Event]) -> String {
+    let mut text = format!("# {} (ID {})\n\n{}, {} years old, {}.\n\n", cat.name, cat.id, cat.breed, cat.age, cat.color);
+    if events.is_empty() {
+        text.push_str("Nothing has been recorded for this cat yet.\n");
+    }
+    for event in events {
+        text.push_str(&format!("- **{}** {}: {}\n", event.at.format("%Y-%m-%d %H:%M"), event.kind, event.summary));
+    }
+    text
+}
@@ -0,0 +1,179 @@
+//! Kana ⇄ romaji (Hepburn) conversion for cat names.
+
+const DIGRAPHS: &[(&str, &str)] = &[
+    ("キャ", "kya"), ("キュ", "kyu"), ("キョ", "kyo"),
+    ("シャ", "sha"), ("シュ", "shu"), ("ショ", "sho"), ("シェ", "she"),
+    ("チャ", "cha"), ("チュ", "chu"), ("チョ", "cho"), ("チェ", "che"),
+    ("ニャ", "nya"), ("ニュ", "nyu"), ("ニョ", "nyo"),
+    ("ヒャ", "hya"), ("ヒュ", "hyu"), ("ヒョ", "hyo"),
+    ("ミャ", "mya"), ("ミュ", "myu"), ("ミョ", "myo"),
+    ("リャ", "rya"), ("リュ", "ryu"), ("リョ", "ryo"),
+    ("ギャ", "gya"), ("ギュ", "gyu"), ("ギョ", "gyo"),
+    ("ジャ", "ja"), ("ジュ", "ju"), ("ジョ", "jo"), ("ジェ", "je"),
+    ("ビャ", "bya"), ("ビュ", "byu"), ("ビョ", "byo"),
+    ("ピャ", "pya"), ("ピュ", "pyu"), ("ピョ", "pyo"),
+    ("ティ", "ti"), ("ディ", "di"),
+    ("ファ", "fa"), ("フィ", "fi"), ("フェ", "fe"), ("フォ", "fo"),
+];
+
+const MONOGRAPHS: &[(char, &str)] = &[
+    ('ア', "a"), ('イ', "i"), ('ウ', "u"), ('エ', "e"), ('オ', "o"),
+    ('カ', "ka"), ('キ', "ki"), ('ク', "ku"), ('ケ', "ke"), ('コ', "ko"),
+    ('サ', "sa"), ('シ', "shi"), ('ス', "su"), ('セ', "se"), ('ソ', "so"),
+    ('タ', "ta"), ('チ', "chi"), ('ツ', "tsu"), ('テ', "te"), ('ト', "to"),
+    ('ナ', "na"), ('ニ', "ni"), ('ヌ', "nu"), ('ネ', "ne"), ('ノ', "no"),
+    ('ハ', "ha"), ('ヒ', "hi"), ('フ', "fu"), ('ヘ', "he"), ('ホ', "ho"),
+    ('マ', "ma"), ('ミ', "mi"), ('ム', "mu"), ('メ', "me"), ('モ', "mo"),
+    ('ヤ', "ya"), ('ユ', "yu"), ('ヨ', "yo"),
+    ('ラ', "ra"), ('リ', "ri"), ('ル', "ru"), ('レ', "re"), ('ロ', "ro"),
+    ('ワ', "wa"), ('ヲ', "wo"), ('ン', "n"),
+    ('ガ', "ga"), ('ギ', "gi"), ('グ', "gu"), ('ゲ', "ge"), ('ゴ', "go"),
+    ('ザ', "za"), ('ジ', "ji"), ('ズ', "zu"), ('ゼ', "ze"), ('ゾ', "zo"),
+    ('ダ', "da"), ('ヂ', "ji"), ('ヅ', "zu"), ('デ', "de"), ('ド', "do"),
+    ('バ', "ba"), ('ビ', "bi"), ('ブ', "bu"), ('ベ', "be"), ('ボ', "bo"),
+    ('パ', "pa"), ('ピ', "pi"), ('プ', "pu"), ('ペ', "pe"), ('ポ', "po"),
+    ('ヴ', "vu"),
+    ('ァ', "a"), ('ィ', "i"), ('ゥ', "u"), ('ェ', "e"), ('ォ', "o"),
+    ('ャ', "ya"), ('ュ', "yu"), ('ョ', "yo"),
+];
+
+/// Maps hiragana to the matching katakana; other characters are unchanged.
+pub fn hiragana_to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{3041}'..='\u{3096}' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+pub fn contains_kana(text: &str) -> bool {
+    text.chars().any(|c| matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}'))
+}
+
+/// Converts hiragana/katakana to Hepburn romaji. Non-kana characters pass through.
+pub fn to_romaji(text: &str) -> String {
+    let chars: Vec<char> = hiragana_to_katakana(text).chars().collect();
+    let mut romaji = String::new();
+    let mut double_next = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == 'ッ' {
+            double_next = true;
+            i += 1;
+            continue;
+        }
+        if c == 'ー' {
+            if let Some(vowel) = romaji.chars().last().filter(|v| "aeiou".contains(*v)) {
+                romaji.push(vowel);
+            }
+            i += 1;
+            continue;
+        }
+
+        let pair: String = chars[i..chars.len().min(i + 2)].iter().collect();
+        let (syllable, width) = match DIGRAPHS.iter().find(|(kana, _)| *kana == pair) {
+            Some((_, roman)) => (roman.to_string(), 2),
+            None => match MONOGRAPHS.iter().find(|(kana, _)| *kana == c) {
+                Some((_, roman)) => (roman.to_string(), 1),
+                None => (c.to_string(), 1),
+            },
+        };
+
+        if double_next {
+            if syllable.starts_with("ch") {
+                romaji.push('t');
+            } else if let Some(first) = syllable.chars().next().filter(|c| !"aeiou".contains(*c)) {
+                romaji.push(first);
+            }
+            double_next = false;
+        }
+        romaji.push_str(&syllable);
+        i += width;
+    }
+    romaji
+}
+
+/// Converts romaji to katakana. Unknown letters pass through unchanged.
+pub fn to_katakana(text: &str) -> String {
+    let lower = text.to_lowercase();
+    let chars: Vec<char> = lower.chars().collect();
+    let mut katakana = String::new();
+    let mut i = 0;
+
+    let lookup = |roman: &str| -> Option<String> {
+        DIGRAPHS
+            .iter()
+            .find(|(_, r)| *r == roman)
+            .map(|(kana, _)| kana.to_string())
+            .or_else(|| {
+                MONOGRAPHS
+                    .iter()
+                    .find(|(kana, r)| *r == roman && !matches!(*kana, 'ヂ' | 'ヅ' | 'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ャ' | 'ュ' | 'ョ'))
+                    .map(|(kana, _)| kana.to_string())
+            })
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        // Doubled consonant (or "tch") becomes a small tsu.
+        if c.is_ascii_alphabetic() && !"aeioun".contains(c) && (next == Some(c) || (c == 't' && next == Some('c'))) {
+            katakana.push('ッ');
+            i += 1;
+            continue;
+        }
+        // Syllabic n: before a consonant, at the end, or written "nn".
+        if c == 'n' && next.is_none_or(|n| !"aeiouy".contains(n)) {
+            katakana.push('ン');
+            let after = chars.get(i + 2).copied();
+            let written_nn = next == Some('n') && after.is_none_or(|a| !"aeiouy".contains(a));
+            i += if written_nn { 2 } else { 1 };
+            continue;
+        }
+
+        let matched = (1..=3).rev().find_map(|len| {
+            let end = i + len;
+            if end > chars.len() {
+                return None;
+            }
+            let roman: String = chars[i..end].iter().collect();
+            lookup(&roman).map(|kana| (kana, len))
+        });
+        match matched {
+            Some((kana, len)) => {
+                katakana.push_str(&kana);
+                i += len;
+            }
+            None => {
+                katakana.push(c);
+                i += 1;
+            }
+        }
+    }
+    katakana
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct NameForms {
+    pub input: String,
+    pub romaji: String,
+    pub katakana: String,
+}
+
+/// Gives both the romanized and katakana spelling of a name written in either script.
+pub fn name_forms(name: &str) -> NameForms {
+    let (romaji, katakana) = if contains_kana(name) {
+        (to_romaji(name), hiragana_to_katakana(name))
+    } else {
+        (name.to_lowercase(), to_katakana(name))
+    };
+    NameForms {
+        input: name.to_string(),
+        romaji,
+        katakana,
+    }
+}
@@ -0,0 +1,279 @@
+//! The transports clients can connect over, any number at once:
+//!
+//! - stdio, on unless `--no-stdio` is given;
+//! - HTTP with server-sent events, with `--http <addr>` (or
+//!   `CAT_SERVER_HTTP_ADDR`): clients open `GET /sse` and post to `/message`,
+//!   and people can browse the data at `/` (see [`crate::web`]);
+//! - a Unix socket, with `--unix-socket <path>` (or `CAT_SERVER_UNIX_SOCKET`),
+//!   speaking the same newline-delimited JSON as stdio.
+//!
+//! HTTP and the Unix socket are behind the `http` and `unix-socket` features
+//! (both on by default), which can also be switched off at runtime; see
+//! [`crate::features`]. Each can also be set in the `[transport]` section of
+//! the configuration file (`stdio = false`, `http = "..."`,
+//! `unix_socket = "..."`).
+//!
+//! Every connection gets its own [`Connection`] over the one shared
+//! [`CatServer`], so all clients see the same live data. Open connections are
+//! tracked in [`Connections`], which backs `list_sessions` and
+//! `disconnect_session`.
+
+use crate::CatServer;
+use crate::config;
+use crate::connection::Connection;
+use crate::features;
+use crate::notify::{Batch, Outbox};
+use crate::store::flag_value;
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use rmcp::service::{Peer, RunningService};
+#[cfg(feature = "http")]
+use rmcp::transport::sse_server::{SseServer, SseServerConfig};
+use rmcp::{RoleServer, ServiceExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+pub struct Transports {
+    pub stdio: bool,
+    pub http: Option<SocketAddr>,
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl Transports {
+    pub fn from_args() -> anyhow::Result<Self> {
+        let configured = &config::file().transport;
+        let http = match flag_value("--http")
+            .or_else(|| std::env::var("CAT_SERVER_HTTP_ADDR").ok())
+            .or_else(|| configured.http.clone())
+        {
+            Some(addr) => Some(addr.parse().with_context(|| format!("{} is not an address like 127.0.0.1:8000", addr))?),
+            None => None,
+        };
+        let unix_socket = flag_value("--unix-socket")
+            .or_else(|| std::env::var("CAT_SERVER_UNIX_SOCKET").ok())
+            .or_else(|| configured.unix_socket.clone())
+            .map(PathBuf::from);
+        let switches = features::Switches::from_config();
+        if http.is_some() {
+            switches.require("http", "HTTP")?;
+        }
+        if unix_socket.is_some() {
+            switches.require("unix-socket", "Unix sockets (which also need a Unix platform)")?;
+        }
+        let transports = Self {
+            stdio: !std::env::args().skip(1).any(|arg| arg == "--no-stdio") && configured.stdio.unwrap_or(true),
+            http,
+            unix_socket,
+        };
+        if !transports.stdio && transports.http.is_none() && transports.unix_socket.is_none() {
+            anyhow::bail!("Without stdio, --http or --unix-socket is needed, or there is nothing to serve");
+        }
+        Ok(transports)
+    }
+
+    /// Whether stdio is the only transport, in which case the server stops
+    /// when its client goes away.
+    pub fn only_stdio(&self) -> bool {
+        self.stdio && self.http.is_none() && self.unix_socket.is_none()
+    }
+}
+
+struct Open {
+    transport: &'static str,
+    peer: Peer<RoleServer>,
+    connected_since: NaiveDateTime,
+    calls: AtomicUsize,
+    cancel: CancellationToken,
+    outbox: Arc<Outbox>,
+}
+
+/// One open connection, as `list_sessions` shows it.
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub transport: &'static str,
+    /// Name and version the client gave when it connected.
+    pub client: Option<String>,
+    pub connected_since: NaiveDateTime,
+    pub calls: usize,
+}
+
+/// The connections currently open, on every transport.
+#[derive(Default)]
+pub struct Connections {
+    open: RwLock<HashMap<u64, Open>>,
+    next_id: AtomicU64,
+}
+
+impl Connections {
+    pub fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Keeps `service` listed until its client disconnects or `cancel` fires.
+    pub async fn run(&self, id: u64, transport: &'static str, service: RunningService<RoleServer, Connection>, cancel: CancellationToken) {
+        let (outbox, stop_notifying) = (Arc::new(Outbox::default()), cancel.child_token());
+        tokio::spawn({
+            let (outbox, peer, stop) = (outbox.clone(), service.peer().clone(), stop_notifying.clone());
+            async move { outbox.run(peer, stop).await }
+        });
+        self.open.write().await.insert(id, Open {
+            transport,
+            peer: service.peer().clone(),
+            connected_since: chrono::Local::now().naive_local(),
+            calls: AtomicUsize::new(0),
+            cancel,
+            outbox,
+        });
+        tracing::info!("🔌 Client connected over {} (session {})", transport, id);
+        if let Err(e) = service.waiting().await {
+            tracing::warn!("Session {} over {} ended with an error: {:?}", id, transport, e);
+        }
+        stop_notifying.cancel();
+        self.open.write().await.remove(&id);
+        tracing::info!("Session {} over {} closed", id, transport);
+    }
+
+    pub async fn count_call(&self, id: u64) {
+        if let Some(open) = self.open.read().await.get(&id) {
+            open.calls.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Every open connection, oldest first.
+    pub async fn list(&self) -> Vec<SessionInfo> {
+        let mut sessions: Vec<SessionInfo> = self
+            .open
+            .read()
+            .await
+            .iter()
+            .map(|(id, open)| SessionInfo {
+                id: *id,
+                transport: open.transport,
+                client: open.peer.peer_info().map(|info| format!("{} {}", info.client_info.name, info.client_info.version)),
+                connected_since: open.connected_since,
+                calls: open.calls.load(Ordering::Relaxed),
+            })
+            .collect();
+        sessions.sort_by_key(|session| session.id);
+        sessions
+    }
+
+    /// Closes a connection; its entry goes away once it has shut down.
+    /// Returns the transport it used, or `None` if there is no such session.
+    pub async fn disconnect(&self, id: u64) -> Option<&'static str> {
+        let sessions = self.open.read().await;
+        let open = sessions.get(&id)?;
+        open.cancel.cancel();
+        Some(open.transport)
+    }
+
+    /// Queues resource notifications for every open connection.
+    pub async fn broadcast(&self, batch: &Batch) {
+        for open in self.open.read().await.values() {
+            open.outbox.push(batch);
+        }
+    }
+
+    /// Sends `resources/updated` to session `id` when `uri` changes.
+    pub async fn subscribe(&self, id: u64, uri: &str) {
+        if let Some(open) = self.open.read().await.get(&id) {
+            open.outbox.subscribe(uri);
+        }
+    }
+
+    /// Whether session `id` was subscribed to `uri`.
+    pub async fn unsubscribe(&self, id: u64, uri: &str) -> bool {
+        self.open.read().await.get(&id).is_some_and(|open| open.outbox.unsubscribe(uri))
+    }
+
+    /// The peer of session `id`, if it is still open.
+    pub async fn peer(&self, id: u64) -> Option<Peer<RoleServer>> {
+        self.open.read().await.get(&id).map(|open| open.peer.clone())
+    }
+
+    /// The peer of every open connection, to notify on shutdown.
+    pub async fn peers(&self) -> Vec<Peer<RoleServer>> {
+        self.open.read().await.values().map(|open| open.peer.clone()).collect()
+    }
+}
+
+/// Starts listening for HTTP clients, and serves the web page and REST API
+/// beside them; connections are served until `shutdown`.
+#[cfg(feature = "http")]
+pub async fn serve_http(addr: SocketAddr, server: Arc<CatServer>, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let (mut sse, router) = SseServer::new(SseServerConfig {
+        bind: addr,
+        sse_path: "/sse".to_string(),
+        post_path: "/message".to_string(),
+        ct: shutdown.child_token(),
+        sse_keep_alive: None,
+    });
+    let router = router.merge(crate::web::router(server.clone())).merge(crate::rest::router(server.clone()));
+    let listener = tokio::net::TcpListener::bind(addr).await.with_context(|| format!("Could not listen on {}", addr))?;
+    let stop = shutdown.clone();
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).with_graceful_shutdown(stop.cancelled_owned()).await {
+            tracing::error!("HTTP server stopped: {}", e);
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            let transport = tokio::select! {
+                transport = sse.next_transport() => transport,
+                _ = shutdown.cancelled() => None,
+            };
+            let Some(transport) = transport else { break };
+            let (server, cancel) = (server.clone(), shutdown.child_token());
+            tokio::spawn(async move {
+                let connection = Connection::new(server.clone(), "http");
+                let id = connection.id;
+                match connection.serve_with_ct(transport, cancel.clone()).await {
+                    Ok(service) => server.connections.run(id, "http", service, cancel).await,
+                    Err(e) => tracing::warn!("HTTP client failed to initialize: {:?}", e),
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+/// Starts listening on a Unix socket; connections are served until `shutdown`,
+/// and the socket file is removed then.
+#[cfg(all(unix, feature = "unix-socket"))]
+pub async fn serve_unix_socket(path: PathBuf, server: Arc<CatServer>, shutdown: CancellationToken) -> anyhow::Result<()> {
+    let listener = tokio::net::UnixListener::bind(&path)
+        .with_context(|| format!("Could not listen on {} (if no server is running, delete the stale file)", path.display()))?;
+    tokio::spawn(async move {
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = shutdown.cancelled() => break,
+            };
+            let stream = match stream {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Could not accept a Unix socket client: {}", e);
+                    continue;
+                }
+            };
+            let (server, cancel) = (server.clone(), shutdown.child_token());
+            tokio::spawn(async move {
+                let connection = Connection::new(server.clone(), "unix");
+                let id = connection.id;
+                match connection.serve_with_ct(stream.into_split(), cancel.clone()).await {
+                    Ok(service) => server.connections.run(id, "unix", service, cancel).await,
+                    Err(e) => tracing::warn!("Unix socket client failed to initialize: {:?}", e),
+                }
+            });
+        }
+        let _ = std::fs::remove_file(&path);
+    });
+    Ok(())
+}
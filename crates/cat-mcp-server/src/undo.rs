@@ -0,0 +1,165 @@
+//! Per-connection undo and redo of cat and incident changes.
+//!
+//! An operation is the set of record changes one call made, as captured for the
+//! audit log. Undoing puts each record back to its `before` state and redoing
+//! to its `after` state, but only if every record is still exactly as that
+//! operation left it; otherwise someone changed it since and the undo or redo is
+//! refused rather than overwriting their work.
+
+use crate::audit::Change;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// Tools whose changes can be undone.
+pub const TOOLS: &[&str] = &[
+    "add_cat",
+    "update_cat",
+    "patch_cat",
+    "delete_cat",
+    "restore_cat",
+    "batch_update",
+    "import_cats_csv",
+    "log_incident",
+    "approve_change",
+];
+/// How many operations a connection can undo.
+const DEPTH: usize = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Operation {
+    pub tool: String,
+    pub at: NaiveDateTime,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Default)]
+pub struct UndoStack {
+    done: Vec<Operation>,
+    undone: Vec<Operation>,
+}
+
+impl UndoStack {
+    /// Records a new operation. Anything undone before it can no longer be redone.
+    pub fn push(&mut self, operation: Operation) {
+        if operation.changes.is_empty() {
+            return;
+        }
+        self.done.push(operation);
+        if self.done.len() > DEPTH {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+    }
+
+    pub fn last_done(&self) -> Option<&Operation> {
+        self.done.last()
+    }
+
+    pub fn last_undone(&self) -> Option<&Operation> {
+        self.undone.last()
+    }
+
+    /// Moves the last operation to the redo side, once it has been undone.
+    pub fn undid(&mut self) {
+        if let Some(operation) = self.done.pop() {
+            self.undone.push(operation);
+        }
+    }
+
+    /// Moves the last undone operation back, once it has been redone.
+    pub fn redid(&mut self) {
+        if let Some(operation) = self.undone.pop() {
+            self.done.push(operation);
+        }
+    }
+}
+
+/// Each change as `(record, id, expected current state, state to set)`.
+pub fn steps(changes: &[Change], undo: bool) -> impl Iterator<Item = (&str, u32, &serde_json::Value, &serde_json::Value)> {
+    changes.iter().map(move |change| match undo {
+        true => (change.record.as_str(), change.id, &change.after, &change.before),
+        false => (change.record.as_str(), change.id, &change.before, &change.after),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn operation(tool: &str, changes: Vec<Change>) -> Operation {
+        Operation {
+            tool: tool.to_string(),
+            at: chrono::NaiveDateTime::default(),
+            changes,
+        }
+    }
+
+    fn change(id: u32, before: Value, after: Value) -> Change {
+        Change {
+            record: "cat".to_string(),
+            id,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_move_operations_between_the_stacks() {
+        let mut stack = UndoStack::default();
+        stack.push(operation("add_cat", vec![change(1, Value::Null, json!({ "name": "Mochi" }))]));
+        stack.push(operation("update_cat", vec![change(1, json!({ "name": "Mochi" }), json!({ "name": "Tama" }))]));
+        assert_eq!(stack.last_done().map(|operation| operation.tool.as_str()), Some("update_cat"));
+
+        stack.undid();
+        assert_eq!(stack.last_done().map(|operation| operation.tool.as_str()), Some("add_cat"));
+        assert_eq!(stack.last_undone().map(|operation| operation.tool.as_str()), Some("update_cat"));
+
+        stack.redid();
+        assert_eq!(stack.last_done().map(|operation| operation.tool.as_str()), Some("update_cat"));
+        assert!(stack.last_undone().is_none());
+    }
+
+    #[test]
+    fn a_new_operation_clears_what_could_be_redone() {
+        let mut stack = UndoStack::default();
+        stack.push(operation("add_cat", vec![change(1, Value::Null, json!({}))]));
+        stack.undid();
+        stack.push(operation("add_cat", vec![change(2, Value::Null, json!({}))]));
+        assert!(stack.last_undone().is_none());
+    }
+
+    #[test]
+    fn operations_that_changed_nothing_are_not_kept() {
+        let mut stack = UndoStack::default();
+        stack.push(operation("add_cat", vec![change(1, Value::Null, json!({}))]));
+        stack.undid();
+        stack.push(operation("batch_update", vec![]));
+        assert!(stack.last_done().is_none());
+        assert!(stack.last_undone().is_some(), "an empty operation does not clear the redo side");
+    }
+
+    #[test]
+    fn only_the_latest_operations_are_kept() {
+        let mut stack = UndoStack::default();
+        for id in 0..DEPTH as u32 + 5 {
+            stack.push(operation("add_cat", vec![change(id, Value::Null, json!({}))]));
+        }
+        let mut undone = 0;
+        while stack.last_done().is_some() {
+            stack.undid();
+            undone += 1;
+        }
+        assert_eq!(undone, DEPTH);
+        assert_eq!(stack.last_undone().map(|operation| operation.changes[0].id), Some(5));
+    }
+
+    #[test]
+    fn steps_check_the_state_they_move_away_from() {
+        let changes = [change(1, json!("before"), json!("after"))];
+        let undo: Vec<_> = steps(&changes, true).collect();
+        assert_eq!(undo, [("cat", 1, &json!("after"), &json!("before"))]);
+        let redo: Vec<_> = steps(&changes, false).collect();
+        assert_eq!(redo, [("cat", 1, &json!("before"), &json!("after"))]);
+    }
+}
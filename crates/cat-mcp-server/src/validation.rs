@@ -0,0 +1,80 @@
+//! Field constraints for cat records, shared by every tool that writes cats.
+//!
+//! All violations are collected, so a client can fix everything in one retry.
+
+use cat_mcp_core::Cat;
+use rmcp::{
+    ErrorData,
+    model::ErrorCode,
+};
+use serde::Serialize;
+
+pub const MAX_AGE: u32 = 40;
+const MAX_NAME_LEN: usize = 100;
+/// Breed and color are free-form, within this length.
+const MAX_DESCRIPTOR_LEN: usize = 60;
+const MAX_ITEM_LEN: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub field: String,
+    pub problem: String,
+}
+
+impl Violation {
+    pub fn new(field: &str, problem: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            problem: problem.into(),
+        }
+    }
+}
+
+fn check_text(violations: &mut Vec<Violation>, field: &str, value: &str, max_len: usize, required: bool) {
+    let len = value.trim().chars().count();
+    if required && len == 0 {
+        violations.push(Violation::new(field, "must not be empty"));
+    } else if len > max_len {
+        violations.push(Violation::new(field, format!("must be at most {} characters (got {})", max_len, len)));
+    }
+}
+
+fn check_list(violations: &mut Vec<Violation>, field: &str, items: &[String]) {
+    for (index, item) in items.iter().enumerate() {
+        check_text(violations, &format!("{}[{}]", field, index), item, MAX_ITEM_LEN, true);
+    }
+}
+
+/// Every constraint `cat` breaks; empty when it is valid.
+pub fn check_cat(cat: &Cat) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check_text(&mut violations, "name", &cat.name, MAX_NAME_LEN, true);
+    if cat.age > MAX_AGE {
+        violations.push(Violation::new("age", format!("must be between 0 and {} (got {})", MAX_AGE, cat.age)));
+    }
+    check_text(&mut violations, "breed", &cat.breed, MAX_DESCRIPTOR_LEN, true);
+    check_text(&mut violations, "color", &cat.color, MAX_DESCRIPTOR_LEN, true);
+    check_text(&mut violations, "favorite_toy", &cat.favorite_toy, MAX_ITEM_LEN, false);
+    check_list(&mut violations, "name_variants", &cat.name_variants);
+    check_list(&mut violations, "allergies", &cat.allergies);
+    check_list(&mut violations, "prescribed_diets", &cat.prescribed_diets);
+    check_list(&mut violations, "temperament", &cat.temperament);
+    check_list(&mut violations, "photos", &cat.photos);
+    violations
+}
+
+/// INVALID_PARAMS naming every violation, with the list as `data`.
+pub fn error(violations: &[Violation]) -> ErrorData {
+    let details: Vec<String> = violations.iter().map(|v| format!("{} {}", v.field, v.problem)).collect();
+    ErrorData {
+        code: ErrorCode::INVALID_PARAMS,
+        message: format!("Invalid cat: {}", details.join("; ")).into(),
+        data: serde_json::to_value(violations).ok(),
+    }
+}
+
+/// `Ok` when `cat` is valid, otherwise the error listing every violation.
+pub fn validate(cat: &Cat) -> Result<(), ErrorData> {
+    let violations = check_cat(cat);
+    if violations.is_empty() { Ok(()) } else { Err(error(&violations)) }
+}
@@ -0,0 +1,53 @@
+//! Per-cat adoption waitlists. When a change makes a waitlisted cat available
+//! (restored from the archive, or added back), the sessions that queued for it
+//! are told with a log notification, read from the event bus like resource
+//! notifications.
+
+use crate::audit::Change;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitlistEntry {
+    pub adopter_name: String,
+    pub contact: String,
+    pub joined_at: NaiveDateTime,
+    /// The session that joined, to tell when the cat becomes available.
+    #[serde(default, skip_serializing)]
+    pub session: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Position<'a> {
+    pub position: usize,
+    #[serde(flatten)]
+    pub entry: &'a WaitlistEntry,
+}
+
+/// Numbers entries from 1 in queue order.
+pub fn positions(queue: &VecDeque<WaitlistEntry>) -> Vec<Position<'_>> {
+    queue
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| Position {
+            position: index + 1,
+            entry,
+        })
+        .collect()
+}
+
+/// A cat record that is live: present and not archived.
+fn is_live(state: &Value) -> bool {
+    state.is_object() && state.get("deleted_at").is_none()
+}
+
+/// Cats that `changes` made available: added, or restored from the archive.
+pub fn became_available(changes: &[Change]) -> Vec<u32> {
+    changes
+        .iter()
+        .filter(|change| change.record == "cat" && is_live(&change.after) && !is_live(&change.before))
+        .map(|change| change.id)
+        .collect()
+}
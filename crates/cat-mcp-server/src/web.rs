@@ -0,0 +1,84 @@
+//! A small read-only web page served next to the HTTP transport, so a person
+//! can check what agents have been doing without an MCP client:
+//!
+//! - `GET /`: the page, which reads `GET /cats` from [`crate::rest`] and the
+//!   endpoint below;
+//! - `GET /api/audit?limit=<n>`: the latest changes in the audit log (50 by
+//!   default).
+//!
+//! `GET /api/journal?after=<n>` serves the audit log after its first `n`
+//! entries, unmasked, to a standby following this server (see
+//! [`crate::standby`]).
+//!
+//! The audit log and journal need admin access, as `get_audit_log` does: they
+//! are open when `http` is an admin transport, and otherwise need
+//! `Authorization: Bearer <admin token>`. Everything else is masked like tool
+//! results.
+
+use crate::{CatServer, standby};
+use axum::Router;
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{Html, IntoResponse, Json, Response};
+use axum::routing::get;
+use rmcp::ErrorData;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+const PAGE: &str = include_str!("../web/index.html");
+const DEFAULT_AUDIT_LIMIT: usize = 50;
+
+pub fn router(server: Arc<CatServer>) -> Router {
+    Router::new()
+        .route("/", get(page))
+        .route("/api/audit", get(audit))
+        .route("/api/journal", get(journal))
+        .with_state(server)
+}
+
+/// The bearer token sent with a request, as `admin::Access::check` takes it.
+pub fn bearer_token(headers: &HeaderMap) -> Option<Value> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| Value::String(token.to_string()))
+}
+
+/// A refused or failed request, with the reason as `{"error": "..."}`.
+pub fn failure(status: StatusCode, error: ErrorData) -> Response {
+    (status, Json(serde_json::json!({ "error": error.message }))).into_response()
+}
+
+async fn page() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    limit: Option<usize>,
+}
+
+async fn audit(State(server): State<Arc<CatServer>>, headers: HeaderMap, Query(query): Query<AuditQuery>) -> Response {
+    if let Err(e) = server.admin.check("get_audit_log", "http", bearer_token(&headers).as_ref()) {
+        return failure(StatusCode::UNAUTHORIZED, e);
+    }
+    let mut entries = server.audit.query(None, None, None, None).await;
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+    entries.drain(..entries.len().saturating_sub(limit));
+    Json(server.redactor.apply_value(serde_json::json!(entries))).into_response()
+}
+
+#[derive(Deserialize)]
+struct JournalQuery {
+    after: Option<usize>,
+}
+
+async fn journal(State(server): State<Arc<CatServer>>, headers: HeaderMap, Query(query): Query<JournalQuery>) -> Response {
+    if let Err(e) = server.admin.check("get_audit_log", "http", bearer_token(&headers).as_ref()) {
+        return failure(StatusCode::UNAUTHORIZED, e);
+    }
+    let (length, entries) = server.audit.since(query.after.unwrap_or(0), standby::PAGE_SIZE).await;
+    Json(standby::Page { length, entries }).into_response()
+}
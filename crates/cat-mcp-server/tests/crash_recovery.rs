@@ -0,0 +1,197 @@
+//! Kills the server while it is writing and checks that the store it leaves
+//! behind still loads, with every change it acknowledged.
+//!
+//! Where the kill lands is random; the seed is printed, and
+//! `CRASH_RECOVERY_SEED=<seed>` repeats a run.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Cats added in one go, so the server is still writing when it is killed.
+const CATS: u32 = 20;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Server {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    /// Answers read while waiting for another one.
+    pending: VecDeque<Value>,
+    next_id: u64,
+}
+
+impl Server {
+    fn start(data_dir: &Path, store: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mcp-server-rust"))
+            .args(store)
+            .env("MCP_CAT_DATA_DIR", data_dir)
+            .env("RUST_LOG", "error")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("the server starts");
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut server = Self { child, stdin, lines, pending: VecDeque::new(), next_id: 0 };
+        server.request("initialize", json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "crash-recovery", "version": "0" },
+        }));
+        server.send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }));
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        writeln!(self.stdin, "{}", message).expect("the server reads its input");
+        self.stdin.flush().expect("the server reads its input");
+    }
+
+    /// Sends a request without waiting for the answer, returning its ID.
+    fn post(&mut self, method: &str, params: Value) -> u64 {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        id
+    }
+
+    /// The next answer to any request, skipping notifications.
+    fn next_answer(&mut self) -> Value {
+        if let Some(message) = self.pending.pop_front() {
+            return message;
+        }
+        loop {
+            let line = self.lines.recv_timeout(TIMEOUT).expect("the server answers");
+            let message: Value = serde_json::from_str(&line).expect("the server writes JSON-RPC");
+            if message.get("id").is_some() && message.get("method").is_none() {
+                return message;
+            }
+        }
+    }
+
+    /// The answer to request `id`; answers to other requests are kept for later.
+    fn answer(&mut self, id: u64) -> Value {
+        if let Some(index) = self.pending.iter().position(|message| message["id"] == json!(id)) {
+            return self.pending.remove(index).expect("the answer is pending");
+        }
+        let mut others = Vec::new();
+        let answer = loop {
+            let message = self.next_answer();
+            if message["id"] == json!(id) {
+                break message;
+            }
+            others.push(message);
+        };
+        self.pending.extend(others);
+        answer
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.post(method, params);
+        self.answer(id)
+    }
+
+    fn call(&mut self, tool: &str, arguments: Value) -> u64 {
+        self.post("tools/call", json!({ "name": tool, "arguments": arguments }))
+    }
+
+    fn kill(mut self) {
+        self.child.kill().expect("the server can be killed");
+        self.child.wait().expect("the server exits");
+    }
+}
+
+fn text(answer: &Value) -> String {
+    assert!(answer.get("error").is_none(), "the call failed: {}", answer);
+    assert_ne!(answer["result"]["isError"], json!(true), "the call failed: {}", answer);
+    answer["result"]["content"]
+        .as_array()
+        .expect("the result has content")
+        .iter()
+        .filter_map(|content| content["text"].as_str())
+        .collect()
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cat-server-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("the scratch directory can be created");
+    dir
+}
+
+fn seed() -> u64 {
+    let seed = match std::env::var("CRASH_RECOVERY_SEED") {
+        Ok(seed) => seed.parse().expect("CRASH_RECOVERY_SEED is a number"),
+        Err(_) => rand::random(),
+    };
+    println!("crash recovery seed: {}", seed);
+    seed
+}
+
+/// Starts adding [`CATS`] cats, kills the server after a random number of
+/// them are acknowledged while the rest are still being written, then
+/// restarts it on the same store.
+fn survives_a_kill_mid_write(name: &str, store: impl Fn(&Path) -> Vec<String>) {
+    let mut rng = StdRng::seed_from_u64(seed());
+    let dir = scratch_dir(name);
+    let args = store(&dir);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let mut server = Server::start(&dir, &args);
+    let names: Vec<String> = (1..=CATS).map(|n| format!("Crash {}", n)).collect();
+    let calls: Vec<u64> = names
+        .iter()
+        .map(|name| server.call("add_cat", json!({ "name": name, "age": 2, "breed": "Persian", "color": "White", "force": true })))
+        .collect();
+    let mut acknowledged = Vec::new();
+    for _ in 0..rng.gen_range(1..CATS) {
+        let answer = server.next_answer();
+        let index = calls.iter().position(|id| answer["id"] == json!(id)).expect("the answer is to an add_cat call");
+        let added = text(&answer);
+        assert!(added.contains(&format!("\"{}\"", names[index])), "{}", added);
+        acknowledged.push(&names[index]);
+    }
+    server.kill();
+
+    let mut server = Server::start(&dir, &args);
+    let id = server.call("list_all_cats", json!({}));
+    let listed = text(&server.answer(id));
+    for name in acknowledged {
+        assert!(listed.contains(&format!("\"{}\"", name)), "acknowledged cat {} was lost: {}", name, listed);
+    }
+    // Later cats may or may not have made it, but none is stored half-written.
+    let id = server.call("add_cat", json!({ "name": "After", "age": 1, "breed": "Siamese", "color": "Cream", "force": true }));
+    text(&server.answer(id));
+    server.kill();
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn the_json_store_survives_a_kill_mid_write() {
+    // Saving after every change, so each acknowledged cat is on disk.
+    survives_a_kill_mid_write("crash-json", |dir| {
+        vec!["--data-file".to_string(), dir.join("cats.json").display().to_string(), "--autosave-secs".to_string(), "0".to_string()]
+    });
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn the_sqlite_store_survives_a_kill_mid_write() {
+    survives_a_kill_mid_write("crash-sqlite", |dir| vec!["--db".to_string(), dir.join("cats.sqlite").display().to_string()]);
+}